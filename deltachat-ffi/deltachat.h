@@ -289,6 +289,43 @@ void*           dc_get_userdata              (dc_context_t* context);
 int             dc_open                      (dc_context_t* context, const char* dbfile, const char* blobdir);
 
 
+/**
+ * Open context database encrypted by a passphrase.
+ * Works as dc_open(), however, the database is encrypted using SQLCipher;
+ * if the database does not exist, it is created encrypted.
+ *
+ * This function is only available if deltachat-core is compiled with the `sqlcipher` feature,
+ * otherwise it always fails.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param dbfile The file to use to store the database.
+ * @param blobdir A directory to store the blobs in, see dc_open().
+ *     Note that the blobs are not encrypted.
+ * @param passphrase The passphrase to encrypt the database with.
+ * @return 1 on success, 0 on failure
+ *     eg. if the passphrase is wrong
+ *     or if there is already a database opened for the context.
+ */
+int             dc_open_with_passphrase      (dc_context_t* context, const char* dbfile, const char* blobdir, const char* passphrase);
+
+
+/**
+ * Change the passphrase of the opened database.
+ * The database is re-encrypted with the new passphrase,
+ * this may take a while for large databases.
+ * The function should not be called while the threads are running.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param passphrase The new passphrase.
+ *     Pass NULL or the empty string to remove the encryption.
+ * @return 1 on success, 0 on failure,
+ *     eg. if deltachat-core is not compiled with the `sqlcipher` feature.
+ */
+int             dc_change_db_passphrase      (dc_context_t* context, const char* passphrase);
+
+
 /**
  * Close context database opened by dc_open().
  * Before this, connections to SMTP and IMAP are closed; these connections
@@ -347,6 +384,9 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    It is planned for future versions
  *                    to send this image together with the next messages.
  * - `e2ee_enabled` = 0=no end-to-end-encryption, 1=prefer end-to-end-encryption (default)
+ * - `key_gen_type` = type of the key to generate, one of the @ref DC_KEY_GEN flags,
+ *                    defaults to #DC_KEY_GEN_DEFAULT (0), which is currently RSA 2048.
+ *                    Only affects newly generated keys, see also dc_rotate_self_key()
  * - `mdns_enabled` = 0=do not send or request read receipts,
  *                    1=send and request read receipts (default)
  * - `inbox_watch`  = 1=watch `INBOX`-folder for changes (default),
@@ -355,6 +395,16 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    0=do not watch the `Sent`-folder
  * - `mvbox_watch`  = 1=watch `DeltaChat`-folder for changes (default),
  *                    0=do not watch the `DeltaChat`-folder
+ * - `extra_watch_folders` = newline-separated list of additional
+ *                    folders to fetch messages from, eg. folders that are filled by
+ *                    server-side filters; the folders are polled about once a minute
+ *                    by dc_perform_extra_fetch() and dc_perform_extra_idle();
+ *                    folders already watched by another thread are skipped,
+ *                    default is no additional folders
+ * - `delete_server_after` = 0=do not delete messages from the server (default),
+ *                    >0=delete messages from the server after the given number of seconds;
+ *                    the messages are kept on the device.
+ *                    Autocrypt Setup Messages are never deleted this way.
  * - `mvbox_move`   = 1=heuristically detect chat-messages
  *                    and move them to the `DeltaChat`-folder,
  *                    0=do not move chat-messages
@@ -367,6 +417,13 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `save_mime_headers` = 1=save mime headers
  *                    and make dc_get_mime_headers() work for subsequent calls,
  *                    0=do not save mime headers (default)
+ * - `location_simplify_tolerance` = maximum distance in meters
+ *                    a streamed location may deviate from the sent track
+ *                    when locations are dropped to save space, default 10,
+ *                    0=send all locations
+ * - `location_stationary_radius` = locations within this radius in meters
+ *                    are considered stationary and are sent only when arriving
+ *                    and when leaving, default 5, 0=do not detect stationary locations
  *
  * If you want to retrieve a value, use dc_get_config().
  *
@@ -789,6 +846,55 @@ void            dc_perform_sentbox_idle      (dc_context_t* context);
 void            dc_interrupt_sentbox_idle    (dc_context_t* context);
 
 
+/**
+ * Fetch new messages from the folders set by the `extra_watch_folders` option.
+ * This function and dc_perform_extra_idle()
+ * must be called from the same thread, typically in a loop:
+ *
+ * ~~~
+ *     void* extra_thread_func(void* context)
+ *     {
+ *         while (true) {
+ *             dc_perform_extra_fetch(context);
+ *             dc_perform_extra_idle(context);
+ *         }
+ *     }
+ * ~~~
+ *
+ * If `extra_watch_folders` is not set, the function does nothing.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return None.
+ */
+void            dc_perform_extra_fetch       (dc_context_t* context);
+
+
+/**
+ * Wait until the folders set by the `extra_watch_folders` option
+ * should be fetched again.
+ * As IMAP NOTIFY is not supported,
+ * the folders are polled about once a minute instead of being idled on.
+ * This function and dc_perform_extra_fetch()
+ * must be called from the same thread, typically in a loop.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return None.
+ */
+void            dc_perform_extra_idle        (dc_context_t* context);
+
+
+/**
+ * Interrupt waiting in dc_perform_extra_idle().
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return None.
+ */
+void            dc_interrupt_extra_idle      (dc_context_t* context);
+
+
 /**
  * Execute pending smtp-jobs.
  * This function and dc_perform_smtp_idle() must be called from the same thread,
@@ -1386,6 +1492,27 @@ uint32_t        dc_get_next_media            (dc_context_t* context, uint32_t ms
 void            dc_set_chat_visibility       (dc_context_t* context, uint32_t chat_id, int visibility);
 
 
+/**
+ * Require encryption for all messages sent to a chat.
+ *
+ * If encryption is required, messages are never sent unencrypted.
+ * If the key of a member is unknown, the message fails instead,
+ * an info message names the members without key
+ * and #DC_EVENT_ENCRYPTION_REQUIRED_FAILED is emitted.
+ * Encryption can also be required for single contacts
+ * using dc_set_contact_encryption_required().
+ *
+ * Calling this function may result in the event #DC_EVENT_CHAT_MODIFIED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The ID of the chat.
+ * @param required 1=require encryption, 0=allow sending unencrypted messages
+ * @return 1=success, 0=error
+ */
+int             dc_set_chat_encryption_required (dc_context_t* context, uint32_t chat_id, int required);
+
+
 /**
  * Delete a chat.
  *
@@ -1567,6 +1694,25 @@ int             dc_remove_contact_from_chat  (dc_context_t* context, uint32_t ch
 int             dc_set_chat_name             (dc_context_t* context, uint32_t chat_id, const char* name);
 
 
+/**
+ * Set the description of a group chat.
+ *
+ * If the group is already _promoted_ (any message was sent to the group),
+ * all group members are informed by a special status message that is sent automatically by this function.
+ *
+ * Line breaks in the description are replaced by spaces.
+ *
+ * Sends out #DC_EVENT_CHAT_MODIFIED and #DC_EVENT_MSGS_CHANGED if a status message was sent.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to set the description for. Must be a group chat.
+ * @param description New description of the group. Pass an empty string to remove the description.
+ * @return 1=success, 0=error
+ */
+int             dc_set_chat_description      (dc_context_t* context, uint32_t chat_id, const char* description);
+
+
 /**
  * Set group profile image.
  *
@@ -1587,6 +1733,61 @@ int             dc_set_chat_name             (dc_context_t* context, uint32_t ch
 int             dc_set_chat_profile_image    (dc_context_t* context, uint32_t chat_id, const char* image);
 
 
+/**
+ * Promote a group member to group admin or demote a group admin.
+ *
+ * Groups without admins can be changed by all members.
+ * Once a group has admins, only admins can add or remove members,
+ * change the group name, the group image and the admins;
+ * changes by other members are ignored.
+ * Everyone can still leave the group.
+ *
+ * If the group has no admins yet, oneself is promoted to group admin as well.
+ * If the group is already promoted, all group members are informed about the change.
+ *
+ * Other members accept changes of the admins only from admins.
+ * The first admins of an unverified group are only accepted
+ * from the message creating the group,
+ * so they must be set before the group is promoted.
+ * Note that in unverified groups, the sender of a message is not authenticated,
+ * so admin roles do not protect against forged messages there.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID of the group.
+ * @param contact_id The contact ID of the group member to promote or demote.
+ * @param admin 1=promote to group admin, 0=demote.
+ * @return 1=success, 0=error
+ */
+int             dc_set_group_admin           (dc_context_t* context, uint32_t chat_id, uint32_t contact_id, int admin);
+
+
+/**
+ * Check if a contact is admin of a group.
+ * Note that groups without any admins can be changed by all members,
+ * use dc_get_group_admins() to check for this case.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID of the group.
+ * @param contact_id The contact ID to check.
+ * @return 1=contact is group admin, 0=contact is no group admin
+ */
+int             dc_is_group_admin            (dc_context_t* context, uint32_t chat_id, uint32_t contact_id);
+
+
+/**
+ * Get the contact IDs of the admins of a group.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID of the group.
+ * @return An array of contact IDs; empty if the group has no admins.
+ *     Must be freed using dc_array_unref() when done.
+ */
+dc_array_t*     dc_get_group_admins          (dc_context_t* context, uint32_t chat_id);
+
+
 
 /**
  * Set mute duration of a chat.
@@ -1791,6 +1992,7 @@ uint32_t        dc_create_contact            (dc_context_t* context, const char*
 
 #define         DC_GCL_VERIFIED_ONLY         0x01
 #define         DC_GCL_ADD_SELF              0x02
+#define         DC_GCL_SORT_BY_LAST_SEEN     0x04
 
 
 /**
@@ -1830,6 +2032,8 @@ int             dc_add_address_book          (dc_context_t* context, const char*
  *     - if the flag DC_GCL_ADD_SELF is set, SELF is added to the list unless filtered by other parameters
  *     - if the flag DC_GCL_VERIFIED_ONLY is set, only verified contacts are returned.
  *       if DC_GCL_VERIFIED_ONLY is not set, verified and unverified contacts are returned.
+ *     - if the flag DC_GCL_SORT_BY_LAST_SEEN is set, the contacts are sorted by recent activity,
+ *       see dc_contact_get_last_seen(); otherwise, they are sorted by name.
  * @param query A string to filter the list.  Typically used to implement an
  *     incremental search.  NULL for no filtering.
  * @return An array containing all contact IDs.  Must be dc_array_unref()'d
@@ -1872,6 +2076,166 @@ dc_array_t*     dc_get_blocked_contacts      (dc_context_t* context);
 void            dc_block_contact             (dc_context_t* context, uint32_t contact_id, int block);
 
 
+/**
+ * Add a further email address to a contact.
+ * Messages from this address are assigned to the contact,
+ * messages to the contact are still sent to the primary address
+ * returned by dc_contact_get_addr().
+ *
+ * If the address already belongs to another contact, this function fails;
+ * use dc_merge_contacts() in this case.
+ * May result in a #DC_EVENT_CONTACTS_CHANGED event.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_id The ID of the contact.
+ * @param addr The email address to add.
+ * @return 1=success, 0=error
+ */
+int             dc_add_contact_addr          (dc_context_t* context, uint32_t contact_id, const char* addr);
+
+
+/**
+ * Remove an email address added by dc_add_contact_addr() from a contact.
+ * The primary address cannot be removed.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_id The ID of the contact.
+ * @param addr The email address to remove.
+ * @return 1=success, 0=error
+ */
+int             dc_remove_contact_addr       (dc_context_t* context, uint32_t contact_id, const char* addr);
+
+
+/**
+ * Make an address added by dc_add_contact_addr() the primary address of the contact.
+ * The primary address is used for sending,
+ * the former primary address becomes a further address.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_id The ID of the contact.
+ * @param addr The new primary email address.
+ * @return 1=success, 0=error
+ */
+int             dc_set_contact_primary_addr  (dc_context_t* context, uint32_t contact_id, const char* addr);
+
+
+/**
+ * Get all email addresses of a contact as a JSON array of strings.
+ * The primary address comes first.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_id The ID of the contact.
+ * @return JSON array, on errors an empty string is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_contact_addrs_json    (dc_context_t* context, uint32_t contact_id);
+
+
+/**
+ * Merge two contacts belonging to the same person.
+ *
+ * All addresses of the other contact are added to the contact,
+ * messages and chat memberships are moved and the one-to-one chats are combined.
+ * Afterwards, the other contact is deleted.
+ * May result in #DC_EVENT_CONTACTS_CHANGED and #DC_EVENT_MSGS_CHANGED events.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_id The ID of the contact to keep.
+ * @param other_contact_id The ID of the contact to merge into contact_id.
+ * @return 1=success, 0=error
+ */
+int             dc_merge_contacts            (dc_context_t* context, uint32_t contact_id, uint32_t other_contact_id);
+
+
+/**
+ * Require encryption for all messages sent to a contact.
+ *
+ * If encryption is required, messages to chats the contact is member of
+ * are never sent unencrypted.
+ * If the key of the contact is unknown, the message fails instead,
+ * see dc_set_chat_encryption_required() for details.
+ *
+ * May result in a #DC_EVENT_CONTACTS_CHANGED event.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_id The ID of the contact.
+ * @param required 1=require encryption, 0=allow sending unencrypted messages
+ * @return 1=success, 0=error
+ */
+int             dc_set_contact_encryption_required (dc_context_t* context, uint32_t contact_id, int required);
+
+
+/**
+ * Add a rule that is applied to incoming messages before they are assigned to a chat.
+ * This can be used to block spammers, to drop newsletters
+ * or to accept or archive contact requests automatically.
+ *
+ * Rules are evaluated in the order they are added, the first matching rule wins.
+ * When a rule matches, #DC_EVENT_FILTER_RULE_MATCHED is emitted.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param kind What the rule is matched against, one of @ref DC_FILTER_KIND.
+ * @param header Name of the header to match for #DC_FILTER_KIND_HEADER,
+ *     NULL or an empty string otherwise.
+ * @param pattern Glob pattern for #DC_FILTER_KIND_ADDR and #DC_FILTER_KIND_LIST_ID,
+ *     domain for #DC_FILTER_KIND_DOMAIN,
+ *     regular expression for #DC_FILTER_KIND_SUBJECT and #DC_FILTER_KIND_HEADER.
+ *     Matching is case-insensitive.
+ * @param action What happens to matching messages, one of @ref DC_FILTER_ACTION.
+ * @return ID of the new rule, 0 on errors, eg. for an invalid pattern.
+ */
+uint32_t        dc_add_filter_rule           (dc_context_t* context, int kind, const char* header, const char* pattern, int action);
+
+
+/**
+ * Change a rule added by dc_add_filter_rule().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param rule_id ID of the rule to change.
+ * @param kind See dc_add_filter_rule().
+ * @param header See dc_add_filter_rule().
+ * @param pattern See dc_add_filter_rule().
+ * @param action See dc_add_filter_rule().
+ * @return 1=success, 0=error
+ */
+int             dc_update_filter_rule        (dc_context_t* context, uint32_t rule_id, int kind, const char* header, const char* pattern, int action);
+
+
+/**
+ * Delete a rule added by dc_add_filter_rule().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param rule_id ID of the rule to delete.
+ * @return 1=success, 0=error
+ */
+int             dc_delete_filter_rule        (dc_context_t* context, uint32_t rule_id);
+
+
+/**
+ * Get all filter rules as JSON.
+ *
+ * The JSON is an array of objects with the fields
+ * `id`, `kind`, `header`, `pattern` and `action`
+ * in the order the rules are evaluated;
+ * `kind` and `action` are given as names, eg. `"Domain"` or `"Block"`.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @return JSON array, on errors an empty string is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_filter_rules_json     (dc_context_t* context);
+
+
 /**
  * Get encryption info for a contact.
  * Get a multi-line encryption info, containing your fingerprint and the
@@ -1918,9 +2282,9 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
 // import/export and tools
 
 #define         DC_IMEX_EXPORT_SELF_KEYS      1 // param1 is a directory where the keys are written to
-#define         DC_IMEX_IMPORT_SELF_KEYS      2 // param1 is a directory where the keys are searched in and read from
-#define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to
-#define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import
+#define         DC_IMEX_IMPORT_SELF_KEYS      2 // param1 is a directory where the keys are searched in and read from, param2 is an optional passphrase
+#define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to, param2 is an optional passphrase
+#define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import, param2 is the passphrase for encrypted backups
 
 
 /**
@@ -1933,12 +2297,18 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
  * - **DC_IMEX_EXPORT_BACKUP** (11) - Export a backup to the directory given as `param1`.
  *   The backup contains all contacts, chats, images and other data and device independent settings.
  *   The backup does not contain device dependent settings as ringtones or LED notification settings.
- *   The name of the backup is typically `delta-chat.<day>.bak`, if more than one backup is create on a day,
- *   the format is `delta-chat.<day>-<number>.bak`
+ *   The backup is a tar archive with the name `delta-chat-<day>-<number>.tar`.
+ *   It is written to a temporary file first, so an existing backup file is always complete.
+ *   If the database is encrypted, see dc_open_with_passphrase(), the backup is encrypted with the same passphrase.
+ *   If a passphrase is given as `param2`, the whole backup is additionally encrypted with this passphrase.
  *
  * - **DC_IMEX_IMPORT_BACKUP** (12) - `param1` is the file (not: directory) to import. The file is normally
- *   created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
+ *   created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup();
+ *   backups in the older `.bak` format are supported as well. Importing a backup
  *   is only possible as long as the context is not configured or used in another way.
+ *   Encrypted backups can only be imported into a database opened with the same passphrase;
+ *   unencrypted backups are encrypted when imported into an encrypted database.
+ *   If dc_imex_is_backup_encrypted() returns 1, the passphrase used on export must be given as `param2`.
  *
  * - **DC_IMEX_EXPORT_SELF_KEYS** (1) - Export all private keys and all public keys of the user to the
  *   directory given as `param1`.  The default key is written to the files `public-key-default.asc`
@@ -1947,6 +2317,8 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
  *
  * - **DC_IMEX_IMPORT_SELF_KEYS** (2) - Import private keys found in the directory given as `param1`.
  *   The last imported key is made the default keys unless its name contains the string `legacy`.  Public keys are not imported.
+ *   Keys protected by a passphrase, eg. exported from GnuPG or Thunderbird, are unlocked using the passphrase given as `param2`.
+ *   A key is only imported if one of its user IDs matches the configured address.
  *
  * While dc_imex() returns immediately, the started job may take a while,
  * you can stop it using dc_stop_ongoing_process(). During execution of the job,
@@ -1999,7 +2371,11 @@ void            dc_imex                      (dc_context_t* context, int what, c
  *     char* file = NULL;
  *     if ((file=dc_imex_has_backup(context, dir))!=NULL && ask_user_whether_to_import())
  *     {
- *         dc_imex(context, DC_IMEX_IMPORT_BACKUP, file, NULL);
+ *         char* passphrase = NULL;
+ *         if (dc_imex_is_backup_encrypted(context, file)) {
+ *             passphrase = ask_user_for_backup_passphrase();
+ *         }
+ *         dc_imex(context, DC_IMEX_IMPORT_BACKUP, file, passphrase);
  *         // connect
  *     }
  *     else
@@ -2023,6 +2399,51 @@ void            dc_imex                      (dc_context_t* context, int what, c
 char*           dc_imex_has_backup           (dc_context_t* context, const char* dir);
 
 
+/**
+ * Check if a backup is encrypted by a passphrase.
+ * Such backups are created by DC_IMEX_EXPORT_BACKUP if a passphrase is given;
+ * to import them, the same passphrase has to be passed to DC_IMEX_IMPORT_BACKUP.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param file The backup file, eg. as returned by dc_imex_has_backup().
+ * @return 1=backup is encrypted and needs a passphrase,
+ *     0=backup is not encrypted or cannot be read.
+ */
+int             dc_imex_is_backup_encrypted  (dc_context_t* context, const char* file);
+
+
+/**
+ * Check a backup without importing it.
+ *
+ * The database of the backup is opened read-only and checked for integrity,
+ * moreover, it is checked that all files referenced by messages, chats and contacts
+ * are contained in the backup and that the self key can decrypt messages.
+ * This may take a while for large backups and should not be called from the UI thread.
+ *
+ * The result is a JSON object with the following fields:
+ * - `addr`: address of the account the backup belongs to
+ * - `backup_time`: time the backup was created, seconds since 1970
+ * - `size`: size of the backup file in bytes
+ * - `dbversion` and `dbversion_supported`: the database version of the backup
+ *   and whether it can be imported by this version of deltachat-core
+ * - `integrity_check`: `"ok"` if the database is intact
+ * - `msg_count` and `chat_count`: number of messages and chats in the backup
+ * - `missing_blobs`: array of referenced files missing in the backup
+ * - `keypair_ok`: true if the self key can decrypt messages
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param file The backup file, eg. as returned by dc_imex_has_backup().
+ * @param passphrase The passphrase for backups encrypted on export,
+ *     see dc_imex_is_backup_encrypted(), NULL otherwise.
+ * @return JSON object with the report; NULL if the backup cannot be read at all,
+ *     eg. on a wrong passphrase.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_imex_verify_backup_json   (dc_context_t* context, const char* file, const char* passphrase);
+
+
 /**
  * Initiate Autocrypt Setup Transfer.
  * Before starting the setup transfer with this function, the user should be asked:
@@ -2094,6 +2515,27 @@ char*           dc_initiate_key_transfer     (dc_context_t* context);
 int             dc_continue_key_transfer     (dc_context_t* context, uint32_t msg_id, const char* setup_code);
 
 
+/**
+ * Replace the own key by a newly generated one.
+ *
+ * The new key is sent to the contacts in the Autocrypt header of the next messages;
+ * the old keys are kept, so that messages encrypted to them can still be decrypted.
+ * Verified contacts have to verify the new key again.
+ * A device message informs about the new fingerprint
+ * and #DC_EVENT_SELF_KEY_CHANGED is emitted.
+ *
+ * As key generation may take some time, do not call this function from the UI thread.
+ * Other devices using the same account have to get the new key,
+ * eg. by dc_initiate_key_transfer().
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return The fingerprint of the new key, NULL on errors.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_rotate_self_key           (dc_context_t* context);
+
+
 /**
  * Signal an ongoing process to stop.
  *
@@ -2178,6 +2620,92 @@ dc_lot_t*       dc_check_qr                  (dc_context_t* context, const char*
 char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t chat_id);
 
 
+/**
+ * Get an invite link that offers the same Setup-Contact or Verified-Group invitation
+ * as the QR code returned by dc_get_securejoin_qr().
+ *
+ * The link has the form `https://i.delta.chat/#FINGERPRINT&a=ADDR&...`
+ * and can be shared by any other channel.
+ * All data are in the fragment of the link which is not sent to the web server.
+ *
+ * The receiving device passes the link to dc_check_qr() and dc_join_securejoin()
+ * exactly as a scanned QR code.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id If set to a group-chat-id,
+ *     the Verified-Group-Invite protocol is offered in the link,
+ *     if set to 0, the Setup-Contact protocol is offered.
+ * @return The invite link,
+ *     On errors, an empty string is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_securejoin_invite_url (dc_context_t* context, uint32_t chat_id);
+
+
+/**
+ * Create a new Setup-Contact or Verified-Group invitation with limits
+ * and get the QR code text offering it.
+ *
+ * Other than the invitation offered by dc_get_securejoin_qr(),
+ * the invitation cannot be used after the given time or number of joins.
+ * Joiners using an expired invitation get an error message.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id If set to a group-chat-id,
+ *     the Verified-Group-Invite protocol is offered in the QR code,
+ *     if set to 0, the Setup-Contact protocol is offered.
+ * @param expires Timestamp after which the invitation cannot be used anymore, 0 for no expiry.
+ * @param max_uses Maximal number of joins using the invitation, 0 for no limit.
+ * @return Text that should go to the QR code,
+ *     On errors, an empty QR code is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_create_securejoin_invite  (dc_context_t* context, uint32_t chat_id, int64_t expires, int max_uses);
+
+
+/**
+ * Get the active invitations of a chat as JSON.
+ *
+ * The JSON is an array of objects with the fields
+ * `chat_id`, `invitenumber`, `auth`, `timestamp`, `expires`, `max_uses` and `uses`;
+ * the newest invitation comes first.
+ * Invitations can be revoked by passing `invitenumber` to dc_revoke_securejoin_invite().
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The group-chat-id or 0 for Setup-Contact invitations.
+ * @return JSON array, on errors an empty string is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_securejoin_invites_json (dc_context_t* context, uint32_t chat_id);
+
+
+/**
+ * Revoke an invitation.
+ * Joiners still using the invitation get an error message.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param invitenumber The invitenumber of the invitation as returned by dc_get_securejoin_invites_json().
+ * @return 1=success, 0=error, eg. the invitation is not active.
+ */
+int             dc_revoke_securejoin_invite  (dc_context_t* context, const char* invitenumber);
+
+
+/**
+ * Revoke all invitations of a chat.
+ * The next call to dc_get_securejoin_qr() offers a new invitation.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The group-chat-id or 0 for Setup-Contact invitations.
+ * @return 1=success, 0=error
+ */
+int             dc_regenerate_securejoin_invites (dc_context_t* context, uint32_t chat_id);
+
+
 /**
  * Continue a Setup-Contact or Verified-Group-Invite protocol
  * started on another device with dc_get_securejoin_qr().
@@ -2192,13 +2720,23 @@ char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t ch
  * - If the given QR code starts the Setup-Contact protocol,
  *   the function typically returns immediately
  *   and the handshake runs in background.
- *   Subsequent calls of dc_join_securejoin() will abort unfinished tasks.
+ *   Subsequent calls of dc_join_securejoin() for the same inviter will abort unfinished tasks,
+ *   joins with different inviters run in parallel.
+ *   dc_stop_ongoing_process() does not affect these joins,
+ *   use dc_abort_securejoin() to abort them.
  *   The returned chat is the one-to-one opportunistic chat.
  *   When the protocol has finished, an info-message is added to that chat.
  * - If the given QR code starts the Verified-Group-Invite protocol,
  *   the function waits until the protocol has finished.
  *   This is because the verified group is not opportunistic
  *   and can be created only when the contacts have verified each other.
+ *   Only one Verified-Group-Invite can be waited for at the same time.
+ *   Calling dc_stop_ongoing_process() aborts the join.
+ *
+ * The state of the handshake is saved in the database;
+ * if the app is terminated during the handshake,
+ * the handshake continues when the next message of the inviter arrives
+ * and the group is created in background.
  *
  * See https://countermitm.readthedocs.io/en/latest/new.html
  * for details about both protocols.
@@ -2216,6 +2754,63 @@ char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t ch
 uint32_t        dc_join_securejoin           (dc_context_t* context, const char* qr);
 
 
+/**
+ * Abort a Setup-Contact or Verified-Group-Invite protocol
+ * started with dc_join_securejoin().
+ * The saved state of the handshake is deleted,
+ * later messages of the inviter belonging to the handshake are ignored.
+ * If dc_join_securejoin() waits for a Verified-Group-Invite to finish,
+ * it returns 0.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id The ID of the inviter,
+ *     this is the contact ID returned by dc_check_qr() in lot.id.
+ * @return 1=a handshake was aborted, 0=there was no handshake with the inviter.
+ */
+int             dc_abort_securejoin          (dc_context_t* context, uint32_t contact_id);
+
+
+/**
+ * Get the safety number of the own key and the key of a contact.
+ *
+ * The safety number is the same on both sides,
+ * so it can be compared out-of-band, eg. by reading it out in a phone call,
+ * if scanning a QR code is not possible.
+ * If the user confirms that the numbers match,
+ * the UI should call dc_confirm_safety_number() to verify the contact.
+ *
+ * The safety number consists of 12 blocks of 5 digits, separated by spaces and newlines.
+ * It is also shown by dc_get_contact_encrinfo().
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id The ID of the contact.
+ * @return The safety number, NULL if no key of the contact is known.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_safety_number         (dc_context_t* context, uint32_t contact_id);
+
+
+/**
+ * Verify a contact after the user confirmed that the safety numbers match,
+ * see dc_get_safety_number().
+ *
+ * The contact is only verified if the given safety number is still the current one,
+ * ie. the keys were not changed in between.
+ * If there is a one-to-one chat with the contact, it becomes verified.
+ * On success, #DC_EVENT_CONTACTS_CHANGED is emitted.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param contact_id The ID of the contact.
+ * @param safety_number The safety number as returned by dc_get_safety_number(),
+ *     spaces and newlines are ignored.
+ * @return 1=contact verified, 0=safety number does not match or other error
+ */
+int             dc_confirm_safety_number     (dc_context_t* context, uint32_t contact_id, const char* safety_number);
+
+
 // location streaming
 
 
@@ -2794,6 +3389,19 @@ int             dc_chat_get_type             (const dc_chat_t* chat);
 char*           dc_chat_get_name             (const dc_chat_t* chat);
 
 
+/**
+ * Get the description of a group chat.
+ *
+ * To change the description, use dc_set_chat_description()
+ *
+ * @memberof dc_chat_t
+ * @param chat The chat object.
+ * @return Description as a string, empty string if the chat has no description.
+ *     Must be released using dc_str_unref() after usage. Never NULL.
+ */
+char*           dc_chat_get_description      (const dc_chat_t* chat);
+
+
 /*
  * Get a subtitle for a chat.  The subtitle is eg. the email-address or the
  * number of group members.
@@ -2918,6 +3526,9 @@ int             dc_chat_can_send              (const dc_chat_t* chat);
  * Check if a chat is verified.  Verified chats contain only verified members
  * and encryption is alwasy enabled.  Verified chats are created using
  * dc_create_group_chat() by setting the 'verified' parameter to true.
+ * One-to-one chats become verified after a successful Setup-Contact protocol,
+ * see dc_join_securejoin();
+ * they lose the verification if the key of the contact changes.
  *
  * @memberof dc_chat_t
  * @param chat The chat object.
@@ -2926,6 +3537,19 @@ int             dc_chat_can_send              (const dc_chat_t* chat);
 int             dc_chat_is_verified          (const dc_chat_t* chat);
 
 
+/**
+ * Check if encryption is required for messages sent to the chat,
+ * see dc_set_chat_encryption_required().
+ * Encryption may also be required by members of the chat,
+ * see dc_contact_is_encryption_required().
+ *
+ * @memberof dc_chat_t
+ * @param chat The chat object.
+ * @return 1=encryption is required, 0=encryption is not required by the chat
+ */
+int             dc_chat_is_encryption_required (const dc_chat_t* chat);
+
+
 /**
  * Check if locations are sent to the chat
  * at the time the object was created using dc_get_chat().
@@ -3352,6 +3976,33 @@ int             dc_msg_has_deviating_timestamp(const dc_msg_t* msg);
 int             dc_msg_has_location           (const dc_msg_t* msg);
 
 
+/**
+ * Get the name of the point of interest bound to a message.
+ * Points of interest are set using dc_msg_set_poi().
+ * The position of the point of interest is returned by dc_get_locations().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Name of the point of interest.
+ *     NULL if the message does not share a point of interest.
+ *     The returned value must be released using dc_str_unref() after usage.
+ */
+char*           dc_msg_get_poi_label          (const dc_msg_t* msg);
+
+
+/**
+ * Get the description, typically the address,
+ * of the point of interest bound to a message.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Description of the point of interest.
+ *     NULL if the message does not share a point of interest or it has no description.
+ *     The returned value must be released using dc_str_unref() after usage.
+ */
+char*           dc_msg_get_poi_description    (const dc_msg_t* msg);
+
+
 /**
  * Check if a message was sent successfully.
  *
@@ -3542,6 +4193,24 @@ void            dc_msg_set_duration           (dc_msg_t* msg, int duration);
 void            dc_msg_set_location           (dc_msg_t* msg, double latitude, double longitude);
 
 
+/**
+ * Set a point of interest, a named place, that should be bound to the message object.
+ * Works as dc_msg_set_location(), but the location is sent together
+ * with a name and an optional description, typically the address.
+ * For recipients not using Delta Chat,
+ * a `geo:` URI is added to the text of the message.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @param latitude North-south position of the place.
+ * @param longitude East-west position of the place.
+ * @param label Name of the place.
+ * @param description Description of the place, typically the address. May be NULL.
+ * @return None.
+ */
+void            dc_msg_set_poi                (dc_msg_t* msg, double latitude, double longitude, const char* label, const char* description);
+
+
 /**
  * Late filing information to a message.
  * In contrast to the dc_msg_set_*() functions, this function really stores the information in the database.
@@ -3740,6 +4409,30 @@ int             dc_contact_is_blocked        (const dc_contact_t* contact);
 int             dc_contact_is_verified       (dc_contact_t* contact);
 
 
+/**
+ * Check if encryption is required for messages sent to the contact,
+ * see dc_set_contact_encryption_required().
+ *
+ * @memberof dc_contact_t
+ * @param contact The contact object.
+ * @return 1=encryption is required, 0=encryption is not required
+ */
+int             dc_contact_is_encryption_required (dc_contact_t* contact);
+
+
+/**
+ * Get the time of the last message received from the contact.
+ * Hidden messages as read receipts are also taken into account,
+ * so this can be used to tell whether a contact is still active.
+ *
+ * @memberof dc_contact_t
+ * @param contact The contact object.
+ * @return Timestamp of the last message received from the contact,
+ *     0 if no message was received from the contact yet.
+ */
+int64_t         dc_contact_get_last_seen     (dc_contact_t* contact);
+
+
 /**
  * @class dc_provider_t
  *
@@ -4501,6 +5194,41 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
 #define DC_EVENT_SECUREJOIN_MEMBER_ADDED 2062
 
 
+/**
+ * A filter rule matched an incoming message, see dc_add_filter_rule().
+ *
+ * @param data1 (int) ID of the rule.
+ * @param data2 (int) ID of the contact that sent the message.
+ * @return 0
+ */
+#define DC_EVENT_FILTER_RULE_MATCHED 2070
+
+
+/**
+ * The own key was replaced by dc_rotate_self_key().
+ *
+ * @param data1 0
+ * @param data2 0
+ * @return 0
+ */
+#define DC_EVENT_SELF_KEY_CHANGED 2071
+
+
+/**
+ * A message was not sent because encryption is required for the chat or its members,
+ * but no key is known for some of the recipients,
+ * see dc_set_chat_encryption_required() and dc_set_contact_encryption_required().
+ *
+ * The message is marked as failed (#DC_EVENT_MSG_FAILED is emitted as well)
+ * and an info message naming the recipients without key is added to the chat.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) msg_id
+ * @return 0
+ */
+#define DC_EVENT_ENCRYPTION_REQUIRED_FAILED 2072
+
+
 /**
  * @}
  */
@@ -4530,6 +5258,38 @@ void            dc_array_add_id              (dc_array_t*, uint32_t); // depreca
 #define DC_SHOW_EMAILS_ALL               2
 
 
+/**
+ * @defgroup DC_KEY_GEN DC_KEY_GEN
+ *
+ * These constants are used as values for dc_get_config("key_gen_type")
+ * and dc_set_config("key_gen_type").
+ *
+ * @addtogroup DC_KEY_GEN
+ * @{
+ */
+
+/**
+ * Use the default key type, currently RSA 2048.
+ */
+#define DC_KEY_GEN_DEFAULT 0
+
+/**
+ * Generate RSA 2048 keys.
+ */
+#define DC_KEY_GEN_RSA2048 1
+
+/**
+ * Generate Ed25519 keys for signing with a Curve25519 subkey for encryption.
+ * Keys are generated much faster and are much smaller than RSA keys,
+ * however, older clients may not be able to use them.
+ */
+#define DC_KEY_GEN_ED25519 2
+
+/**
+ * @}
+ */
+
+
 /**
  * @defgroup DC_PROVIDER_STATUS DC_PROVIDER_STATUS
  *
@@ -4627,6 +5387,84 @@ void            dc_array_add_id              (dc_array_t*, uint32_t); // depreca
  */
 
 
+/**
+ * @defgroup DC_FILTER_KIND DC_FILTER_KIND
+ *
+ * These constants describe what a filter rule is matched against,
+ * see dc_add_filter_rule().
+ *
+ * @addtogroup DC_FILTER_KIND
+ * @{
+ */
+
+/**
+ * The sender address matches a glob pattern, eg. `*@news.example.org`.
+ * `*` matches any number of characters, `?` matches a single character.
+ */
+#define         DC_FILTER_KIND_ADDR            1
+
+/**
+ * The sender address belongs to the given domain or one of its subdomains.
+ */
+#define         DC_FILTER_KIND_DOMAIN          2
+
+/**
+ * The subject matches a regular expression.
+ */
+#define         DC_FILTER_KIND_SUBJECT         3
+
+/**
+ * The header given to dc_add_filter_rule() matches a regular expression.
+ */
+#define         DC_FILTER_KIND_HEADER          4
+
+/**
+ * The List-Id of a mailing list message matches a glob pattern,
+ * eg. `*.lists.example.org`.
+ */
+#define         DC_FILTER_KIND_LIST_ID         5
+
+/**
+ * @}
+ */
+
+
+/**
+ * @defgroup DC_FILTER_ACTION DC_FILTER_ACTION
+ *
+ * These constants describe what happens to messages matching a filter rule,
+ * see dc_add_filter_rule().
+ *
+ * @addtogroup DC_FILTER_ACTION
+ * @{
+ */
+
+/**
+ * The sender is blocked and the message is dropped.
+ */
+#define         DC_FILTER_ACTION_BLOCK         1
+
+/**
+ * The message is dropped.
+ */
+#define         DC_FILTER_ACTION_TRASH         2
+
+/**
+ * The message is shown in a normal chat instead of the deaddrop,
+ * even for classic emails that are not shown otherwise.
+ */
+#define         DC_FILTER_ACTION_ACCEPT        3
+
+/**
+ * Like #DC_FILTER_ACTION_ACCEPT, but the chat is archived.
+ */
+#define         DC_FILTER_ACTION_ARCHIVE       4
+
+/**
+ * @}
+ */
+
+
 /*
  * TODO: Strings need some doumentation about used placeholders.
  *
@@ -4683,6 +5521,15 @@ void            dc_array_add_id              (dc_array_t*, uint32_t); // depreca
 #define DC_STR_LOCATION                   66
 #define DC_STR_STICKER                    67
 #define DC_STR_DEVICE_MESSAGES            68
+#define DC_STR_MSGGRPADMINADDED           73
+#define DC_STR_MSGGRPADMINREMOVED         74
+#define DC_STR_MSGGRPDESCRIPTIONCHANGED   75
+#define DC_STR_SECUREJOININVITEINVALID    76
+#define DC_STR_CHATVERIFICATIONLOST       77
+#define DC_STR_LASTSEEN                   78
+#define DC_STR_SELFKEYCHANGED             79
+#define DC_STR_ENCRYPTIONREQUIREDNOKEY    80
+#define DC_STR_SAFETYNUMBER               81
 #define DC_STR_COUNT                      68
 
 /*
@@ -159,6 +159,7 @@ impl ContextWrapper {
                 | Event::IncomingMsg { chat_id, msg_id }
                 | Event::MsgDelivered { chat_id, msg_id }
                 | Event::MsgFailed { chat_id, msg_id }
+                | Event::EncryptionRequiredFailed { chat_id, msg_id }
                 | Event::MsgRead { chat_id, msg_id } => {
                     ffi_cb(
                         self,
@@ -207,6 +208,20 @@ impl ContextWrapper {
                         contact_id as uintptr_t,
                     );
                 }
+                Event::FilterRuleMatched {
+                    rule_id,
+                    contact_id,
+                } => {
+                    ffi_cb(
+                        self,
+                        event_id,
+                        rule_id as uintptr_t,
+                        contact_id as uintptr_t,
+                    );
+                }
+                Event::SelfKeyChanged => {
+                    ffi_cb(self, event_id, 0, 0);
+                }
             }
         }
     }
@@ -267,21 +282,50 @@ pub unsafe extern "C" fn dc_open(
         eprintln!("ignoring careless call to dc_open()");
         return 0;
     }
-    let ffi_context = &*context;
+    open_context(&*context, dbfile, blobdir, None)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_open_with_passphrase(
+    context: *mut dc_context_t,
+    dbfile: *const libc::c_char,
+    blobdir: *const libc::c_char,
+    passphrase: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || dbfile.is_null() || passphrase.is_null() {
+        eprintln!("ignoring careless call to dc_open_with_passphrase()");
+        return 0;
+    }
+    open_context(
+        &*context,
+        dbfile,
+        blobdir,
+        Some(to_string_lossy(passphrase)),
+    )
+}
+
+unsafe fn open_context(
+    ffi_context: &'static ContextWrapper,
+    dbfile: *const libc::c_char,
+    blobdir: *const libc::c_char,
+    passphrase: Option<String>,
+) -> libc::c_int {
     let rust_cb = move |_ctx: &Context, evt: Event| ffi_context.translate_cb(evt);
 
     let ctx = if blobdir.is_null() || *blobdir == 0 {
-        Context::new(
+        Context::new_with_passphrase(
             Box::new(rust_cb),
             ffi_context.os_name.clone(),
             as_path(dbfile).to_path_buf(),
+            passphrase,
         )
     } else {
-        Context::with_blobdir(
+        Context::with_blobdir_and_passphrase(
             Box::new(rust_cb),
             ffi_context.os_name.clone(),
             as_path(dbfile).to_path_buf(),
             as_path(blobdir).to_path_buf(),
+            passphrase,
         )
     };
     match ctx {
@@ -294,6 +338,26 @@ pub unsafe extern "C" fn dc_open(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_change_db_passphrase(
+    context: *mut dc_context_t,
+    passphrase: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_change_db_passphrase()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    let passphrase = to_opt_string_lossy(passphrase).filter(|p| !p.is_empty());
+    ffi_context
+        .with_inner(|ctx| {
+            ctx.change_db_passphrase(passphrase)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to change database passphrase")
+        })
+        .unwrap_or(0)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_close(context: *mut dc_context_t) {
     if context.is_null() {
@@ -650,6 +714,42 @@ pub unsafe extern "C" fn dc_interrupt_sentbox_idle(context: *mut dc_context_t) {
         .unwrap_or(())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_perform_extra_fetch(context: *mut dc_context_t) {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_perform_extra_fetch()");
+        return;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| job::perform_extra_fetch(ctx))
+        .unwrap_or(())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_perform_extra_idle(context: *mut dc_context_t) {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_perform_extra_idle()");
+        return;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| job::perform_extra_idle(ctx))
+        .unwrap_or(())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_interrupt_extra_idle(context: *mut dc_context_t) {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_interrupt_extra_idle()");
+        return;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| job::interrupt_extra_idle(ctx))
+        .unwrap_or(())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_perform_smtp_jobs(context: *mut dc_context_t) {
     if context.is_null() {
@@ -1219,6 +1319,26 @@ pub unsafe extern "C" fn dc_set_chat_visibility(
         .unwrap_or(())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_encryption_required(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    required: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_chat_encryption_required()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::set_encryption_required(ctx, ChatId::new(chat_id), required != 0)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set encryption requirement of chat")
+        })
+        .unwrap_or(0)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_delete_chat(context: *mut dc_context_t, chat_id: u32) {
     if context.is_null() {
@@ -1399,6 +1519,86 @@ pub unsafe extern "C" fn dc_set_chat_name(
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_description(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    description: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null()
+        || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL as u32
+        || description.is_null()
+    {
+        eprintln!("ignoring careless call to dc_set_chat_description()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::set_chat_description(ctx, ChatId::new(chat_id), to_string_lossy(description))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set chat description")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_group_admin(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    contact_id: u32,
+    admin: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL as u32 {
+        eprintln!("ignoring careless call to dc_set_group_admin()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::set_group_admin(ctx, ChatId::new(chat_id), contact_id, admin != 0)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set group admin")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_is_group_admin(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_is_group_admin()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::is_group_admin(ctx, ChatId::new(chat_id), contact_id) as libc::c_int
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_group_admins(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_group_admins()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let arr = dc_array_t::from(chat::get_group_admins(ctx, ChatId::new(chat_id)));
+            Box::into_raw(Box::new(arr))
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_set_chat_profile_image(
     context: *mut dc_context_t,
@@ -1755,138 +1955,431 @@ pub unsafe extern "C" fn dc_block_contact(
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_get_contact_encrinfo(
+pub unsafe extern "C" fn dc_add_contact_addr(
     context: *mut dc_context_t,
     contact_id: u32,
-) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_contact_encrinfo()");
-        return "".strdup();
+    addr: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || addr.is_null() {
+        eprintln!("ignoring careless call to dc_add_contact_addr()");
+        return 0;
     }
     let ffi_context = &*context;
     ffi_context
         .with_inner(|ctx| {
-            Contact::get_encrinfo(ctx, contact_id)
-                .map(|s| s.strdup())
-                .unwrap_or_else(|e| {
-                    error!(ctx, "{}", e);
-                    ptr::null_mut()
-                })
+            Contact::add_addr(ctx, contact_id, to_string_lossy(addr))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to add contact address")
         })
-        .unwrap_or_else(|_| "".strdup())
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_delete_contact(
+pub unsafe extern "C" fn dc_remove_contact_addr(
     context: *mut dc_context_t,
     contact_id: u32,
+    addr: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || contact_id <= constants::DC_CONTACT_ID_LAST_SPECIAL as u32 {
-        eprintln!("ignoring careless call to dc_delete_contact()");
+    if context.is_null() || addr.is_null() {
+        eprintln!("ignoring careless call to dc_remove_contact_addr()");
         return 0;
     }
     let ffi_context = &*context;
     ffi_context
-        .with_inner(|ctx| match Contact::delete(ctx, contact_id) {
-            Ok(_) => 1,
-            Err(_) => 0,
+        .with_inner(|ctx| {
+            Contact::remove_addr(ctx, contact_id, to_string_lossy(addr))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to remove contact address")
         })
         .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_get_contact(
+pub unsafe extern "C" fn dc_set_contact_primary_addr(
     context: *mut dc_context_t,
     contact_id: u32,
-) -> *mut dc_contact_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_contact()");
-        return ptr::null_mut();
+    addr: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || addr.is_null() {
+        eprintln!("ignoring careless call to dc_set_contact_primary_addr()");
+        return 0;
     }
     let ffi_context = &*context;
     ffi_context
         .with_inner(|ctx| {
-            Contact::get_by_id(ctx, contact_id)
-                .map(|contact| Box::into_raw(Box::new(ContactWrapper { context, contact })))
-                .unwrap_or_else(|_| ptr::null_mut())
+            Contact::set_primary_addr(ctx, contact_id, to_string_lossy(addr))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set primary contact address")
         })
-        .unwrap_or_else(|_| ptr::null_mut())
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_imex(
+pub unsafe extern "C" fn dc_get_contact_addrs_json(
     context: *mut dc_context_t,
-    what: libc::c_int,
-    param1: *const libc::c_char,
-    _param2: *const libc::c_char,
-) {
+    contact_id: u32,
+) -> *mut libc::c_char {
     if context.is_null() {
-        eprintln!("ignoring careless call to dc_imex()");
-        return;
+        eprintln!("ignoring careless call to dc_get_contact_addrs_json()");
+        return "".strdup();
     }
-    let what = match imex::ImexMode::from_i32(what as i32) {
-        Some(what) => what,
-        None => {
-            eprintln!("ignoring invalid argument {} to dc_imex", what);
-            return;
-        }
-    };
-
     let ffi_context = &*context;
     ffi_context
-        .with_inner(|ctx| imex::imex(ctx, what, to_opt_string_lossy(param1)))
-        .ok();
+        .with_inner(|ctx| {
+            let addrs = Contact::get_addrs(ctx, contact_id)
+                .unwrap_or_log_default(ctx, "Failed to get contact addresses");
+            serde_json::to_string(&addrs)
+                .unwrap_or_log_default(
+                    ctx,
+                    "dc_get_contact_addrs_json() failed to serialise to json",
+                )
+                .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_imex_has_backup(
+pub unsafe extern "C" fn dc_merge_contacts(
     context: *mut dc_context_t,
-    dir: *const libc::c_char,
-) -> *mut libc::c_char {
-    if context.is_null() || dir.is_null() {
-        eprintln!("ignoring careless call to dc_imex_has_backup()");
-        return ptr::null_mut(); // NULL explicitly defined as "has no backup"
+    contact_id: u32,
+    other_contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_merge_contacts()");
+        return 0;
     }
     let ffi_context = &*context;
     ffi_context
-        .with_inner(|ctx| match imex::has_backup(ctx, to_string_lossy(dir)) {
-            Ok(res) => res.strdup(),
-            Err(err) => {
-                // do not bubble up error to the user,
-                // the ui will expect that the file does not exist or cannot be accessed
-                warn!(ctx, "dc_imex_has_backup: {}", err);
-                ptr::null_mut()
-            }
+        .with_inner(|ctx| {
+            Contact::merge(ctx, contact_id, other_contact_id)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to merge contacts")
         })
-        .unwrap_or_else(|_| ptr::null_mut())
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_initiate_key_transfer(context: *mut dc_context_t) -> *mut libc::c_char {
+pub unsafe extern "C" fn dc_set_contact_encryption_required(
+    context: *mut dc_context_t,
+    contact_id: u32,
+    required: libc::c_int,
+) -> libc::c_int {
     if context.is_null() {
-        eprintln!("ignoring careless call to dc_initiate_key_transfer()");
-        return ptr::null_mut(); // NULL explicitly defined as "error"
+        eprintln!("ignoring careless call to dc_set_contact_encryption_required()");
+        return 0;
     }
     let ffi_context = &*context;
     ffi_context
-        .with_inner(|ctx| match imex::initiate_key_transfer(ctx) {
-            Ok(res) => res.strdup(),
-            Err(err) => {
-                error!(ctx, "dc_initiate_key_transfer(): {}", err);
-                ptr::null_mut()
-            }
+        .with_inner(|ctx| {
+            Contact::set_encryption_required(ctx, contact_id, required != 0)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set encryption requirement of contact")
         })
-        .unwrap_or_else(|_| ptr::null_mut())
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_continue_key_transfer(
+pub unsafe extern "C" fn dc_add_filter_rule(
     context: *mut dc_context_t,
-    msg_id: u32,
-    setup_code: *const libc::c_char,
-) -> libc::c_int {
-    if context.is_null()
-        || msg_id <= constants::DC_MSG_ID_LAST_SPECIAL as u32
+    kind: libc::c_int,
+    header: *const libc::c_char,
+    pattern: *const libc::c_char,
+    action: libc::c_int,
+) -> u32 {
+    if context.is_null() || pattern.is_null() {
+        eprintln!("ignoring careless call to dc_add_filter_rule()");
+        return 0;
+    }
+    let (kind, action) = match (
+        filter::FilterKind::from_i32(kind),
+        filter::FilterAction::from_i32(action),
+    ) {
+        (Some(kind), Some(action)) => (kind, action),
+        _ => return 0,
+    };
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            filter::add_rule(
+                ctx,
+                kind,
+                to_opt_string_lossy(header).unwrap_or_default(),
+                to_string_lossy(pattern),
+                action,
+            )
+            .unwrap_or_log_default(ctx, "Failed to add filter rule")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_update_filter_rule(
+    context: *mut dc_context_t,
+    rule_id: u32,
+    kind: libc::c_int,
+    header: *const libc::c_char,
+    pattern: *const libc::c_char,
+    action: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() || pattern.is_null() {
+        eprintln!("ignoring careless call to dc_update_filter_rule()");
+        return 0;
+    }
+    let (kind, action) = match (
+        filter::FilterKind::from_i32(kind),
+        filter::FilterAction::from_i32(action),
+    ) {
+        (Some(kind), Some(action)) => (kind, action),
+        _ => return 0,
+    };
+    let rule = filter::FilterRule {
+        id: rule_id,
+        kind,
+        header: to_opt_string_lossy(header).unwrap_or_default(),
+        pattern: to_string_lossy(pattern),
+        action,
+    };
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            filter::update_rule(ctx, &rule)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to update filter rule")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_delete_filter_rule(
+    context: *mut dc_context_t,
+    rule_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_delete_filter_rule()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            filter::delete_rule(ctx, rule_id)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to delete filter rule")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_filter_rules_json(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_filter_rules_json()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let rules = filter::get_rules(ctx);
+            serde_json::to_string(&rules)
+                .unwrap_or_log_default(
+                    ctx,
+                    "dc_get_filter_rules_json() failed to serialise to json",
+                )
+                .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_contact_encrinfo(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_contact_encrinfo()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            Contact::get_encrinfo(ctx, contact_id)
+                .map(|s| s.strdup())
+                .unwrap_or_else(|e| {
+                    error!(ctx, "{}", e);
+                    ptr::null_mut()
+                })
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_delete_contact(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() || contact_id <= constants::DC_CONTACT_ID_LAST_SPECIAL as u32 {
+        eprintln!("ignoring careless call to dc_delete_contact()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match Contact::delete(ctx, contact_id) {
+            Ok(_) => 1,
+            Err(_) => 0,
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_contact(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> *mut dc_contact_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_contact()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            Contact::get_by_id(ctx, contact_id)
+                .map(|contact| Box::into_raw(Box::new(ContactWrapper { context, contact })))
+                .unwrap_or_else(|_| ptr::null_mut())
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_imex(
+    context: *mut dc_context_t,
+    what: libc::c_int,
+    param1: *const libc::c_char,
+    param2: *const libc::c_char,
+) {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_imex()");
+        return;
+    }
+    let what = match imex::ImexMode::from_i32(what as i32) {
+        Some(what) => what,
+        None => {
+            eprintln!("ignoring invalid argument {} to dc_imex", what);
+            return;
+        }
+    };
+
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let param2 = to_opt_string_lossy(param2);
+            imex::imex(
+                ctx,
+                what,
+                to_opt_string_lossy(param1),
+                param2.as_ref().map(|s| s.as_str()),
+            )
+        })
+        .ok();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_imex_has_backup(
+    context: *mut dc_context_t,
+    dir: *const libc::c_char,
+) -> *mut libc::c_char {
+    if context.is_null() || dir.is_null() {
+        eprintln!("ignoring careless call to dc_imex_has_backup()");
+        return ptr::null_mut(); // NULL explicitly defined as "has no backup"
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match imex::has_backup(ctx, to_string_lossy(dir)) {
+            Ok(res) => res.strdup(),
+            Err(err) => {
+                // do not bubble up error to the user,
+                // the ui will expect that the file does not exist or cannot be accessed
+                warn!(ctx, "dc_imex_has_backup: {}", err);
+                ptr::null_mut()
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_imex_is_backup_encrypted(
+    context: *mut dc_context_t,
+    file: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || file.is_null() {
+        eprintln!("ignoring careless call to dc_imex_is_backup_encrypted()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match imex::is_backup_encrypted(as_path(file)) {
+            Ok(encrypted) => encrypted as libc::c_int,
+            Err(err) => {
+                warn!(ctx, "dc_imex_is_backup_encrypted: {}", err);
+                0
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_imex_verify_backup_json(
+    context: *mut dc_context_t,
+    file: *const libc::c_char,
+    passphrase: *const libc::c_char,
+) -> *mut libc::c_char {
+    if context.is_null() || file.is_null() {
+        eprintln!("ignoring careless call to dc_imex_verify_backup_json()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    let passphrase = to_opt_string_lossy(passphrase);
+    ffi_context
+        .with_inner(|ctx| {
+            match imex::verify_backup(ctx, as_path(file), passphrase.as_ref().map(|s| s.as_str())) {
+                Ok(report) => serde_json::to_string(&report)
+                    .unwrap_or_log_default(
+                        ctx,
+                        "dc_imex_verify_backup_json() failed to serialise to json",
+                    )
+                    .strdup(),
+                Err(err) => {
+                    error!(ctx, "dc_imex_verify_backup_json: {}", err);
+                    ptr::null_mut()
+                }
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_initiate_key_transfer(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_initiate_key_transfer()");
+        return ptr::null_mut(); // NULL explicitly defined as "error"
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match imex::initiate_key_transfer(ctx) {
+            Ok(res) => res.strdup(),
+            Err(err) => {
+                error!(ctx, "dc_initiate_key_transfer(): {}", err);
+                ptr::null_mut()
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_continue_key_transfer(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    setup_code: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null()
+        || msg_id <= constants::DC_MSG_ID_LAST_SPECIAL as u32
         || setup_code.is_null()
     {
         eprintln!("ignoring careless call to dc_continue_key_transfer()");
@@ -1954,6 +2447,171 @@ pub unsafe extern "C" fn dc_get_securejoin_qr(
         .unwrap_or_else(|_| "".strdup())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_securejoin_invite_url(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_securejoin_invite_url()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            securejoin::dc_get_securejoin_invite_url(ctx, ChatId::new(chat_id))
+                .unwrap_or_else(|| "".to_string())
+                .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_safety_number(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_safety_number()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match securejoin::get_safety_number(ctx, contact_id) {
+            Ok(safety_number) => safety_number.strdup(),
+            Err(err) => {
+                warn!(ctx, "Cannot get safety number: {}", err);
+                ptr::null_mut()
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_confirm_safety_number(
+    context: *mut dc_context_t,
+    contact_id: u32,
+    safety_number: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || safety_number.is_null() {
+        eprintln!("ignoring careless call to dc_confirm_safety_number()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            securejoin::confirm_safety_number(ctx, contact_id, &to_string_lossy(safety_number))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to confirm safety number")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_create_securejoin_invite(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    expires: i64,
+    max_uses: libc::c_int,
+) -> *mut libc::c_char {
+    if context.is_null() || max_uses < 0 {
+        eprintln!("ignoring careless call to dc_create_securejoin_invite()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            securejoin::dc_create_securejoin_invite(
+                ctx,
+                ChatId::new(chat_id),
+                expires,
+                max_uses as u32,
+            )
+            .unwrap_or_else(|| "".to_string())
+            .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_securejoin_invites_json(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_securejoin_invites_json()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let invites = token::get_invites(ctx, ChatId::new(chat_id));
+            serde_json::to_string(&invites)
+                .unwrap_or_log_default(
+                    ctx,
+                    "dc_get_securejoin_invites_json() failed to serialise to json",
+                )
+                .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_revoke_securejoin_invite(
+    context: *mut dc_context_t,
+    invitenumber: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || invitenumber.is_null() {
+        eprintln!("ignoring careless call to dc_revoke_securejoin_invite()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            token::revoke_invite(ctx, &to_string_lossy(invitenumber))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to revoke invite")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_regenerate_securejoin_invites(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_regenerate_securejoin_invites()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            token::regenerate_invites(ctx, ChatId::new(chat_id))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to regenerate invites")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_rotate_self_key(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_rotate_self_key()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match e2ee::rotate_self_key(ctx) {
+            Ok(fingerprint) => fingerprint.strdup(),
+            Err(err) => {
+                error!(ctx, "Failed to rotate key: {}", err);
+                ptr::null_mut()
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_join_securejoin(
     context: *mut dc_context_t,
@@ -1969,6 +2627,21 @@ pub unsafe extern "C" fn dc_join_securejoin(
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_abort_securejoin(
+    context: *mut dc_context_t,
+    contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_abort_securejoin()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| securejoin::dc_abort_securejoin(ctx, contact_id) as libc::c_int)
+        .unwrap_or(0)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_send_locations_to_chat(
     context: *mut dc_context_t,
@@ -2414,6 +3087,16 @@ pub unsafe extern "C" fn dc_chat_get_name(chat: *mut dc_chat_t) -> *mut libc::c_
     ffi_chat.chat.get_name().strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_chat_get_description(chat: *mut dc_chat_t) -> *mut libc::c_char {
+    if chat.is_null() {
+        eprintln!("ignoring careless call to dc_chat_get_description()");
+        return dc_strdup(ptr::null());
+    }
+    let ffi_chat = &*chat;
+    ffi_chat.chat.get_description().strdup()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_subtitle(chat: *mut dc_chat_t) -> *mut libc::c_char {
     if chat.is_null() {
@@ -2520,6 +3203,16 @@ pub unsafe extern "C" fn dc_chat_is_verified(chat: *mut dc_chat_t) -> libc::c_in
     ffi_chat.chat.is_verified() as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_chat_is_encryption_required(chat: *mut dc_chat_t) -> libc::c_int {
+    if chat.is_null() {
+        eprintln!("ignoring careless call to dc_chat_is_encryption_required()");
+        return 0;
+    }
+    let ffi_chat = &*chat;
+    ffi_chat.chat.is_encryption_required() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_sending_locations(chat: *mut dc_chat_t) -> libc::c_int {
     if chat.is_null() {
@@ -2899,6 +3592,32 @@ pub unsafe extern "C" fn dc_msg_has_location(msg: *mut dc_msg_t) -> libc::c_int
     ffi_msg.message.has_location() as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_poi_label(msg: *mut dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_poi_label()");
+        return ptr::null_mut();
+    }
+    let ffi_msg = &*msg;
+    match ffi_msg.message.get_poi_label() {
+        Some(label) => label.strdup(),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_poi_description(msg: *mut dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_poi_description()");
+        return ptr::null_mut();
+    }
+    let ffi_msg = &*msg;
+    match ffi_msg.message.get_poi_description() {
+        Some(description) => description.strdup(),
+        None => ptr::null_mut(),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_sent(msg: *mut dc_msg_t) -> libc::c_int {
     if msg.is_null() {
@@ -3038,6 +3757,29 @@ pub unsafe extern "C" fn dc_msg_set_location(
     ffi_msg.message.set_location(latitude, longitude)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_set_poi(
+    msg: *mut dc_msg_t,
+    latitude: libc::c_double,
+    longitude: libc::c_double,
+    label: *const libc::c_char,
+    description: *const libc::c_char,
+) {
+    if msg.is_null() || label.is_null() {
+        eprintln!("ignoring careless call to dc_msg_set_poi()");
+        return;
+    }
+    let ffi_msg = &mut *msg;
+    ffi_msg.message.set_poi(
+        latitude,
+        longitude,
+        to_string_lossy(label),
+        to_opt_string_lossy(description)
+            .as_ref()
+            .map(|s| s.as_str()),
+    )
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_latefiling_mediasize(
     msg: *mut dc_msg_t,
@@ -3206,6 +3948,28 @@ pub unsafe extern "C" fn dc_contact_is_verified(contact: *mut dc_contact_t) -> l
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_contact_is_encryption_required(
+    contact: *mut dc_contact_t,
+) -> libc::c_int {
+    if contact.is_null() {
+        eprintln!("ignoring careless call to dc_contact_is_encryption_required()");
+        return 0;
+    }
+    let ffi_contact = &*contact;
+    ffi_contact.contact.is_encryption_required() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_contact_get_last_seen(contact: *mut dc_contact_t) -> i64 {
+    if contact.is_null() {
+        eprintln!("ignoring careless call to dc_contact_get_last_seen()");
+        return 0;
+    }
+    let ffi_contact = &*contact;
+    ffi_contact.contact.last_seen()
+}
+
 // dc_lot_t
 
 #[no_mangle]
@@ -9,6 +9,7 @@ use deltachat::context::*;
 use deltachat::dc_receive_imf::*;
 use deltachat::dc_tools::*;
 use deltachat::error::Error;
+use deltachat::filter::{self, FilterAction, FilterKind};
 use deltachat::imex::*;
 use deltachat::job::*;
 use deltachat::location;
@@ -320,11 +321,14 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                  get-setupcodebegin <msg-id>\n\
                  continue-key-transfer <msg-id> <setup-code>\n\
                  has-backup\n\
-                 export-backup\n\
-                 import-backup <backup-file>\n\
+                 export-backup [<passphrase>]\n\
+                 import-backup <backup-file> [<passphrase>]\n\
+                 verify-backup <backup-file> [<passphrase>]\n\
+                 change-db-passphrase [<passphrase>]\n\
                  export-keys\n\
-                 import-keys\n\
+                 import-keys [<passphrase>]\n\
                  export-setup\n\
+                 rotate-key\n\
                  poke [<eml-file>|<folder>|<addr> <key-file>]\n\
                  reset <flags>\n\
                  stop\n\
@@ -357,11 +361,13 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                  removemember <contact-id>\n\
                  groupname <name>\n\
                  groupimage [<file>]\n\
+                 groupdescription [<text>]\n\
+                 groupadmin <contact-id> [0|1]\n\
                  chatinfo\n\
                  sendlocations <seconds>\n\
                  setlocation <lat> <lng>\n\
                  dellocations\n\
-                 getlocations [<contact-id>]\n\
+                 getlocations [<contact-id>] [<file.gpx>|<file.geojson>]\n\
                  send <text>\n\
                  send-garbage\n\
                  sendimage <file> [<text>]\n\
@@ -374,6 +380,7 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                  pin <chat-id>\n\
                  unpin <chat-id>\n\
                  delchat <chat-id>\n\
+                 requireencryption <chat-id> [0|1]\n\
                  ===========================Message commands==\n\
                  listmsgs <query>\n\
                  msginfo <msg-id>\n\
@@ -389,9 +396,18 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                  addcontact [<name>] <addr>\n\
                  contactinfo <contact-id>\n\
                  delcontact <contact-id>\n\
+                 addcontactaddr <contact-id> <addr>\n\
+                 primarycontactaddr <contact-id> <addr>\n\
+                 mergecontacts <contact-id> <other-contact-id>\n\
+                 contactrequireencryption <contact-id> [0|1]\n\
+                 confirmsafetynumber <contact-id> <safety-number>\n\
                  cleanupcontacts\n\
+                 listfilters\n\
+                 addfilter <addr|domain|subject|header|listid> <block|trash|accept|archive> [<header-name>] <pattern>\n\
+                 delfilter <rule-id>\n\
                  ======================================Misc.==\n\
                  getqr [<chat-id>]\n\
+                 getinviteurl [<chat-id>]\n\
                  getbadqr\n\
                  checkqr <qr-content>\n\
                  providerinfo <addr>\n\
@@ -436,17 +452,43 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
             has_backup(context, blobdir)?;
         }
         "export-backup" => {
-            imex(context, ImexMode::ExportBackup, Some(blobdir));
+            let passphrase = if arg1.is_empty() { None } else { Some(arg1) };
+            imex(context, ImexMode::ExportBackup, Some(blobdir), passphrase);
         }
         "import-backup" => {
             ensure!(!arg1.is_empty(), "Argument <backup-file> missing.");
-            imex(context, ImexMode::ImportBackup, Some(arg1));
+            let passphrase = if arg2.is_empty() { None } else { Some(arg2) };
+            imex(context, ImexMode::ImportBackup, Some(arg1), passphrase);
+        }
+        "change-db-passphrase" => {
+            let passphrase = if arg1.is_empty() {
+                None
+            } else {
+                Some(arg1.to_string())
+            };
+            context.change_db_passphrase(passphrase)?;
+            println!("Database passphrase changed.");
+        }
+        "verify-backup" => {
+            ensure!(!arg1.is_empty(), "Argument <backup-file> missing.");
+            let passphrase = if arg2.is_empty() { None } else { Some(arg2) };
+            let report = verify_backup(context, arg1, passphrase)?;
+            println!("{:#?}", report);
+            println!(
+                "Backup is {}.",
+                if report.is_ok() { "ok" } else { "NOT ok" }
+            );
         }
         "export-keys" => {
-            imex(context, ImexMode::ExportSelfKeys, Some(blobdir));
+            imex(context, ImexMode::ExportSelfKeys, Some(blobdir), None);
         }
         "import-keys" => {
-            imex(context, ImexMode::ImportSelfKeys, Some(blobdir));
+            let passphrase = if arg1.is_empty() { None } else { Some(arg1) };
+            imex(context, ImexMode::ImportSelfKeys, Some(blobdir), passphrase);
+        }
+        "rotate-key" => {
+            let fingerprint = deltachat::e2ee::rotate_self_key(context)?;
+            println!("Key replaced, new fingerprint:\n{}", fingerprint);
         }
         "export-setup" => {
             let setup_code = create_setup_code(context);
@@ -682,6 +724,31 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
 
             println!("Chat image set");
         }
+        "groupdescription" => {
+            ensure!(sel_chat.is_some(), "No chat selected.");
+            let description = format!("{} {}", arg1, arg2);
+            chat::set_chat_description(
+                context,
+                sel_chat.as_ref().unwrap().get_id(),
+                description.trim(),
+            )?;
+
+            println!("Chat description set");
+        }
+        "groupadmin" => {
+            ensure!(sel_chat.is_some(), "No chat selected.");
+            ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
+            let contact_id: u32 = arg1.parse()?;
+            let admin = arg2 != "0";
+            chat::set_group_admin(
+                context,
+                sel_chat.as_ref().unwrap().get_id(),
+                contact_id,
+                admin,
+            )?;
+
+            println!("Group admin {}", if admin { "set" } else { "removed" });
+        }
         "chatinfo" => {
             ensure!(sel_chat.is_some(), "No chat selected.");
 
@@ -701,14 +768,22 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
         "getlocations" => {
             ensure!(sel_chat.is_some(), "No chat selected.");
 
+            let chat_id = sel_chat.as_ref().unwrap().get_id();
             let contact_id = arg1.parse().unwrap_or_default();
-            let locations = location::get_range(
-                context,
-                sel_chat.as_ref().unwrap().get_id(),
-                contact_id,
-                0,
-                0,
-            );
+            if !arg2.is_empty() {
+                let content = match dc_get_filesuffix_lc(arg2).as_ref().map(|s| s.as_str()) {
+                    Some("gpx") => location::get_gpx(context, chat_id, contact_id, 0, 0),
+                    Some("geojson") | Some("json") => {
+                        location::get_geojson(context, chat_id, contact_id, 0, 0)
+                    }
+                    _ => bail!("Unknown export format, use .gpx or .geojson."),
+                };
+                std::fs::write(arg2, content)?;
+                println!("Locations written to {}.", arg2);
+                return Ok(());
+            }
+
+            let locations = location::get_range(context, chat_id, contact_id, 0, 0);
             let default_marker = "-".to_string();
             for location in &locations {
                 let marker = location.marker.as_ref().unwrap_or(&default_marker);
@@ -862,6 +937,16 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                 },
             )?;
         }
+        "requireencryption" => {
+            ensure!(!arg1.is_empty(), "Argument <chat-id> missing.");
+            let chat_id = ChatId::new(arg1.parse()?);
+            let required = arg2 != "0";
+            chat::set_encryption_required(context, chat_id, required)?;
+            println!(
+                "Encryption {}",
+                if required { "required" } else { "not required" }
+            );
+        }
         "delchat" => {
             ensure!(!arg1.is_empty(), "Argument <chat-id> missing.");
             let chat_id = ChatId::new(arg1.parse()?);
@@ -947,6 +1032,11 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                 }
             );
 
+            let addrs = Contact::get_addrs(context, contact_id)?;
+            if addrs.len() > 1 {
+                res += &format!("Addresses: {}\n", addrs.join(", "));
+            }
+
             res += &Contact::get_encrinfo(context, contact_id)?;
 
             let chatlist = Chatlist::try_load(context, 0, None, Some(contact_id))?;
@@ -971,6 +1061,95 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
             ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
             Contact::delete(context, arg1.parse()?)?;
         }
+        "addcontactaddr" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <contact-id> <addr> expected."
+            );
+            Contact::add_addr(context, arg1.parse()?, arg2)?;
+        }
+        "primarycontactaddr" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <contact-id> <addr> expected."
+            );
+            Contact::set_primary_addr(context, arg1.parse()?, arg2)?;
+        }
+        "mergecontacts" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <contact-id> <other-contact-id> expected."
+            );
+            Contact::merge(context, arg1.parse()?, arg2.parse()?)?;
+        }
+        "contactrequireencryption" => {
+            ensure!(!arg1.is_empty(), "Argument <contact-id> missing.");
+            let required = arg2 != "0";
+            Contact::set_encryption_required(context, arg1.parse()?, required)?;
+            println!(
+                "Encryption {}",
+                if required { "required" } else { "not required" }
+            );
+        }
+        "confirmsafetynumber" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <contact-id> <safety-number> expected."
+            );
+            deltachat::securejoin::confirm_safety_number(context, arg1.parse()?, arg2)?;
+            println!("Contact verified.");
+        }
+        "listfilters" => {
+            for rule in filter::get_rules(context) {
+                println!(
+                    "#{}: {} {}{} -> {}",
+                    rule.id,
+                    rule.kind,
+                    if rule.header.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!("{}: ", rule.header)
+                    },
+                    rule.pattern,
+                    rule.action
+                );
+            }
+        }
+        "addfilter" => {
+            ensure!(
+                !arg1.is_empty() && !arg2.is_empty(),
+                "Arguments <kind> <action> <pattern> expected."
+            );
+            let kind = match arg1 {
+                "addr" => FilterKind::Addr,
+                "domain" => FilterKind::Domain,
+                "subject" => FilterKind::Subject,
+                "header" => FilterKind::Header,
+                "listid" => FilterKind::ListId,
+                _ => bail!("Unknown filter kind: {}", arg1),
+            };
+            let mut args = arg2.splitn(2, ' ');
+            let action = match args.next().unwrap_or_default() {
+                "block" => FilterAction::Block,
+                "trash" => FilterAction::Trash,
+                "accept" => FilterAction::Accept,
+                "archive" => FilterAction::Archive,
+                action => bail!("Unknown filter action: {}", action),
+            };
+            let mut pattern = args.next().unwrap_or_default();
+            let mut header = "";
+            if kind == FilterKind::Header {
+                let mut args = pattern.splitn(2, ' ');
+                header = args.next().unwrap_or_default();
+                pattern = args.next().unwrap_or_default();
+            }
+            let rule_id = filter::add_rule(context, kind, header, pattern, action)?;
+            println!("Filter rule #{} added.", rule_id);
+        }
+        "delfilter" => {
+            ensure!(!arg1.is_empty(), "Argument <rule-id> missing.");
+            filter::delete_rule(context, arg1.parse()?)?;
+        }
         "checkqr" => {
             ensure!(!arg1.is_empty(), "Argument <qr-content> missing.");
             let res = check_qr(context, arg1);
@@ -125,6 +125,7 @@ struct Handle {
     handle_imap: Option<std::thread::JoinHandle<()>>,
     handle_mvbox: Option<std::thread::JoinHandle<()>>,
     handle_sentbox: Option<std::thread::JoinHandle<()>>,
+    handle_extra: Option<std::thread::JoinHandle<()>>,
     handle_smtp: Option<std::thread::JoinHandle<()>>,
 }
 
@@ -178,6 +179,16 @@ fn start_threads(c: Arc<RwLock<Context>>) {
         });
     });
 
+    let ctx = c.clone();
+    let handle_extra = std::thread::spawn(move || loop {
+        while_running!({
+            perform_extra_fetch(&ctx.read().unwrap());
+            while_running!({
+                perform_extra_idle(&ctx.read().unwrap());
+            });
+        });
+    });
+
     let ctx = c;
     let handle_smtp = std::thread::spawn(move || loop {
         while_running!({
@@ -192,6 +203,7 @@ fn start_threads(c: Arc<RwLock<Context>>) {
         handle_imap: Some(handle_imap),
         handle_mvbox: Some(handle_mvbox),
         handle_sentbox: Some(handle_sentbox),
+        handle_extra: Some(handle_extra),
         handle_smtp: Some(handle_smtp),
     });
 }
@@ -204,11 +216,13 @@ fn stop_threads(context: &Context) {
         interrupt_inbox_idle(context);
         interrupt_mvbox_idle(context);
         interrupt_sentbox_idle(context);
+        interrupt_extra_idle(context);
         interrupt_smtp_idle(context);
 
         handle.handle_imap.take().unwrap().join().unwrap();
         handle.handle_mvbox.take().unwrap().join().unwrap();
         handle.handle_sentbox.take().unwrap().join().unwrap();
+        handle.handle_extra.take().unwrap().join().unwrap();
         handle.handle_smtp.take().unwrap().join().unwrap();
     }
 }
@@ -234,16 +248,19 @@ impl Completer for DcHelper {
     }
 }
 
-const IMEX_COMMANDS: [&str; 12] = [
+const IMEX_COMMANDS: [&str; 15] = [
     "initiate-key-transfer",
     "get-setupcodebegin",
     "continue-key-transfer",
     "has-backup",
     "export-backup",
     "import-backup",
+    "verify-backup",
+    "change-db-passphrase",
     "export-keys",
     "import-keys",
     "export-setup",
+    "rotate-key",
     "poke",
     "reset",
     "stop",
@@ -263,7 +280,7 @@ const DB_COMMANDS: [&str; 11] = [
     "housekeeping",
 ];
 
-const CHAT_COMMANDS: [&str; 26] = [
+const CHAT_COMMANDS: [&str; 29] = [
     "listchats",
     "listarchived",
     "chat",
@@ -275,6 +292,8 @@ const CHAT_COMMANDS: [&str; 26] = [
     "removemember",
     "groupname",
     "groupimage",
+    "groupdescription",
+    "groupadmin",
     "chatinfo",
     "sendlocations",
     "setlocation",
@@ -290,6 +309,7 @@ const CHAT_COMMANDS: [&str; 26] = [
     "pin",
     "unpin",
     "delchat",
+    "requireencryption",
 ];
 const MESSAGE_COMMANDS: [&str; 8] = [
     "listmsgs",
@@ -301,16 +321,33 @@ const MESSAGE_COMMANDS: [&str; 8] = [
     "unstar",
     "delmsg",
 ];
-const CONTACT_COMMANDS: [&str; 6] = [
+const CONTACT_COMMANDS: [&str; 14] = [
     "listcontacts",
     "listverified",
     "addcontact",
     "contactinfo",
     "delcontact",
+    "addcontactaddr",
+    "primarycontactaddr",
+    "mergecontacts",
+    "contactrequireencryption",
+    "confirmsafetynumber",
     "cleanupcontacts",
+    "listfilters",
+    "addfilter",
+    "delfilter",
 ];
-const MISC_COMMANDS: [&str; 9] = [
-    "getqr", "getbadqr", "checkqr", "event", "fileinfo", "clear", "exit", "quit", "help",
+const MISC_COMMANDS: [&str; 10] = [
+    "getqr",
+    "getbadqr",
+    "getinviteurl",
+    "checkqr",
+    "event",
+    "fileinfo",
+    "clear",
+    "exit",
+    "quit",
+    "help",
 ];
 
 impl Hinter for DcHelper {
@@ -367,10 +404,12 @@ fn main_0(args: Vec<String>) -> Result<(), failure::Error> {
         println!("Error: Bad arguments, expected [db-name].");
         return Err(format_err!("No db-name specified"));
     }
-    let context = Context::new(
+    // an optional second argument is the passphrase the database is encrypted with
+    let context = Context::new_with_passphrase(
         Box::new(receive_event),
         "CLI".into(),
         Path::new(&args[1]).to_path_buf(),
+        args.get(2).cloned(),
     )?;
 
     println!("Delta Chat Core is awaiting your commands.");
@@ -505,6 +544,15 @@ fn handle_cmd(line: &str, ctx: Arc<RwLock<Context>>) -> Result<ExitResult, failu
                 }
             }
         }
+        "getinviteurl" => {
+            start_threads(ctx.clone());
+            if let Some(url) = dc_get_securejoin_invite_url(
+                &ctx.read().unwrap(),
+                ChatId::new(arg1.parse().unwrap_or_default()),
+            ) {
+                println!("{}", url);
+            }
+        }
         "joinqr" => {
             start_threads(ctx.clone());
             if !arg0.is_empty() {
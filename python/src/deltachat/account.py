@@ -2,6 +2,7 @@
 
 from __future__ import print_function
 import atexit
+import json
 import threading
 import os
 import re
@@ -26,7 +27,8 @@ class Account(object):
     by the underlying deltachat core library.  All public Account methods are
     meant to be memory-safe and return memory-safe objects.
     """
-    def __init__(self, db_path, logid=None, eventlogging=True, os_name=None, debug=True):
+    def __init__(self, db_path, logid=None, eventlogging=True, os_name=None, debug=True,
+                 passphrase=None):
         """ initialize account object.
 
         :param db_path: a path to the account database. The database
@@ -36,6 +38,8 @@ class Account(object):
         :param eventlogging: if False no eventlogging and no context callback will be configured
         :param os_name: this will be put to the X-Mailer header in outgoing messages
         :param debug: turn on debug logging for events.
+        :param passphrase: passphrase the database is encrypted with,
+                           requires the core to be built with the sqlcipher feature.
         """
         self._dc_context = ffi.gc(
             lib.dc_context_new(lib.py_dc_callback, ffi.NULL, as_dc_charpointer(os_name)),
@@ -50,7 +54,12 @@ class Account(object):
 
         if hasattr(db_path, "encode"):
             db_path = db_path.encode("utf8")
-        if not lib.dc_open(self._dc_context, db_path, ffi.NULL):
+        if passphrase is None:
+            opened = lib.dc_open(self._dc_context, db_path, ffi.NULL)
+        else:
+            opened = lib.dc_open_with_passphrase(
+                self._dc_context, db_path, ffi.NULL, as_dc_charpointer(passphrase))
+        if not opened:
             raise ValueError("Could not dc_open: {}".format(db_path))
         self._configkeys = self.get_config("sys.config_keys").split()
         self._imex_events = Queue()
@@ -372,12 +381,13 @@ class Account(object):
         """ export public and private keys to the specified directory. """
         return self._export(path, imex_cmd=1)
 
-    def export_all(self, path):
+    def export_all(self, path, passphrase=None):
         """return new file containing a backup of all database state
         (chats, contacts, keys, media, ...). The file is created in the
-        the `path` directory.
+        the `path` directory. If `passphrase` is given, the backup
+        is encrypted with it.
         """
-        export_files = self._export(path, 11)
+        export_files = self._export(path, 11, passphrase=passphrase)
         if len(export_files) != 1:
             raise RuntimeError("found more than one new file")
         return export_files[0]
@@ -389,9 +399,10 @@ class Account(object):
         except Empty:
             pass
 
-    def _export(self, path, imex_cmd):
+    def _export(self, path, imex_cmd, passphrase=None):
         self._imex_events_clear()
-        lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), ffi.NULL)
+        passphrase = as_dc_charpointer(passphrase) if passphrase is not None else ffi.NULL
+        lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), passphrase)
         if not self._threads.is_started():
             lib.dc_perform_imap_jobs(self._dc_context)
         files_written = []
@@ -404,24 +415,51 @@ class Account(object):
                     raise ValueError("export failed, exp-files: {}".format(files_written))
                 return files_written
 
-    def import_self_keys(self, path):
+    def change_db_passphrase(self, passphrase):
+        """ re-encrypt the database with a new passphrase.
+        Pass None to remove the encryption.
+        """
+        passphrase = as_dc_charpointer(passphrase) if passphrase is not None else ffi.NULL
+        if not lib.dc_change_db_passphrase(self._dc_context, passphrase):
+            raise ValueError("could not change database passphrase")
+
+    def is_backup_encrypted(self, path):
+        """ return True if the backup at `path` needs a passphrase to be imported. """
+        return bool(lib.dc_imex_is_backup_encrypted(self._dc_context, as_dc_charpointer(path)))
+
+    def verify_backup(self, path, passphrase=None):
+        """ check the backup at `path` without importing it.
+
+        :returns: dictionary with the report, see dc_imex_verify_backup_json().
+        """
+        passphrase = as_dc_charpointer(passphrase) if passphrase is not None else ffi.NULL
+        res = lib.dc_imex_verify_backup_json(
+            self._dc_context, as_dc_charpointer(path), passphrase)
+        if res == ffi.NULL:
+            raise ValueError("could not read backup {!r}".format(path))
+        return json.loads(from_dc_charpointer(res))
+
+    def import_self_keys(self, path, passphrase=None):
         """ Import private keys found in the `path` directory.
         The last imported key is made the default keys unless its name
         contains the string legacy. Public keys are not imported.
+        Keys protected by a passphrase are unlocked using `passphrase`.
         """
-        self._import(path, imex_cmd=2)
+        self._import(path, imex_cmd=2, passphrase=passphrase)
 
-    def import_all(self, path):
+    def import_all(self, path, passphrase=None):
         """import delta chat state from the specified backup `path` (a file).
 
         The account must be in unconfigured state for import to attempted.
+        Backups encrypted on export need the same `passphrase`.
         """
         assert not self.is_configured(), "cannot import into configured account"
-        self._import(path, imex_cmd=12)
+        self._import(path, imex_cmd=12, passphrase=passphrase)
 
-    def _import(self, path, imex_cmd):
+    def _import(self, path, imex_cmd, passphrase=None):
         self._imex_events_clear()
-        lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), ffi.NULL)
+        passphrase = as_dc_charpointer(passphrase) if passphrase is not None else ffi.NULL
+        lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), passphrase)
         if not self._threads.is_started():
             lib.dc_perform_imap_jobs(self._dc_context)
         if not self._imex_events.get():
@@ -502,7 +540,7 @@ class Account(object):
         ev = self._evlogger.get_matching("DC_EVENT_INCOMING_MSG")
         return self.get_message_by_id(ev[2])
 
-    def start_threads(self, mvbox=False, sentbox=False):
+    def start_threads(self, mvbox=False, sentbox=False, extra=False):
         """ start IMAP/SMTP threads (and configure account if it hasn't happened).
 
         :raises: ValueError if 'addr' or 'mail_pw' are not configured.
@@ -510,7 +548,7 @@ class Account(object):
         """
         if not self.is_configured():
             self.configure()
-        self._threads.start(mvbox=mvbox, sentbox=sentbox)
+        self._threads.start(mvbox=mvbox, sentbox=sentbox, extra=extra)
 
     def stop_threads(self, wait=True):
         """ stop IMAP/SMTP threads. """
@@ -572,7 +610,7 @@ class IOThreads:
     def is_started(self):
         return len(self._name2thread) > 0
 
-    def start(self, imap=True, smtp=True, mvbox=False, sentbox=False):
+    def start(self, imap=True, smtp=True, mvbox=False, sentbox=False, extra=False):
         assert not self.is_started()
         if imap:
             self._start_one_thread("inbox", self.imap_thread_run)
@@ -580,6 +618,8 @@ class IOThreads:
             self._start_one_thread("mvbox", self.mvbox_thread_run)
         if sentbox:
             self._start_one_thread("sentbox", self.sentbox_thread_run)
+        if extra:
+            self._start_one_thread("extra", self.extra_thread_run)
         if smtp:
             self._start_one_thread("smtp", self.smtp_thread_run)
 
@@ -599,6 +639,7 @@ class IOThreads:
         lib.dc_interrupt_smtp_idle(self._dc_context)
         lib.dc_interrupt_mvbox_idle(self._dc_context)
         lib.dc_interrupt_sentbox_idle(self._dc_context)
+        lib.dc_interrupt_extra_idle(self._dc_context)
         if wait:
             for name, thread in self._name2thread.items():
                 thread.join()
@@ -633,6 +674,14 @@ class IOThreads:
                 lib.dc_perform_sentbox_idle(self._dc_context)
         self._log_event("py-bindings-info", 0, "SENTBOX THREAD FINISHED")
 
+    def extra_thread_run(self):
+        self._log_event("py-bindings-info", 0, "EXTRA THREAD START")
+        while not self._thread_quitflag:
+            lib.dc_perform_extra_fetch(self._dc_context)
+            if not self._thread_quitflag:
+                lib.dc_perform_extra_idle(self._dc_context)
+        self._log_event("py-bindings-info", 0, "EXTRA THREAD FINISHED")
+
     def smtp_thread_run(self):
         self._log_event("py-bindings-info", 0, "SMTP THREAD START")
         while not self._thread_quitflag:
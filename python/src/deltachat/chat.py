@@ -81,6 +81,21 @@ class Chat(object):
         """
         return lib.dc_chat_is_verified(self._dc_chat)
 
+    def is_encryption_required(self):
+        """ return True if messages to this chat must not be sent unencrypted.
+
+        :returns: True if encryption is required, False otherwise.
+        """
+        return bool(lib.dc_chat_is_encryption_required(self._dc_chat))
+
+    def set_encryption_required(self, required):
+        """ require encryption for messages to this chat.
+
+        :param required: True to never send unencrypted messages to this chat.
+        :returns: None
+        """
+        lib.dc_set_chat_encryption_required(self._dc_context, self.id, int(required))
+
     def get_name(self):
         """ return name of this chat.
 
@@ -97,6 +112,22 @@ class Chat(object):
         name = as_dc_charpointer(name)
         return lib.dc_set_chat_name(self._dc_context, self.id, name)
 
+    def get_description(self):
+        """ return description of this group chat.
+
+        :returns: unicode description, empty if there is none
+        """
+        return from_dc_charpointer(lib.dc_chat_get_description(self._dc_chat))
+
+    def set_description(self, description):
+        """ set description of this group chat.
+
+        :param description: as a unicode string, empty to remove the description.
+        :returns: None
+        """
+        description = as_dc_charpointer(description)
+        return lib.dc_set_chat_description(self._dc_context, self.id, description)
+
     def mute(self, duration=None):
         """ mutes the chat
 
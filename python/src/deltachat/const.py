@@ -13,6 +13,7 @@ DC_GCL_NO_SPECIALS = 0x02
 DC_GCL_ADD_ALLDONE_HINT = 0x04
 DC_GCL_VERIFIED_ONLY = 0x01
 DC_GCL_ADD_SELF = 0x02
+DC_GCL_SORT_BY_LAST_SEEN = 0x04
 DC_QR_ASK_VERIFYCONTACT = 200
 DC_QR_ASK_VERIFYGROUP = 202
 DC_QR_FPR_OK = 210
@@ -36,6 +37,18 @@ DC_CHAT_TYPE_VERIFIED_GROUP = 130
 DC_CHAT_VISIBILITY_NORMAL = 0
 DC_CHAT_VISIBILITY_ARCHIVED = 1
 DC_CHAT_VISIBILITY_PINNED = 2
+DC_FILTER_KIND_ADDR = 1
+DC_FILTER_KIND_DOMAIN = 2
+DC_FILTER_KIND_SUBJECT = 3
+DC_FILTER_KIND_HEADER = 4
+DC_FILTER_KIND_LIST_ID = 5
+DC_FILTER_ACTION_BLOCK = 1
+DC_FILTER_ACTION_TRASH = 2
+DC_FILTER_ACTION_ACCEPT = 3
+DC_FILTER_ACTION_ARCHIVE = 4
+DC_KEY_GEN_DEFAULT = 0
+DC_KEY_GEN_RSA2048 = 1
+DC_KEY_GEN_ED25519 = 2
 DC_MSG_ID_MARKER1 = 1
 DC_MSG_ID_DAYMARKER = 9
 DC_MSG_ID_LAST_SPECIAL = 9
@@ -101,6 +114,9 @@ DC_EVENT_IMEX_FILE_WRITTEN = 2052
 DC_EVENT_SECUREJOIN_INVITER_PROGRESS = 2060
 DC_EVENT_SECUREJOIN_JOINER_PROGRESS = 2061
 DC_EVENT_SECUREJOIN_MEMBER_ADDED = 2062
+DC_EVENT_FILTER_RULE_MATCHED = 2070
+DC_EVENT_SELF_KEY_CHANGED = 2071
+DC_EVENT_ENCRYPTION_REQUIRED_FAILED = 2072
 DC_EVENT_FILE_COPIED = 2055
 DC_EVENT_IS_OFFLINE = 2081
 DC_EVENT_GET_STRING = 2091
@@ -160,7 +176,7 @@ DC_STR_COUNT = 68
 
 def read_event_defines(f):
     rex = re.compile(r'#define\s+((?:DC_EVENT|DC_QR|DC_MSG|DC_LP|DC_EMPTY|DC_CERTCK|DC_STATE|DC_STR|'
-                     r'DC_CONTACT_ID|DC_GCL|DC_CHAT|DC_PROVIDER)_\S+)\s+([x\d]+).*')
+                     r'DC_CONTACT_ID|DC_GCL|DC_CHAT|DC_PROVIDER|DC_FILTER|DC_KEY_GEN)_\S+)\s+([x\d]+).*')
     for line in f:
         m = rex.match(line)
         if m:
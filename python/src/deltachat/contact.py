@@ -1,7 +1,7 @@
 """ Contact object. """
 
 from . import props
-from .cutil import from_dc_charpointer
+from .cutil import as_dc_charpointer, from_dc_charpointer
 from .capi import lib, ffi
 
 
@@ -48,6 +48,29 @@ class Contact(object):
         """ Return True if the contact is verified. """
         return lib.dc_contact_is_verified(self._dc_contact)
 
+    def is_encryption_required(self):
+        """ Return True if messages to the contact must not be sent unencrypted. """
+        return bool(lib.dc_contact_is_encryption_required(self._dc_contact))
+
+    def set_encryption_required(self, required):
+        """ Require encryption for messages to the contact. """
+        return bool(lib.dc_set_contact_encryption_required(self._dc_context, self.id, int(required)))
+
+    def get_safety_number(self):
+        """ Return the safety number of the own key and the key of the contact, None if no key is known. """
+        dc_res = lib.dc_get_safety_number(self._dc_context, self.id)
+        if dc_res == ffi.NULL:
+            return None
+        return from_dc_charpointer(dc_res)
+
+    def confirm_safety_number(self, safety_number):
+        """ Verify the contact after the safety numbers were compared. Return True on success. """
+        return bool(lib.dc_confirm_safety_number(self._dc_context, self.id, as_dc_charpointer(safety_number)))
+
+    def get_last_seen(self):
+        """ Return the timestamp of the last message received from the contact, 0 if none. """
+        return lib.dc_contact_get_last_seen(self._dc_contact)
+
     def get_profile_image(self):
         """Get contact profile image.
 
@@ -609,6 +609,11 @@ impl Chat {
         }
     }
 
+    /// Returns the description of a group, empty if there is none.
+    pub fn get_description(&self) -> &str {
+        self.param.get(Param::GroupDescription).unwrap_or_default()
+    }
+
     pub fn get_profile_image(&self, context: &Context) -> Option<PathBuf> {
         if let Some(image_rel) = self.param.get(Param::ProfileImage) {
             if !image_rel.is_empty() {
@@ -667,6 +672,7 @@ impl Chat {
             color: self.get_color(context),
             profile_image: self.get_profile_image(context).unwrap_or_else(PathBuf::new),
             subtitle: self.get_subtitle(context),
+            description: self.get_description().to_string(),
             draft,
             is_muted: self.is_muted(),
         })
@@ -684,9 +690,23 @@ impl Chat {
         !self.is_unpromoted()
     }
 
-    /// Returns true if chat is a verified group chat.
+    /// Returns true if chat is a verified group chat
+    /// or a one-to-one chat verified by a secure-join.
     pub fn is_verified(&self) -> bool {
         self.typ == Chattype::VerifiedGroup
+            || self.typ == Chattype::Single
+                && self.param.get_int(Param::Verified).unwrap_or_default() == 1
+    }
+
+    /// Returns true if messages to the chat must not be sent unencrypted,
+    /// see `set_encryption_required()`.
+    ///
+    /// Encryption may also be required by the members of the chat,
+    /// see `Contact::is_encryption_required()`.
+    pub fn is_encryption_required(&self) -> bool {
+        self.param
+            .get_bool(Param::EncryptionRequired)
+            .unwrap_or_default()
     }
 
     /// Returns true if location streaming is enabled in the chat.
@@ -864,14 +884,16 @@ impl Chat {
                     context,
                     &context.sql,
                     "INSERT INTO locations \
-                     (timestamp,from_id,chat_id, latitude,longitude,independent)\
-                     VALUES (?,?,?, ?,?,1);", // 1=DC_CONTACT_ID_SELF
+                     (timestamp,from_id,chat_id, latitude,longitude,independent, label,description)\
+                     VALUES (?,?,?, ?,?,1, ?,?);", // 1=DC_CONTACT_ID_SELF
                     params![
                         timestamp,
                         DC_CONTACT_ID_SELF,
                         self.id,
                         msg.param.get_float(Param::SetLatitude).unwrap_or_default(),
                         msg.param.get_float(Param::SetLongitude).unwrap_or_default(),
+                        msg.param.get(Param::PoiLabel).unwrap_or_default(),
+                        msg.param.get(Param::PoiDescription).unwrap_or_default(),
                     ],
                 )
                 .is_ok()
@@ -1012,6 +1034,11 @@ pub struct ChatInfo {
     /// Subtitle for the chat.
     pub subtitle: String,
 
+    /// The description of a group.
+    ///
+    /// If there is no description this is an empty string.
+    pub description: String,
+
     /// The draft message text.
     ///
     /// If the chat has not draft this is an empty string.
@@ -1810,6 +1837,10 @@ pub(crate) fn add_contact_to_chat_ex(
         );
         bail!("can not add contact because our account is not part of it");
     }
+    ensure!(
+        may_manage_group(context, chat_id, DC_CONTACT_ID_SELF),
+        "only group admins can add members"
+    );
     if from_handshake && chat.param.get_int(Param::Unpromoted).unwrap_or_default() == 1 {
         chat.param.remove(Param::Unpromoted);
         chat.update_param(context)?;
@@ -2048,6 +2079,12 @@ pub fn remove_contact_from_chat(
                     )
                 );
             } else {
+                // leaving a group is always possible, removing others requires admin rights
+                ensure!(
+                    contact_id == DC_CONTACT_ID_SELF
+                        || may_manage_group(context, chat_id, DC_CONTACT_ID_SELF),
+                    "only group admins can remove members"
+                );
                 /* we should respect this - whatever we send to the group, it gets discarded anyway! */
                 if let Ok(contact) = Contact::get_by_id(context, contact_id) {
                     if chat.is_promoted() {
@@ -2099,6 +2136,196 @@ pub fn remove_contact_from_chat(
     Ok(())
 }
 
+/// Returns the contact IDs of the admins of a group.
+///
+/// Groups without admins can be managed by all members.
+pub fn get_group_admins(context: &Context, chat_id: ChatId) -> Vec<u32> {
+    context
+        .sql
+        .query_map(
+            "SELECT contact_id FROM chats_contacts WHERE chat_id=? AND is_admin=1 ORDER BY contact_id;",
+            params![chat_id],
+            |row| row.get::<_, u32>(0),
+            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .unwrap_or_default()
+}
+
+/// Checks if a contact is an admin of a group.
+pub fn is_group_admin(context: &Context, chat_id: ChatId, contact_id: u32) -> bool {
+    context
+        .sql
+        .exists(
+            "SELECT contact_id FROM chats_contacts WHERE chat_id=? AND contact_id=? AND is_admin=1;",
+            params![chat_id, contact_id as i32],
+        )
+        .unwrap_or_default()
+}
+
+/// Checks if a contact may change the members, the name or the image of a group.
+///
+/// This is the case if the contact is an admin of the group
+/// or if the group has no admins at all.
+///
+/// Admins are only authenticated in verified groups;
+/// in unverified groups, admin roles do not protect against forged sender addresses.
+pub(crate) fn may_manage_group(context: &Context, chat_id: ChatId, contact_id: u32) -> bool {
+    is_group_admin(context, chat_id, contact_id) || get_group_admins(context, chat_id).is_empty()
+}
+
+/// Sets the admin flag of group members without any checks or messages.
+///
+/// Contacts that are not members of the group are ignored.
+pub(crate) fn set_group_admins_raw(
+    context: &Context,
+    chat_id: ChatId,
+    contact_ids: &[u32],
+    admin: bool,
+) -> Result<(), Error> {
+    for contact_id in contact_ids {
+        sql::execute(
+            context,
+            &context.sql,
+            "UPDATE chats_contacts SET is_admin=? WHERE chat_id=? AND contact_id=?;",
+            params![admin as i32, chat_id, *contact_id as i32],
+        )?;
+    }
+    Ok(())
+}
+
+/// Marks a one-to-one chat as verified or unverified.
+///
+/// Verified one-to-one chats are set up by a secure-join
+/// and, like verified groups, only accept and send messages
+/// encrypted with the verified key of the contact.
+pub(crate) fn set_chat_verified(
+    context: &Context,
+    chat_id: ChatId,
+    verified: bool,
+) -> Result<(), Error> {
+    let mut chat = Chat::load_from_db(context, chat_id)?;
+    ensure!(
+        chat.typ == Chattype::Single && !chat.param.exists(Param::Selftalk),
+        "{} is not a one-to-one chat that can be verified",
+        chat_id
+    );
+    if chat.is_verified() != verified {
+        chat.param.set_int(Param::Verified, verified as i32);
+        chat.update_param(context)?;
+        emit_event!(context, Event::ChatModified(chat_id));
+    }
+    Ok(())
+}
+
+/// Sets whether messages to the chat must be sent encrypted.
+///
+/// As long as a key of any member is missing,
+/// messages to the chat are not sent unencrypted but fail.
+pub fn set_encryption_required(
+    context: &Context,
+    chat_id: ChatId,
+    required: bool,
+) -> Result<(), Error> {
+    ensure!(
+        !chat_id.is_special(),
+        "Cannot require encryption for special chat {}",
+        chat_id
+    );
+    let mut chat = Chat::load_from_db(context, chat_id)?;
+    ensure!(
+        !chat.is_device_talk(),
+        "Cannot require encryption for the device chat"
+    );
+    if chat.is_encryption_required() != required {
+        chat.param
+            .set_int(Param::EncryptionRequired, required as i32);
+        chat.update_param(context)?;
+        emit_event!(context, Event::ChatModified(chat_id));
+    }
+    Ok(())
+}
+
+/// Promotes a group member to admin or revokes the admin role.
+///
+/// Only admins can change the admin roles.
+/// If the group has no admins yet, the own account becomes admin as well,
+/// so that it does not lose the right to manage the group.
+/// Once a group has admins, only admins can add or remove members
+/// and change the name or the image of the group;
+/// the other members will ignore such changes from non-admins.
+///
+/// If the group is promoted, the members are informed by a status message.
+pub fn set_group_admin(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: u32,
+    admin: bool,
+) -> Result<(), Error> {
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+    ensure!(
+        real_group_exists(context, chat_id),
+        "{} is not a group where admins can be set",
+        chat_id
+    );
+    if !is_contact_in_chat(context, chat_id, DC_CONTACT_ID_SELF) {
+        emit_event!(
+            context,
+            Event::ErrorSelfNotInGroup("Cannot set group admin; self not in group.".into())
+        );
+        bail!("can not set admin because our account is not part of the group");
+    }
+    ensure!(
+        is_contact_in_chat(context, chat_id, contact_id),
+        "contact {} is not a member of the group",
+        contact_id
+    );
+    ensure!(
+        may_manage_group(context, chat_id, DC_CONTACT_ID_SELF),
+        "only group admins can change admins"
+    );
+    if is_group_admin(context, chat_id, contact_id) == admin {
+        return Ok(());
+    }
+
+    let chat = Chat::load_from_db(context, chat_id)?;
+    let contact = Contact::get_by_id(context, contact_id)?;
+    let first_admin = admin && get_group_admins(context, chat_id).is_empty();
+    // in unverified groups, other members take the first admins
+    // only from the message creating the group, see dc_receive_imf()
+    ensure!(
+        !first_admin || !chat.is_promoted() || chat.is_verified(),
+        "the first admins of an unverified group must be set before the group is promoted"
+    );
+    if first_admin {
+        set_group_admins_raw(context, chat_id, &[DC_CONTACT_ID_SELF], true)?;
+    }
+    set_group_admins_raw(context, chat_id, &[contact_id], admin)?;
+
+    if chat.is_promoted() {
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some(context.stock_system_msg(
+            if admin {
+                StockMessage::MsgGrpAdminAdded
+            } else {
+                StockMessage::MsgGrpAdminRemoved
+            },
+            contact.get_addr(),
+            "",
+            DC_CONTACT_ID_SELF,
+        ));
+        msg.param.set_cmd(SystemMessage::GroupAdminsChanged);
+        msg.param.set(Param::Arg, contact.get_addr());
+        msg.param.set_int(Param::Arg2, admin.into());
+        msg.id = send_msg(context, chat_id, &mut msg)?;
+        context.call_cb(Event::MsgsChanged {
+            chat_id,
+            msg_id: msg.id,
+        });
+    }
+    context.call_cb(Event::ChatModified(chat_id));
+    Ok(())
+}
+
 fn set_group_explicitly_left(context: &Context, grpid: impl AsRef<str>) -> Result<(), Error> {
     if !is_group_explicitly_left(context, grpid.as_ref())? {
         sql::execute(
@@ -2144,6 +2371,8 @@ pub fn set_chat_name(
                 context,
                 Event::ErrorSelfNotInGroup("Cannot set chat name; self not in group".into())
             );
+        } else if !may_manage_group(context, chat_id, DC_CONTACT_ID_SELF) {
+            bail!("only group admins can change the group name");
         } else {
             /* we should respect this - whatever we send to the group, it gets discarded anyway! */
             if sql::execute(
@@ -2185,6 +2414,75 @@ pub fn set_chat_name(
     Ok(())
 }
 
+/// Set the description of a group.
+///
+/// Line breaks are replaced by spaces; to remove the description pass an
+/// empty string. If the group is promoted, all members are informed
+/// about the new description.
+pub fn set_chat_description(
+    context: &Context,
+    chat_id: ChatId,
+    new_description: impl AsRef<str>,
+) -> Result<(), Error> {
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+    ensure!(
+        real_group_exists(context, chat_id),
+        "{} is not a group where a description can be set",
+        chat_id
+    );
+    if !is_contact_in_chat(context, chat_id, DC_CONTACT_ID_SELF) {
+        emit_event!(
+            context,
+            Event::ErrorSelfNotInGroup("Cannot set chat description; self not in group".into())
+        );
+        bail!("Failed to set description");
+    }
+    ensure!(
+        may_manage_group(context, chat_id, DC_CONTACT_ID_SELF),
+        "only group admins can change the group description"
+    );
+
+    let new_description = normalize_group_description(new_description);
+    let mut chat = Chat::load_from_db(context, chat_id)?;
+    let old_description = chat.get_description().to_string();
+    if old_description == new_description {
+        return Ok(());
+    }
+    if new_description.is_empty() {
+        chat.param.remove(Param::GroupDescription);
+    } else {
+        chat.param.set(Param::GroupDescription, &new_description);
+    }
+    chat.update_param(context)?;
+
+    if chat.is_promoted() {
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some(context.stock_system_msg(
+            StockMessage::MsgGrpDescriptionChanged,
+            "",
+            "",
+            DC_CONTACT_ID_SELF,
+        ));
+        msg.param.set_cmd(SystemMessage::GroupDescriptionChanged);
+        if !old_description.is_empty() {
+            msg.param.set(Param::Arg, &old_description);
+        }
+        msg.id = send_msg(context, chat_id, &mut msg)?;
+        context.call_cb(Event::MsgsChanged {
+            chat_id,
+            msg_id: msg.id,
+        });
+    }
+    context.call_cb(Event::ChatModified(chat_id));
+    Ok(())
+}
+
+/// Brings a group description to the form stored in the chat params,
+/// which cannot contain line breaks.
+pub(crate) fn normalize_group_description(description: impl AsRef<str>) -> String {
+    dc_collapse_lines(description.as_ref())
+}
+
 /// Set a new profile image for the chat.
 ///
 /// The profile image can only be set when you are a member of the
@@ -2209,6 +2507,10 @@ pub fn set_chat_profile_image(
         );
         bail!("Failed to set profile image");
     }
+    ensure!(
+        may_manage_group(context, chat_id, DC_CONTACT_ID_SELF),
+        "only group admins can change the group image"
+    );
     let mut msg = Message::new(Viewtype::Text);
     msg.param
         .set_int(Param::Cmd, SystemMessage::GroupImageChanged as i32);
@@ -2530,6 +2832,7 @@ mod tests {
                 "color": 15895624,
                 "profile_image": "",
                 "subtitle": "bob@example.com",
+                "description": "",
                 "draft": "",
                 "is_muted": false
             }
@@ -2961,6 +3264,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_set_chat_description() {
+        let t = dummy_context();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        assert_eq!(
+            Chat::load_from_db(&t.ctx, chat_id)
+                .unwrap()
+                .get_description(),
+            ""
+        );
+
+        set_chat_description(&t.ctx, chat_id, "  all about\r\nfoo \n\n").unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat_id).unwrap();
+        assert_eq!(chat.get_description(), "all about foo");
+        assert_eq!(chat.get_info(&t.ctx).unwrap().description, "all about foo");
+
+        set_chat_description(&t.ctx, chat_id, "").unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat_id).unwrap();
+        assert_eq!(chat.get_description(), "");
+        assert!(!chat.param.exists(Param::GroupDescription));
+
+        let bob = Contact::create(&t.ctx, "bob", "bob@example.org").unwrap();
+        let chat_id = create_by_contact_id(&t.ctx, bob).unwrap();
+        assert!(set_chat_description(&t.ctx, chat_id, "bar").is_err());
+    }
+
+    #[test]
+    fn test_set_group_admin() {
+        let t = dummy_context();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let bob = Contact::create(&t.ctx, "bob", "bob@example.org").unwrap();
+        let claire = Contact::create(&t.ctx, "claire", "claire@example.org").unwrap();
+        assert!(add_contact_to_chat(&t.ctx, chat_id, bob));
+        assert!(get_group_admins(&t.ctx, chat_id).is_empty());
+        assert!(may_manage_group(&t.ctx, chat_id, bob));
+
+        // promoting the first admin promotes ourself as well
+        set_group_admin(&t.ctx, chat_id, bob, true).unwrap();
+        assert_eq!(
+            get_group_admins(&t.ctx, chat_id),
+            vec![DC_CONTACT_ID_SELF, bob]
+        );
+        assert!(is_group_admin(&t.ctx, chat_id, DC_CONTACT_ID_SELF));
+        assert!(set_group_admin(&t.ctx, chat_id, claire, true).is_err());
+
+        // without being admin, the group cannot be changed anymore
+        set_group_admin(&t.ctx, chat_id, DC_CONTACT_ID_SELF, false).unwrap();
+        assert_eq!(get_group_admins(&t.ctx, chat_id), vec![bob]);
+        assert!(!may_manage_group(&t.ctx, chat_id, DC_CONTACT_ID_SELF));
+        assert!(set_chat_name(&t.ctx, chat_id, "bar").is_err());
+        assert!(!add_contact_to_chat(&t.ctx, chat_id, claire));
+        assert!(remove_contact_from_chat(&t.ctx, chat_id, bob).is_err());
+        assert!(set_group_admin(&t.ctx, chat_id, DC_CONTACT_ID_SELF, true).is_err());
+        assert_eq!(
+            Chat::load_from_db(&t.ctx, chat_id).unwrap().get_name(),
+            "foo"
+        );
+    }
+
+    #[test]
+    fn test_set_first_group_admin_promoted() {
+        let t = dummy_context();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let bob = Contact::create(&t.ctx, "bob", "bob@example.org").unwrap();
+        assert!(add_contact_to_chat(&t.ctx, chat_id, bob));
+        let mut chat = Chat::load_from_db(&t.ctx, chat_id).unwrap();
+        chat.param.remove(Param::Unpromoted);
+        chat.update_param(&t.ctx).unwrap();
+
+        // other members would not accept the first admins of a promoted, unverified group
+        assert!(set_group_admin(&t.ctx, chat_id, bob, true).is_err());
+        assert!(get_group_admins(&t.ctx, chat_id).is_empty());
+    }
+
+    #[test]
+    fn test_set_chat_verified() {
+        let t = dummy_context();
+        let bob = Contact::create(&t.ctx, "bob", "bob@example.org").unwrap();
+        let chat_id = create_by_contact_id(&t.ctx, bob).unwrap();
+        assert!(!Chat::load_from_db(&t.ctx, chat_id).unwrap().is_verified());
+
+        set_chat_verified(&t.ctx, chat_id, true).unwrap();
+        assert!(Chat::load_from_db(&t.ctx, chat_id).unwrap().is_verified());
+        set_chat_verified(&t.ctx, chat_id, false).unwrap();
+        assert!(!Chat::load_from_db(&t.ctx, chat_id).unwrap().is_verified());
+
+        // groups are verified on creation only
+        let grp_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        assert!(set_chat_verified(&t.ctx, grp_id, true).is_err());
+        assert!(!Chat::load_from_db(&t.ctx, grp_id).unwrap().is_verified());
+    }
+
     #[test]
     fn test_create_same_chat_twice() {
         let context = dummy_context();
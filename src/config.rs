@@ -9,6 +9,7 @@ use crate::context::Context;
 use crate::dc_tools::*;
 use crate::job::*;
 use crate::mimefactory::RECOMMENDED_FILE_SIZE;
+use crate::param::Params;
 use crate::stock::StockMessage;
 use rusqlite::NO_PARAMS;
 
@@ -44,6 +45,9 @@ pub enum Config {
     #[strum(props(default = "1"))]
     E2eeEnabled,
 
+    #[strum(props(default = "0"))] // also change KeyGenType.default() on changes
+    KeyGenType,
+
     #[strum(props(default = "1"))]
     MdnsEnabled,
 
@@ -59,10 +63,26 @@ pub enum Config {
     #[strum(props(default = "1"))]
     MvboxMove,
 
+    /// Additional folders to fetch messages from, one per line.
+    /// Folders already watched by another thread are skipped.
+    ExtraWatchFolders,
+
+    /// Delete messages from the server after this number of seconds,
+    /// the local copies are kept. 0 means never.
+    #[strum(props(default = "0"))]
+    DeleteServerAfter,
+
     #[strum(props(default = "0"))] // also change ShowEmails.default() on changes
     ShowEmails,
 
     SaveMimeHeaders,
+
+    #[strum(props(default = "10"))]
+    LocationSimplifyTolerance,
+
+    #[strum(props(default = "5"))]
+    LocationStationaryRadius,
+
     ConfiguredAddr,
     ConfiguredMailServer,
     ConfiguredMailUser,
@@ -158,6 +178,17 @@ impl Context {
                 interrupt_mvbox_idle(self);
                 ret
             }
+            Config::DeleteServerAfter => {
+                let ret = self.sql.set_raw_config(self, key, value);
+                job_kill_action(self, Action::DeleteExpiredOnServer);
+                job_add(self, Action::DeleteExpiredOnServer, 0, Params::new(), 0);
+                ret
+            }
+            Config::ExtraWatchFolders => {
+                let ret = self.sql.set_raw_config(self, key, value);
+                interrupt_extra_idle(self);
+                ret
+            }
             Config::Selfstatus => {
                 let def = self.stock_str(StockMessage::StatusLine);
                 let val = if value.is_none() || value.unwrap() == def {
@@ -85,6 +85,12 @@ pub fn JobConfigureImap(context: &Context) -> job::Status {
         .unwrap()
         .imap
         .disconnect(context);
+    context
+        .extra_thread
+        .read()
+        .unwrap()
+        .imap
+        .disconnect(context);
     context.smtp.clone().lock().unwrap().disconnect();
     info!(context, "Configure ...",);
 
@@ -7,7 +7,7 @@ use itertools::Itertools;
 use rusqlite;
 
 use crate::aheader::EncryptPreference;
-use crate::chat::ChatId;
+use crate::chat::{self, ChatId};
 use crate::config::Config;
 use crate::constants::*;
 use crate::context::Context;
@@ -71,6 +71,9 @@ pub struct Contact {
 
     /// Parameters as Param::ProfileImage
     pub param: Params,
+
+    /// Timestamp of the last message received from the contact, 0 if unknown.
+    last_seen: i64,
 }
 
 /// Possible origins of a contact.
@@ -166,7 +169,7 @@ pub enum VerifiedStatus {
 impl Contact {
     pub fn load_from_db(context: &Context, contact_id: u32) -> crate::sql::Result<Self> {
         let mut res = context.sql.query_row(
-            "SELECT c.name, c.addr, c.origin, c.blocked, c.authname, c.param
+            "SELECT c.name, c.addr, c.origin, c.blocked, c.authname, c.param, c.last_seen
                FROM contacts c
               WHERE c.id=?;",
             params![contact_id as i32],
@@ -179,6 +182,7 @@ impl Contact {
                     blocked: row.get::<_, Option<i32>>(3)?.unwrap_or_default() != 0,
                     origin: row.get(2)?,
                     param: row.get::<_, String>(5)?.parse().unwrap_or_default(),
+                    last_seen: row.get(6)?,
                 };
                 Ok(contact)
             },
@@ -271,6 +275,7 @@ impl Contact {
 
     /// Check if an e-mail address belongs to a known and unblocked contact.
     /// Known and unblocked contacts will be returned by `dc_get_contacts()`.
+    /// Besides the primary address, all further addresses of the contact are checked.
     ///
     /// To validate an e-mail address independently of the contact database
     /// use `dc_may_be_valid_addr()`.
@@ -290,7 +295,9 @@ impl Contact {
 
         context.sql.query_get_value(
             context,
-            "SELECT id FROM contacts WHERE addr=?1 COLLATE NOCASE AND id>?2 AND origin>=?3 AND blocked=0;",
+            "SELECT id FROM contacts \
+              WHERE (addr=?1 COLLATE NOCASE OR id IN (SELECT contact_id FROM contacts_addrs WHERE addr=?1 COLLATE NOCASE)) \
+                AND id>?2 AND origin>=?3 AND blocked=0;",
             params![
                 addr_normalized,
                 DC_CONTACT_ID_LAST_SPECIAL as i32,
@@ -301,6 +308,9 @@ impl Contact {
 
     /// Lookup a contact and create it if it does not exist yet.
     ///
+    /// The contact is looked up by its primary address and by its further addresses;
+    /// the primary address is not changed if the contact is found by a further address.
+    ///
     /// Returns the contact_id and a `Modifier` value indicating if a modification occured.
     pub fn add_or_lookup(
         context: &Context,
@@ -345,7 +355,9 @@ impl Contact {
         let mut row_id = 0;
 
         if let Ok((id, row_name, row_addr, row_origin, row_authname)) = context.sql.query_row(
-            "SELECT id, name, addr, origin, authname FROM contacts WHERE addr=? COLLATE NOCASE;",
+            "SELECT id, name, addr, origin, authname FROM contacts \
+             WHERE addr=?1 COLLATE NOCASE \
+             OR id IN (SELECT contact_id FROM contacts_addrs WHERE addr=?1 COLLATE NOCASE);",
             params![addr],
             |row| {
                 let row_id = row.get(0)?;
@@ -371,7 +383,7 @@ impl Contact {
             },
         ) {
             row_id = id;
-            if origin as i32 >= row_origin as i32 && addr != row_addr {
+            if origin as i32 >= row_origin as i32 && addr != row_addr && addr_cmp(addr, &row_addr) {
                 update_addr = true;
             }
             if update_name || update_authname || update_addr || origin > row_origin {
@@ -496,6 +508,11 @@ impl Contact {
         let mut ret = Vec::new();
         let flag_verified_only = listflags_has(listflags, DC_GCL_VERIFIED_ONLY);
         let flag_add_self = listflags_has(listflags, DC_GCL_ADD_SELF);
+        let order_by = if listflags_has(listflags, DC_GCL_SORT_BY_LAST_SEEN) {
+            "c.last_seen DESC, LOWER(c.name||c.addr), c.id"
+        } else {
+            "LOWER(c.name||c.addr), c.id"
+        };
 
         if flag_verified_only || query.is_some() {
             let s3str_like_cmd = format!(
@@ -506,15 +523,18 @@ impl Contact {
                     .unwrap_or_default()
             );
             context.sql.query_map(
-                "SELECT c.id FROM contacts c \
-                 LEFT JOIN acpeerstates ps ON c.addr=ps.addr  \
-                 WHERE c.addr!=?1 \
-                 AND c.id>?2 \
-                 AND c.origin>=?3 \
-                 AND c.blocked=0 \
-                 AND (c.name LIKE ?4 OR c.addr LIKE ?5) \
-                 AND (1=?6 OR LENGTH(ps.verified_key_fingerprint)!=0)  \
-                 ORDER BY LOWER(c.name||c.addr),c.id;",
+                format!(
+                    "SELECT c.id FROM contacts c \
+                     LEFT JOIN acpeerstates ps ON c.addr=ps.addr  \
+                     WHERE c.addr!=?1 \
+                     AND c.id>?2 \
+                     AND c.origin>=?3 \
+                     AND c.blocked=0 \
+                     AND (c.name LIKE ?4 OR c.addr LIKE ?5) \
+                     AND (1=?6 OR LENGTH(ps.verified_key_fingerprint)!=0)  \
+                     ORDER BY {};",
+                    order_by
+                ),
                 params![
                     self_addr,
                     DC_CONTACT_ID_LAST_SPECIAL as i32,
@@ -549,7 +569,10 @@ impl Contact {
             add_self = true;
 
             context.sql.query_map(
-                "SELECT id FROM contacts WHERE addr!=?1 AND id>?2 AND origin>=?3 AND blocked=0 ORDER BY LOWER(name||addr),id;",
+                format!(
+                    "SELECT c.id FROM contacts c WHERE c.addr!=?1 AND c.id>?2 AND c.origin>=?3 AND c.blocked=0 ORDER BY {};",
+                    order_by
+                ),
                 params![self_addr, DC_CONTACT_ID_LAST_SPECIAL as i32, 0x100],
                 |row| row.get::<_, i32>(0),
                 |ids| {
@@ -604,7 +627,14 @@ impl Contact {
         let mut ret = String::new();
 
         if let Ok(contact) = Contact::load_from_db(context, contact_id) {
-            let peerstate = Peerstate::from_addr(context, &context.sql, &contact.addr);
+            let peerstate = contact
+                .load_peerstates(context)
+                .into_iter()
+                .find(|peerstate| {
+                    peerstate
+                        .peek_key(PeerstateVerifiedStatus::Unverified)
+                        .is_some()
+                });
             let loginparam = LoginParam::from_database(context, "configured_");
 
             let mut self_key = Key::from_self_public(context, &loginparam.addr, &context.sql);
@@ -630,6 +660,16 @@ impl Contact {
                 let p = context.stock_str(StockMessage::FingerPrints);
                 ret += &format!(" {}:", p);
 
+                let safety_number = match (
+                    &self_key,
+                    peerstate.peek_key(PeerstateVerifiedStatus::Unverified),
+                ) {
+                    (Some(self_key), Some(peer_key)) => Some(dc_safety_number(
+                        &self_key.fingerprint(),
+                        &peer_key.fingerprint(),
+                    )),
+                    _ => None,
+                };
                 let fingerprint_self = self_key
                     .map(|k| k.formatted_fingerprint())
                     .unwrap_or_default();
@@ -658,6 +698,11 @@ impl Contact {
                     );
                     cat_fingerprint(&mut ret, &loginparam.addr, &fingerprint_self, "");
                 }
+                if let Some(safety_number) = safety_number {
+                    ret += "\n\n";
+                    ret +=
+                        &context.stock_string_repl_str(StockMessage::SafetyNumber, safety_number);
+                }
             } else if 0 == loginparam.server_flags & DC_LP_IMAP_SOCKET_PLAIN as i32
                 && 0 == loginparam.server_flags & DC_LP_SMTP_SOCKET_PLAIN as i32
             {
@@ -665,6 +710,14 @@ impl Contact {
             } else {
                 ret += &context.stock_str(StockMessage::EncrNone);
             }
+
+            if contact.last_seen > 0 {
+                ret += "\n\n";
+                ret += &context.stock_string_repl_str(
+                    StockMessage::LastSeen,
+                    dc_timestamp_to_str(contact.last_seen),
+                );
+            }
         }
 
         Ok(ret)
@@ -708,7 +761,15 @@ impl Contact {
                 &context.sql,
                 "DELETE FROM contacts WHERE id=?;",
                 params![contact_id as i32],
-            ) {
+            )
+            .and_then(|_| {
+                sql::execute(
+                    context,
+                    &context.sql,
+                    "DELETE FROM contacts_addrs WHERE contact_id=?;",
+                    params![contact_id as i32],
+                )
+            }) {
                 Ok(_) => {
                     context.call_cb(Event::ContactsChanged(None));
                     return Ok(());
@@ -746,16 +807,279 @@ impl Contact {
         Ok(())
     }
 
+    /// Returns true if messages to the contact must not be sent unencrypted,
+    /// see `Contact::set_encryption_required()`.
+    pub fn is_encryption_required(&self) -> bool {
+        self.param
+            .get_bool(Param::EncryptionRequired)
+            .unwrap_or_default()
+    }
+
+    /// Sets whether messages to the contact must be sent encrypted.
+    ///
+    /// As long as no key of the contact is known,
+    /// messages to chats with the contact are not sent unencrypted but fail.
+    pub fn set_encryption_required(
+        context: &Context,
+        contact_id: u32,
+        required: bool,
+    ) -> Result<()> {
+        ensure!(
+            contact_id > DC_CONTACT_ID_LAST_SPECIAL,
+            "Cannot require encryption for special contact {}",
+            contact_id
+        );
+        let mut contact = Contact::load_from_db(context, contact_id)?;
+        if contact.is_encryption_required() != required {
+            contact
+                .param
+                .set_int(Param::EncryptionRequired, required as i32);
+            contact.update_param(context)?;
+            context.call_cb(Event::ContactsChanged(Some(contact_id)));
+        }
+        Ok(())
+    }
+
     /// Get the ID of the contact.
     pub fn get_id(&self) -> u32 {
         self.id
     }
 
+    /// Get the timestamp of the last message received from the contact,
+    /// including hidden messages as read receipts.
+    /// Returns 0 if no message was received from the contact.
+    pub fn last_seen(&self) -> i64 {
+        self.last_seen
+    }
+
+    /// Records that a message sent at `timestamp` was received from the contact.
+    pub(crate) fn update_last_seen(context: &Context, contact_id: u32, timestamp: i64) {
+        if contact_id <= DC_CONTACT_ID_LAST_SPECIAL {
+            return;
+        }
+        sql::execute(
+            context,
+            &context.sql,
+            "UPDATE contacts SET last_seen=?1 WHERE id=?2 AND last_seen<?1;",
+            params![timestamp, contact_id as i32],
+        )
+        .ok();
+    }
+
     /// Get email address. The email address is always set for a contact.
+    ///
+    /// This is the primary address of the contact, used for sending.
     pub fn get_addr(&self) -> &str {
         &self.addr
     }
 
+    /// Get all email addresses of a contact, the primary address comes first.
+    pub fn get_addrs(context: &Context, contact_id: u32) -> Result<Vec<String>> {
+        let contact = Contact::load_from_db(context, contact_id)?;
+        let mut addrs = vec![contact.addr];
+        if contact_id > DC_CONTACT_ID_LAST_SPECIAL {
+            addrs.extend(context.sql.query_map(
+                "SELECT addr FROM contacts_addrs WHERE contact_id=? ORDER BY id;",
+                params![contact_id as i32],
+                |row| row.get::<_, String>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )?);
+        }
+        Ok(addrs)
+    }
+
+    /// Add a further email address to a contact.
+    ///
+    /// Messages from the address are assigned to the contact,
+    /// messages to the contact are still sent to the primary address.
+    /// If the address already belongs to another contact,
+    /// the contacts have to be merged using `Contact::merge`.
+    ///
+    /// May result in a `#DC_EVENT_CONTACTS_CHANGED` event.
+    pub fn add_addr(context: &Context, contact_id: u32, addr: impl AsRef<str>) -> Result<()> {
+        ensure!(
+            contact_id > DC_CONTACT_ID_LAST_SPECIAL,
+            "Can not add addresses to special contact"
+        );
+        let addr = addr_normalize(addr.as_ref());
+        ensure!(may_be_valid_addr(addr), "Bad address supplied: {:?}", addr);
+        ensure!(
+            !context.is_self_addr(addr)?,
+            "Can not add own address to contact"
+        );
+        Contact::load_from_db(context, contact_id)?;
+
+        let owner = lookup_id_by_any_addr(context, addr);
+        if owner == contact_id {
+            return Ok(());
+        }
+        ensure!(
+            owner == 0,
+            "{} belongs to contact {}, merge the contacts instead",
+            addr,
+            owner
+        );
+
+        sql::execute(
+            context,
+            &context.sql,
+            "INSERT INTO contacts_addrs (contact_id, addr) VALUES (?, ?);",
+            params![contact_id as i32, addr],
+        )?;
+        context.call_cb(Event::ContactsChanged(None));
+        Ok(())
+    }
+
+    /// Remove a further email address from a contact.
+    ///
+    /// The primary address cannot be removed, use `Contact::set_primary_addr` before.
+    pub fn remove_addr(context: &Context, contact_id: u32, addr: impl AsRef<str>) -> Result<()> {
+        let removed = context.sql.execute(
+            "DELETE FROM contacts_addrs WHERE contact_id=? AND addr=? COLLATE NOCASE;",
+            params![contact_id as i32, addr_normalize(addr.as_ref())],
+        )?;
+        ensure!(
+            removed > 0,
+            "{} is not a further address of contact {}",
+            addr.as_ref(),
+            contact_id
+        );
+        context.call_cb(Event::ContactsChanged(None));
+        Ok(())
+    }
+
+    /// Make a further address of a contact the primary address.
+    ///
+    /// The former primary address becomes a further address.
+    pub fn set_primary_addr(
+        context: &Context,
+        contact_id: u32,
+        addr: impl AsRef<str>,
+    ) -> Result<()> {
+        let contact = Contact::load_from_db(context, contact_id)?;
+        let addr = addr_normalize(addr.as_ref());
+        if addr_cmp(addr, &contact.addr) {
+            return Ok(());
+        }
+        let new_primary: String = context
+            .sql
+            .query_get_value(
+                context,
+                "SELECT addr FROM contacts_addrs WHERE contact_id=? AND addr=? COLLATE NOCASE;",
+                params![contact_id as i32, addr],
+            )
+            .ok_or_else(|| format_err!("{} is not an address of contact {}", addr, contact_id))?;
+
+        sql::execute(
+            context,
+            &context.sql,
+            "UPDATE contacts_addrs SET addr=? WHERE contact_id=? AND addr=?;",
+            params![&contact.addr, contact_id as i32, &new_primary],
+        )?;
+        sql::execute(
+            context,
+            &context.sql,
+            "UPDATE contacts SET addr=? WHERE id=?;",
+            params![&new_primary, contact_id as i32],
+        )?;
+        context.call_cb(Event::ContactsChanged(Some(contact_id)));
+        Ok(())
+    }
+
+    /// Merge two contacts that belong to the same person.
+    ///
+    /// All addresses of `other_id` become further addresses of `contact_id`;
+    /// messages, chat memberships and locations are moved
+    /// and the one-to-one chats are combined.
+    /// Finally, `other_id` is deleted.
+    /// The database changes are done in one transaction.
+    ///
+    /// The peerstates of `other_id` move along with its addresses:
+    /// they stay keyed by the address they were seen for,
+    /// but now belong to `contact_id`, so its encryption info
+    /// and verification state take them into account.
+    ///
+    /// May result in `#DC_EVENT_CONTACTS_CHANGED` and `#DC_EVENT_MSGS_CHANGED` events.
+    pub fn merge(context: &Context, contact_id: u32, other_id: u32) -> Result<()> {
+        ensure!(
+            contact_id > DC_CONTACT_ID_LAST_SPECIAL && other_id > DC_CONTACT_ID_LAST_SPECIAL,
+            "Can not merge special contacts"
+        );
+        ensure!(contact_id != other_id, "Can not merge contact with itself");
+        let contact = Contact::load_from_db(context, contact_id)?;
+        let other = Contact::load_from_db(context, other_id)?;
+
+        let (chat_id, _) = chat::lookup_by_contact_id(context, contact_id).unwrap_or_default();
+        let (other_chat_id, _) = chat::lookup_by_contact_id(context, other_id).unwrap_or_default();
+
+        context.sql.with_transaction(|conn| {
+            // combine the one-to-one chats, if both exist, the messages are moved
+            if !chat_id.is_unset() && !other_chat_id.is_unset() {
+                conn.execute(
+                    "UPDATE msgs SET chat_id=? WHERE chat_id=?;",
+                    params![chat_id, other_chat_id],
+                )?;
+            } else if !other_chat_id.is_unset() {
+                conn.execute(
+                    "UPDATE chats SET name=? WHERE id=?;",
+                    params![contact.get_display_name(), other_chat_id],
+                )?;
+            }
+
+            // chats with both contacts keep only one membership
+            conn.execute(
+                "DELETE FROM chats_contacts WHERE contact_id=?1 \
+                 AND chat_id IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?2);",
+                params![other_id as i32, contact_id as i32],
+            )?;
+            for statement in &[
+                "UPDATE chats_contacts SET contact_id=?1 WHERE contact_id=?2;",
+                "UPDATE msgs SET from_id=?1 WHERE from_id=?2;",
+                "UPDATE msgs SET to_id=?1 WHERE to_id=?2;",
+                "UPDATE msgs_mdns SET contact_id=?1 WHERE contact_id=?2;",
+                "UPDATE locations SET from_id=?1 WHERE from_id=?2;",
+                "UPDATE contacts_addrs SET contact_id=?1 WHERE contact_id=?2;",
+            ] {
+                conn.execute(statement, params![contact_id as i32, other_id as i32])?;
+            }
+            conn.execute(
+                "INSERT INTO contacts_addrs (contact_id, addr) VALUES (?, ?);",
+                params![contact_id as i32, &other.addr],
+            )?;
+
+            if other.origin > contact.origin {
+                conn.execute(
+                    "UPDATE contacts SET origin=? WHERE id=?;",
+                    params![other.origin, contact_id as i32],
+                )?;
+            }
+            if contact.name.is_empty() && !other.name.is_empty() {
+                conn.execute(
+                    "UPDATE contacts SET name=? WHERE id=?;",
+                    params![&other.name, contact_id as i32],
+                )?;
+            }
+            conn.execute("DELETE FROM contacts WHERE id=?;", params![other_id as i32])?;
+            Ok(())
+        })?;
+
+        // the messages are moved already, deleting the chat cleans up its jobs and blobs
+        if !chat_id.is_unset() && !other_chat_id.is_unset() {
+            other_chat_id.delete(context)?;
+        }
+
+        info!(context, "merged contact {} into {}", other_id, contact_id);
+        context.call_cb(Event::ContactsChanged(None));
+        context.call_cb(Event::MsgsChanged {
+            chat_id: ChatId::new(0),
+            msg_id: MsgId::new(0),
+        });
+        Ok(())
+    }
+
     /// Get name authorized by the contact.
     pub fn get_authname(&self) -> &str {
         &self.authname
@@ -862,16 +1186,27 @@ impl Contact {
             }
         }
 
-        let peerstate = Peerstate::from_addr(context, &context.sql, &self.addr);
-        if let Some(ps) = peerstate {
-            if ps.verified_key.is_some() {
-                return VerifiedStatus::BidirectVerified;
-            }
+        // the contact is verified if any of its addresses is, eg. after a merge
+        if self
+            .load_peerstates(context)
+            .iter()
+            .any(|ps| ps.verified_key.is_some())
+        {
+            return VerifiedStatus::BidirectVerified;
         }
 
         VerifiedStatus::Unverified
     }
 
+    /// Loads the peerstates of all addresses of the contact, the primary address first.
+    fn load_peerstates<'a>(&self, context: &'a Context) -> Vec<Peerstate<'a>> {
+        Contact::get_addrs(context, self.id)
+            .unwrap_or_else(|_| vec![self.addr.clone()])
+            .iter()
+            .filter_map(|addr| Peerstate::from_addr(context, &context.sql, addr))
+            .collect()
+    }
+
     pub fn addr_equals_contact(context: &Context, addr: impl AsRef<str>, contact_id: u32) -> bool {
         if addr.as_ref().is_empty() {
             return false;
@@ -883,6 +1218,7 @@ impl Contact {
                 if contact.addr == normalized_addr {
                     return true;
                 }
+                return lookup_id_by_any_addr(context, normalized_addr) == contact_id;
             }
         }
 
@@ -929,6 +1265,20 @@ impl Contact {
     }
 }
 
+/// Returns the ID of the contact owning an address, including blocked and hidden contacts,
+/// or 0 if there is no such contact.
+fn lookup_id_by_any_addr(context: &Context, addr: &str) -> u32 {
+    context
+        .sql
+        .query_get_value(
+            context,
+            "SELECT id FROM contacts WHERE addr=?1 COLLATE NOCASE \
+             UNION SELECT contact_id FROM contacts_addrs WHERE addr=?1 COLLATE NOCASE;",
+            params![addr],
+        )
+        .unwrap_or_default()
+}
+
 /// Extracts first name from full name.
 fn get_first_name(full_name: &str) -> &str {
     full_name.splitn(2, ' ').next().unwrap_or_default()
@@ -1111,6 +1461,7 @@ fn split_address_book(book: &str) -> Vec<(&str, &str)> {
 mod tests {
     use super::*;
 
+    use crate::chat::Chat;
     use crate::test_utils::*;
 
     #[test]
@@ -1339,4 +1690,126 @@ mod tests {
         assert!(addr_cmp(" aa@aa.ORG ", "AA@AA.ORG"));
         assert!(addr_cmp(" mailto:AA@AA.ORG", "Aa@Aa.orG"));
     }
+
+    #[test]
+    fn test_last_seen() {
+        let t = dummy_context();
+        let bob = Contact::create(&t.ctx, "Bob", "bob@example.org").unwrap();
+        let claire = Contact::create(&t.ctx, "Claire", "claire@example.org").unwrap();
+        assert_eq!(Contact::load_from_db(&t.ctx, bob).unwrap().last_seen(), 0);
+        assert_eq!(
+            Contact::get_all(&t.ctx, DC_GCL_SORT_BY_LAST_SEEN as u32, None::<&str>).unwrap(),
+            vec![bob, claire]
+        );
+
+        Contact::update_last_seen(&t.ctx, claire, 1000);
+        Contact::update_last_seen(&t.ctx, claire, 500); // older messages do not count
+        assert_eq!(
+            Contact::load_from_db(&t.ctx, claire).unwrap().last_seen(),
+            1000
+        );
+        assert_eq!(
+            Contact::get_all(&t.ctx, DC_GCL_SORT_BY_LAST_SEEN as u32, None::<&str>).unwrap(),
+            vec![claire, bob]
+        );
+        assert_eq!(
+            Contact::get_all(&t.ctx, 0, None::<&str>).unwrap(),
+            vec![bob, claire]
+        );
+    }
+
+    #[test]
+    fn test_contact_addrs() {
+        let t = dummy_context();
+        let bob = Contact::create(&t.ctx, "Bob", "bob@work.example").unwrap();
+        let claire = Contact::create(&t.ctx, "Claire", "claire@example.org").unwrap();
+
+        Contact::add_addr(&t.ctx, bob, "Bob@Home.example").unwrap();
+        assert!(Contact::add_addr(&t.ctx, bob, "claire@example.org").is_err());
+        assert_eq!(
+            Contact::get_addrs(&t.ctx, bob).unwrap(),
+            vec!["bob@work.example", "Bob@Home.example"]
+        );
+        assert_eq!(Contact::lookup_id_by_addr(&t.ctx, "bob@home.example"), bob);
+
+        // the peerstate of the home address is taken into account now
+        let contact = Contact::load_from_db(&t.ctx, bob).unwrap();
+        assert_eq!(
+            contact.is_verified(&t.ctx),
+            VerifiedStatus::BidirectVerified
+        );
+        configure_alice_keypair(&t.ctx);
+        let encrinfo = Contact::get_encrinfo(&t.ctx, bob).unwrap();
+        assert!(encrinfo.contains("bob@home.example"));
+        assert!(encrinfo.contains(&key.formatted_fingerprint()));
+        let (contact_id, _) =
+            Contact::add_or_lookup(&t.ctx, "", "bob@home.example", Origin::IncomingUnknownFrom)
+                .unwrap();
+        assert_eq!(contact_id, bob);
+        assert!(Contact::addr_equals_contact(
+            &t.ctx,
+            "bob@home.example",
+            bob
+        ));
+        assert!(!Contact::addr_equals_contact(
+            &t.ctx,
+            "bob@home.example",
+            claire
+        ));
+
+        // the primary address is used for sending
+        Contact::set_primary_addr(&t.ctx, bob, "bob@home.example").unwrap();
+        let contact = Contact::load_from_db(&t.ctx, bob).unwrap();
+        assert_eq!(contact.get_addr(), "Bob@Home.example");
+        assert!(Contact::remove_addr(&t.ctx, bob, "bob@home.example").is_err());
+        Contact::remove_addr(&t.ctx, bob, "bob@work.example").unwrap();
+        assert_eq!(
+            Contact::get_addrs(&t.ctx, bob).unwrap(),
+            vec!["Bob@Home.example"]
+        );
+        assert_eq!(Contact::lookup_id_by_addr(&t.ctx, "bob@work.example"), 0);
+    }
+
+    #[test]
+    fn test_merge_contacts() {
+        let t = dummy_context();
+        let bob = Contact::create(&t.ctx, "Bob", "bob@work.example").unwrap();
+        let bob2 = Contact::create(&t.ctx, "", "bob@home.example").unwrap();
+        let chat_id = chat::create_by_contact_id(&t.ctx, bob).unwrap();
+        let chat2_id = chat::create_by_contact_id(&t.ctx, bob2).unwrap();
+
+        // only the home address was verified
+        let key = Key::from(bob_keypair().public);
+        let mut peerstate = Peerstate::new(&t.ctx, "bob@home.example".to_string());
+        peerstate.public_key_fingerprint = Some(key.fingerprint());
+        peerstate.public_key = Some(key.clone());
+        peerstate.verified_key_fingerprint = Some(key.fingerprint());
+        peerstate.verified_key = Some(key.clone());
+        peerstate.save_to_db(&t.ctx.sql, true).unwrap();
+        let contact = Contact::load_from_db(&t.ctx, bob).unwrap();
+        assert_eq!(contact.is_verified(&t.ctx), VerifiedStatus::Unverified);
+
+        chat::add_info_msg(&t.ctx, chat_id, "work");
+        chat::add_info_msg(&t.ctx, chat2_id, "home");
+        let group_id =
+            chat::create_group_chat(&t.ctx, VerifiedStatus::Unverified, "group").unwrap();
+        assert!(chat::add_contact_to_chat(&t.ctx, group_id, bob));
+        assert!(chat::add_contact_to_chat(&t.ctx, group_id, bob2));
+
+        assert!(Contact::merge(&t.ctx, bob, bob).is_err());
+        Contact::merge(&t.ctx, bob, bob2).unwrap();
+        assert!(Contact::load_from_db(&t.ctx, bob2).is_err());
+        assert_eq!(
+            Contact::get_addrs(&t.ctx, bob).unwrap(),
+            vec!["bob@work.example", "bob@home.example"]
+        );
+        assert_eq!(Contact::lookup_id_by_addr(&t.ctx, "bob@home.example"), bob);
+
+        assert!(Chat::load_from_db(&t.ctx, chat2_id).is_err());
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).len(), 2);
+        assert_eq!(
+            chat::get_chat_contacts(&t.ctx, group_id),
+            vec![bob, DC_CONTACT_ID_SELF]
+        );
+    }
 }
@@ -11,12 +11,12 @@ use crate::constants::*;
 use crate::contact::*;
 use crate::error::*;
 use crate::events::Event;
+use crate::filter::CompiledRule;
 use crate::imap::*;
 use crate::job::*;
 use crate::job_thread::JobThread;
 use crate::key::Key;
 use crate::login_param::LoginParam;
-use crate::lot::Lot;
 use crate::message::{self, Message, MessengerMessage, MsgId};
 use crate::param::Params;
 use crate::smtp::Smtp;
@@ -44,6 +44,7 @@ pub struct Context {
     pub inbox_thread: Arc<RwLock<JobThread>>,
     pub sentbox_thread: Arc<RwLock<JobThread>>,
     pub mvbox_thread: Arc<RwLock<JobThread>>,
+    pub extra_thread: Arc<RwLock<JobThread>>,
     pub smtp: Arc<Mutex<Smtp>>,
     pub smtp_state: Arc<(Mutex<SmtpState>, Condvar)>,
     pub oauth2_critical: Arc<Mutex<()>>,
@@ -51,12 +52,17 @@ pub struct Context {
     cb: Box<ContextCallback>,
     pub os_name: Option<String>,
     pub cmdline_sel_chat_id: Arc<RwLock<ChatId>>,
-    pub bob: Arc<RwLock<BobStatus>>,
     pub last_smeared_timestamp: RwLock<i64>,
     pub running_state: Arc<RwLock<RunningState>>,
     /// Mutex to avoid generating the key for the user more than once.
     pub generating_key_mutex: Mutex<()>,
+    /// Passphrase for the pending import/export job.
+    ///
+    /// It is only kept in memory so that it is never written to the jobs table.
+    pub(crate) imex_passphrase: Mutex<Option<String>>,
     pub translated_stockstrings: RwLock<HashMap<usize, String>>,
+    /// Compiled filter rules, loaded on first use.
+    pub(crate) filter_rules: RwLock<Option<Arc<Vec<CompiledRule>>>>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -83,6 +89,19 @@ pub fn get_info() -> HashMap<&'static str, String> {
 impl Context {
     /// Creates new context.
     pub fn new(cb: Box<ContextCallback>, os_name: String, dbfile: PathBuf) -> Result<Context> {
+        Context::new_with_passphrase(cb, os_name, dbfile, None)
+    }
+
+    /// Creates new context with the database encrypted by `passphrase`.
+    ///
+    /// If the database does not exist yet, it is created encrypted.
+    /// Requires the `sqlcipher` feature if a passphrase is given.
+    pub fn new_with_passphrase(
+        cb: Box<ContextCallback>,
+        os_name: String,
+        dbfile: PathBuf,
+        passphrase: Option<String>,
+    ) -> Result<Context> {
         pretty_env_logger::try_init_timed().ok();
 
         let mut blob_fname = OsString::new();
@@ -92,7 +111,7 @@ impl Context {
         if !blobdir.exists() {
             std::fs::create_dir_all(&blobdir)?;
         }
-        Context::with_blobdir(cb, os_name, dbfile, blobdir)
+        Context::with_blobdir_and_passphrase(cb, os_name, dbfile, blobdir, passphrase)
     }
 
     pub fn with_blobdir(
@@ -100,6 +119,16 @@ impl Context {
         os_name: String,
         dbfile: PathBuf,
         blobdir: PathBuf,
+    ) -> Result<Context> {
+        Context::with_blobdir_and_passphrase(cb, os_name, dbfile, blobdir, None)
+    }
+
+    pub fn with_blobdir_and_passphrase(
+        cb: Box<ContextCallback>,
+        os_name: String,
+        dbfile: PathBuf,
+        blobdir: PathBuf,
+        passphrase: Option<String>,
     ) -> Result<Context> {
         ensure!(
             blobdir.is_dir(),
@@ -116,7 +145,6 @@ impl Context {
             smtp: Arc::new(Mutex::new(Smtp::new())),
             smtp_state: Arc::new((Mutex::new(Default::default()), Condvar::new())),
             oauth2_critical: Arc::new(Mutex::new(())),
-            bob: Arc::new(RwLock::new(Default::default())),
             last_smeared_timestamp: RwLock::new(0),
             cmdline_sel_chat_id: Arc::new(RwLock::new(ChatId::new(0))),
             inbox_thread: Arc::new(RwLock::new(JobThread::new(
@@ -134,12 +162,20 @@ impl Context {
                 "configured_mvbox_folder",
                 Imap::new(),
             ))),
+            extra_thread: Arc::new(RwLock::new(JobThread::new(
+                "EXTRA",
+                "extra_watch_folders",
+                Imap::new(),
+            ))),
             probe_imap_network: Arc::new(RwLock::new(false)),
             perform_inbox_jobs_needed: Arc::new(RwLock::new(false)),
             generating_key_mutex: Mutex::new(()),
+            imex_passphrase: Mutex::new(None),
             translated_stockstrings: RwLock::new(HashMap::new()),
+            filter_rules: RwLock::new(None),
         };
 
+        ctx.sql.set_passphrase(passphrase);
         ensure!(
             ctx.sql.open(&ctx, &ctx.dbfile, false),
             "Failed opening sqlite database"
@@ -148,6 +184,15 @@ impl Context {
         Ok(ctx)
     }
 
+    /// Changes the passphrase the database is encrypted with.
+    ///
+    /// Pass `None` to remove the encryption.
+    /// Backups exported afterwards are encrypted with the new passphrase.
+    pub fn change_db_passphrase(&self, passphrase: Option<String>) -> Result<()> {
+        self.sql
+            .change_passphrase(self, self.get_dbfile(), passphrase)
+    }
+
     /// Returns database file path.
     pub fn get_dbfile(&self) -> &Path {
         self.dbfile.as_path()
@@ -282,6 +327,7 @@ impl Context {
         res.insert("number_of_contacts", contacts.to_string());
         res.insert("database_dir", self.get_dbfile().display().to_string());
         res.insert("database_version", dbversion.to_string());
+        res.insert("database_encrypted", self.sql.is_encrypted().to_string());
         res.insert("blobdir", self.get_blobdir().display().to_string());
         res.insert("display_name", displayname.unwrap_or_else(|| unset.into()));
         res.insert(
@@ -462,6 +508,8 @@ impl Drop for Context {
         self.sentbox_thread.read().unwrap().imap.disconnect(self);
         info!(self, "disconnecting mvbox-thread",);
         self.mvbox_thread.read().unwrap().imap.disconnect(self);
+        info!(self, "disconnecting extra-thread",);
+        self.extra_thread.read().unwrap().imap.disconnect(self);
         info!(self, "disconnecting SMTP");
         self.smtp.clone().lock().unwrap().disconnect();
         self.sql.close(self);
@@ -477,13 +525,6 @@ impl Default for RunningState {
     }
 }
 
-#[derive(Debug, Default)]
-pub struct BobStatus {
-    pub expects: i32,
-    pub status: i32,
-    pub qr_scan: Option<Lot>,
-}
-
 #[derive(Debug, PartialEq)]
 pub enum PerformJobsNeeded {
     Not,
@@ -580,6 +621,61 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    #[cfg(not(feature = "sqlcipher"))]
+    fn test_passphrase_requires_sqlcipher() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbfile = tmp.path().join("db.sqlite");
+        let res = Context::new_with_passphrase(
+            Box::new(|_, _| ()),
+            "FakeOS".into(),
+            dbfile.clone(),
+            Some("secret".into()),
+        );
+        assert!(res.is_err());
+
+        let ctx = Context::new(Box::new(|_, _| ()), "FakeOS".into(), dbfile).unwrap();
+        assert!(ctx.change_db_passphrase(Some("secret".into())).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "sqlcipher")]
+    fn test_db_passphrase() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbfile = tmp.path().join("db.sqlite");
+        let open = |passphrase: Option<&str>| {
+            Context::new_with_passphrase(
+                Box::new(|_, _| ()),
+                "FakeOS".into(),
+                dbfile.clone(),
+                passphrase.map(|p| p.to_string()),
+            )
+        };
+
+        let ctx = open(Some("secret")).unwrap();
+        ctx.set_config(Config::Displayname, Some("Alice")).unwrap();
+        assert_eq!(ctx.get_info().get("database_encrypted").unwrap(), "true");
+        drop(ctx);
+
+        assert!(open(None).is_err());
+        assert!(open(Some("wrong")).is_err());
+
+        let ctx = open(Some("secret")).unwrap();
+        assert_eq!(ctx.get_config(Config::Displayname).unwrap(), "Alice");
+        ctx.change_db_passphrase(Some("other".into())).unwrap();
+        assert_eq!(ctx.get_config(Config::Displayname).unwrap(), "Alice");
+        drop(ctx);
+        assert!(open(Some("secret")).is_err());
+
+        let ctx = open(Some("other")).unwrap();
+        ctx.change_db_passphrase(None).unwrap();
+        drop(ctx);
+
+        let ctx = open(None).unwrap();
+        assert_eq!(ctx.get_config(Config::Displayname).unwrap(), "Alice");
+        assert_eq!(ctx.get_info().get("database_encrypted").unwrap(), "false");
+    }
+
     #[test]
     fn no_crashes_on_context_deref() {
         let t = dummy_context();
@@ -11,6 +11,7 @@ use crate::context::Context;
 use crate::dc_tools::*;
 use crate::error::Result;
 use crate::events::Event;
+use crate::filter::{self, FilterAction};
 use crate::headerdef::HeaderDef;
 use crate::job::*;
 use crate::message::{self, MessageState, MessengerMessage, MsgId};
@@ -103,6 +104,16 @@ pub fn dc_receive_imf(
             (0, false, Origin::Unknown)
         };
     let incoming = from_id != DC_CONTACT_ID_SELF;
+    if incoming {
+        // also hidden messages and read receipts show that the contact is active
+        let now = time();
+        let seen_timestamp = if sent_timestamp > 0 && sent_timestamp < now {
+            sent_timestamp
+        } else {
+            now
+        };
+        Contact::update_last_seen(context, from_id, seen_timestamp);
+    }
 
     let mut to_ids = ContactIds::new();
     for header_def in &[HeaderDef::To, HeaderDef::Cc] {
@@ -287,6 +298,7 @@ fn add_parts(
     let mut mime_in_reply_to = String::new();
     let mut mime_references = String::new();
     let mut incoming_origin = incoming_origin;
+    let mut filter_action = None;
 
     // check, if the mail is already in our database - if so, just update the folder/uid
     // (if the mail was moved around) and finish. (we may get a mail twice eg. if it is
@@ -363,13 +375,51 @@ fn add_parts(
                 }
                 Err(err) => {
                     *hidden = true;
-                    context.bob.write().unwrap().status = 0; // secure-join failed
-                    context.stop_ongoing();
+                    securejoin::dc_abort_securejoin(context, from_id); // secure-join failed
                     error!(context, "Error in Secure-Join message handling: {}", err);
                 }
             }
         }
 
+        // filter rules are evaluated before the message is assigned to a chat
+        if from_id > DC_CONTACT_ID_LAST_SPECIAL && mime_parser.get(HeaderDef::SecureJoin).is_none()
+        {
+            if let Some(rule) = filter::find_matching_rule(context, mime_parser, from_id) {
+                info!(
+                    context,
+                    "Filter rule {} matches, action: {}", rule.id, rule.action
+                );
+                context.call_cb(Event::FilterRuleMatched {
+                    rule_id: rule.id,
+                    contact_id: from_id,
+                });
+                filter_action = Some(rule.action);
+            }
+        }
+        let accept_sender = match filter_action {
+            Some(FilterAction::Block) => {
+                Contact::block(context, from_id);
+                *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+                false
+            }
+            Some(FilterAction::Trash) => {
+                *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+                false
+            }
+            Some(FilterAction::Accept) | Some(FilterAction::Archive) => {
+                // accepting overrides the show_emails setting
+                if chat_id.is_trash() {
+                    *chat_id = ChatId::new(0);
+                }
+                allow_creation = true;
+                if filter_action == Some(FilterAction::Archive) && state == MessageState::InFresh {
+                    state = MessageState::InNoticed;
+                }
+                true
+            }
+            None => false,
+        };
+
         let (test_normal_chat_id, test_normal_chat_id_blocked) =
             chat::lookup_by_contact_id(context, from_id).unwrap_or_default();
 
@@ -379,12 +429,13 @@ fn add_parts(
             // try to create a group
             // (groups appear automatically only if the _sender_ is known, see core issue #54)
 
-            let create_blocked =
-                if !test_normal_chat_id.is_unset() && test_normal_chat_id_blocked == Blocked::Not {
-                    Blocked::Not
-                } else {
-                    Blocked::Deaddrop
-                };
+            let create_blocked = if accept_sender
+                || !test_normal_chat_id.is_unset() && test_normal_chat_id_blocked == Blocked::Not
+            {
+                Blocked::Not
+            } else {
+                Blocked::Deaddrop
+            };
 
             let (new_chat_id, new_chat_id_blocked) = create_or_lookup_group(
                 context,
@@ -407,7 +458,7 @@ fn add_parts(
 
         if chat_id.is_unset() {
             // check if the message belongs to a mailing list
-            if mime_parser.is_mailinglist_message() {
+            if mime_parser.is_mailinglist_message() && !accept_sender {
                 *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
                 info!(context, "Message belongs to a mailing list and is ignored.",);
             }
@@ -415,7 +466,7 @@ fn add_parts(
 
         if chat_id.is_unset() {
             // try to create a normal chat
-            let create_blocked = if from_id == to_id {
+            let create_blocked = if from_id == to_id || accept_sender {
                 Blocked::Not
             } else {
                 Blocked::Deaddrop
@@ -454,6 +505,19 @@ fn add_parts(
             *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
         }
 
+        // messages in verified one-to-one chats must be sent with a verified key
+        if let Ok(chat) = Chat::load_from_db(context, *chat_id) {
+            if chat.typ == Chattype::Single && chat.is_verified() {
+                if let Err(err) =
+                    check_verified_properties(context, mime_parser, from_id, &ContactIds::new())
+                {
+                    warn!(context, "verification problem: {}", err);
+                    let s = format!("{}. See 'Info' for more details", err);
+                    mime_parser.repl_msg_by_error(s);
+                }
+            }
+        }
+
         // if the chat_id is blocked,
         // for unknown senders and non-delta-messages set the state to NOTICED
         // to not result in a chatlist-contact-request (this would require the state FRESH)
@@ -547,8 +611,12 @@ fn add_parts(
         &mut rcvd_timestamp,
     );
 
-    // unarchive chat
-    chat_id.unarchive(context)?;
+    if filter_action == Some(FilterAction::Archive) && !chat_id.is_special() {
+        chat_id.set_visibility(context, chat::ChatVisibility::Archived)?;
+    } else {
+        // unarchive chat
+        chat_id.unarchive(context)?;
+    }
 
     // if the mime-headers should be saved, find out its size
     // (the mime-header ends with an empty line)
@@ -771,6 +839,7 @@ fn create_or_lookup_group(
     let mut X_MrRemoveFromGrp = None;
     let mut X_MrAddToGrp = None;
     let mut X_MrGrpNameChanged = false;
+    let mut X_MrGrpDescriptionChanged = false;
     let mut better_msg: String = From::from("");
 
     if mime_parser.is_system_message == SystemMessage::LocationStreamingEnabled {
@@ -859,6 +928,34 @@ fn create_or_lookup_group(
                 );
 
                 mime_parser.is_system_message = SystemMessage::GroupNameChanged;
+            } else if mime_parser
+                .get(HeaderDef::ChatGroupDescriptionChanged)
+                .is_some()
+            {
+                X_MrGrpDescriptionChanged = true;
+                better_msg = context.stock_system_msg(
+                    StockMessage::MsgGrpDescriptionChanged,
+                    "",
+                    "",
+                    from_id as u32,
+                );
+                mime_parser.is_system_message = SystemMessage::GroupDescriptionChanged;
+            } else if let Some(addr) = mime_parser.get(HeaderDef::ChatGroupAdminAdded).cloned() {
+                mime_parser.is_system_message = SystemMessage::GroupAdminsChanged;
+                better_msg = context.stock_system_msg(
+                    StockMessage::MsgGrpAdminAdded,
+                    &addr,
+                    "",
+                    from_id as u32,
+                );
+            } else if let Some(addr) = mime_parser.get(HeaderDef::ChatGroupAdminRemoved).cloned() {
+                mime_parser.is_system_message = SystemMessage::GroupAdminsChanged;
+                better_msg = context.stock_system_msg(
+                    StockMessage::MsgGrpAdminRemoved,
+                    &addr,
+                    "",
+                    from_id as u32,
+                );
             } else if let Some(value) = mime_parser.get(HeaderDef::ChatContent) {
                 if value == "group-avatar-changed" {
                     if let Some(avatar_action) = &mime_parser.group_avatar {
@@ -884,6 +981,9 @@ fn create_or_lookup_group(
     // check, if we have a chat with this group ID
     let (mut chat_id, chat_id_verified, _blocked) = chat::get_chat_id_by_grpid(context, &grpid)
         .unwrap_or((ChatId::new(0), false, Blocked::Not));
+    // the sender of a message to a verified group is authenticated
+    // unless the verification fails
+    let mut sender_verified = chat_id_verified;
     if !chat_id.is_error() {
         if chat_id_verified {
             if let Err(err) =
@@ -892,6 +992,7 @@ fn create_or_lookup_group(
                 warn!(context, "verification problem: {}", err);
                 let s = format!("{}. See 'Info' for more details", err);
                 mime_parser.repl_msg_by_error(s);
+                sender_verified = false;
             }
         }
         if !chat::is_contact_in_chat(context, chat_id, from_id as u32) {
@@ -984,6 +1085,51 @@ fn create_or_lookup_group(
 
     // XXX insert code in a different PR :)
 
+    let group_created = recreate_member_list;
+
+    // once a group has admins, only admins can change members, name, image and admins;
+    // everyone may leave the group.
+    let may_manage = group_created || chat::may_manage_group(context, chat_id, from_id);
+    // the admins are taken from the message creating the group and can then be changed by admins only.
+    // the first admins of an existing group are only accepted in verified groups,
+    // in other groups, anyone knowing the group ID could claim to be admin.
+    // note that this does not protect against forged sender addresses in unverified groups.
+    let may_change_admins = group_created
+        || (!chat_id_verified || sender_verified)
+            && chat::is_contact_in_chat(context, chat_id, from_id)
+            && (chat::is_group_admin(context, chat_id, from_id)
+                || sender_verified && chat::get_group_admins(context, chat_id).is_empty());
+    // a non-admin leaving the group only removes themself,
+    // the member list must not be recreated from the recipients of such a message.
+    let mut sender_left_group = false;
+    if !may_manage {
+        sender_left_group = X_MrRemoveFromGrp
+            .as_ref()
+            .map(|addr| Contact::addr_equals_contact(context, addr, from_id))
+            .unwrap_or_default();
+        X_MrRemoveFromGrp = None;
+        if X_MrAddToGrp.is_some()
+            || !sender_left_group && mime_parser.get(HeaderDef::ChatGroupMemberRemoved).is_some()
+            || X_MrGrpNameChanged
+            || X_MrGrpDescriptionChanged
+            || mime_parser.is_system_message == SystemMessage::GroupAdminsChanged
+            || mime_parser.is_system_message == SystemMessage::GroupImageChanged
+        {
+            warn!(
+                context,
+                "ignoring group changes by non-admin {} in {}", from_id, chat_id
+            );
+            mime_parser.repl_msg_by_error(format!(
+                "{} Ignored, only group admins can change the group.",
+                better_msg
+            ));
+        }
+        X_MrAddToGrp = None;
+        X_MrGrpNameChanged = false;
+        X_MrGrpDescriptionChanged = false;
+        mime_parser.group_avatar = None;
+    }
+
     // execute group commands
     if X_MrAddToGrp.is_some() || X_MrRemoveFromGrp.is_some() {
         recreate_member_list = true;
@@ -1004,6 +1150,42 @@ fn create_or_lookup_group(
             }
         }
     }
+    if sender_left_group {
+        info!(context, "non-admin {} left chat {}", from_id, chat_id);
+        if sql::execute(
+            context,
+            &context.sql,
+            "DELETE FROM chats_contacts WHERE chat_id=? AND contact_id=?;",
+            params![chat_id, from_id as i32],
+        )
+        .is_ok()
+        {
+            send_EVENT_CHAT_MODIFIED = true;
+        }
+    }
+    // the description is sent with every group message,
+    // without an explicit change it is only taken if the group has no description yet,
+    // eg. because we were added to the group or missed the change.
+    let description = chat::normalize_group_description(
+        mime_parser
+            .get(HeaderDef::ChatGroupDescription)
+            .map(|s| s.as_str())
+            .unwrap_or_default(),
+    );
+    if X_MrGrpDescriptionChanged || (may_manage && !description.is_empty()) {
+        if let Ok(mut chat) = Chat::load_from_db(context, chat_id) {
+            if X_MrGrpDescriptionChanged || chat.get_description().is_empty() {
+                info!(context, "updating group description for chat {}", chat_id);
+                if description.is_empty() {
+                    chat.param.remove(Param::GroupDescription);
+                } else {
+                    chat.param.set(Param::GroupDescription, description);
+                }
+                chat.update_param(context)?;
+                send_EVENT_CHAT_MODIFIED = true;
+            }
+        }
+    }
     if let Some(avatar_action) = &mime_parser.group_avatar {
         info!(context, "group-avatar change for {}", chat_id);
         if let Ok(mut chat) = Chat::load_from_db(context, chat_id) {
@@ -1028,6 +1210,7 @@ fn create_or_lookup_group(
         // https://github.com/deltachat/deltachat-core/issues/127
 
         let skip = X_MrRemoveFromGrp.as_ref();
+        let admins = chat::get_group_admins(context, chat_id);
         sql::execute(
             context,
             &context.sql,
@@ -1053,9 +1236,47 @@ fn create_or_lookup_group(
                 chat::add_to_chat_contacts_table(context, chat_id, to_id);
             }
         }
+        chat::set_group_admins_raw(context, chat_id, &admins, true)?;
         send_EVENT_CHAT_MODIFIED = true;
     }
 
+    // update the group admins
+    if may_change_admins {
+        if let Some(value) = mime_parser.get(HeaderDef::ChatGroupAdmins) {
+            let mut admins = value
+                .split(',')
+                .map(str::trim)
+                .filter(|addr| !addr.is_empty())
+                .map(|addr| {
+                    if addr_cmp(addr, &self_addr) {
+                        DC_CONTACT_ID_SELF
+                    } else {
+                        Contact::lookup_id_by_addr(context, addr)
+                    }
+                })
+                .filter(|&contact_id| contact_id != 0)
+                .collect::<Vec<_>>();
+            admins.sort();
+            admins.dedup();
+            let old_admins = chat::get_group_admins(context, chat_id);
+            if admins != old_admins {
+                info!(context, "updating admins of chat {}", chat_id);
+                chat::set_group_admins_raw(context, chat_id, &old_admins, false)?;
+                chat::set_group_admins_raw(context, chat_id, &admins, true)?;
+                send_EVENT_CHAT_MODIFIED = true;
+            }
+        }
+    } else if mime_parser.is_system_message == SystemMessage::GroupAdminsChanged && may_manage {
+        warn!(
+            context,
+            "ignoring admin changes by {} in {}", from_id, chat_id
+        );
+        mime_parser.repl_msg_by_error(format!(
+            "{} Ignored, only group admins can change the group.",
+            better_msg
+        ));
+    }
+
     if send_EVENT_CHAT_MODIFIED {
         context.call_cb(Event::ChatModified(chat_id));
     }
@@ -1628,7 +1849,9 @@ fn dc_create_incoming_rfc724_mid(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::dummy_context;
+    use crate::chat::VerifiedStatus;
+    use crate::message::Message;
+    use crate::test_utils::{configure_alice_keypair, dummy_context};
 
     #[test]
     fn test_hex_hash() {
@@ -1687,4 +1910,277 @@ mod tests {
             Some("123-45-9@stub".into())
         );
     }
+
+    #[test]
+    fn test_non_admin_leave_does_not_add_members() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let chat_id = chat::create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let bob = Contact::create(&t.ctx, "bob", "bob@example.org").unwrap();
+        let claire = Contact::create(&t.ctx, "claire", "claire@example.org").unwrap();
+        assert!(chat::add_contact_to_chat(&t.ctx, chat_id, bob));
+        assert!(chat::add_contact_to_chat(&t.ctx, chat_id, claire));
+        chat::set_group_admin(&t.ctx, chat_id, claire, true).unwrap();
+        let grpid = Chat::load_from_db(&t.ctx, chat_id).unwrap().grpid;
+
+        // bob is no admin and leaves the group, but also addresses mallory
+        let raw = format!(
+            "From: bob <bob@example.org>\n\
+             To: alice@example.com, claire@example.org, mallory@example.org\n\
+             Subject: foo\n\
+             Message-ID: <leave@example.org>\n\
+             Chat-Version: 1.0\n\
+             Chat-Group-ID: {}\n\
+             Chat-Group-Name: foo\n\
+             Chat-Group-Member-Removed: bob@example.org\n\
+             Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+             \n\
+             Member bob@example.org removed.\n",
+            grpid
+        );
+        dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", 1, false).unwrap();
+
+        let mut members = chat::get_chat_contacts(&t.ctx, chat_id);
+        members.sort();
+        let mut expected = vec![DC_CONTACT_ID_SELF, claire];
+        expected.sort();
+        assert_eq!(members, expected);
+    }
+
+    #[test]
+    fn test_group_admins_received() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let receive = |uid: u32, from: &str, grpid: &str, headers: &str| {
+            let raw = format!(
+                "From: {}\n\
+                 To: alice@example.com, bob@example.org, claire@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <admins{}@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: {}\n\
+                 Chat-Group-Name: foo\n\
+                 {}\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello\n",
+                from, uid, grpid, headers
+            );
+            dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", uid, false).unwrap();
+        };
+        let admins = |grpid: &str| {
+            let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, grpid).unwrap();
+            chat::get_group_admins(&t.ctx, chat_id)
+                .into_iter()
+                .map(|contact_id| {
+                    Contact::load_from_db(&t.ctx, contact_id)
+                        .unwrap()
+                        .get_addr()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // the admins are taken from the message creating the group
+        receive(
+            1,
+            "bob@example.org",
+            "aDmInS1",
+            "Chat-Group-Admins: bob@example.org\n",
+        );
+        assert_eq!(admins("aDmInS1"), vec!["bob@example.org"]);
+
+        // afterwards, they are only changed by admins
+        receive(
+            2,
+            "claire@example.org",
+            "aDmInS1",
+            "Chat-Group-Admins: claire@example.org\n\
+             Chat-Group-Admin-Added: claire@example.org\n",
+        );
+        assert_eq!(admins("aDmInS1"), vec!["bob@example.org"]);
+        receive(
+            3,
+            "bob@example.org",
+            "aDmInS1",
+            "Chat-Group-Admins: bob@example.org, claire@example.org\n\
+             Chat-Group-Admin-Added: claire@example.org\n",
+        );
+        assert_eq!(
+            admins("aDmInS1"),
+            vec!["bob@example.org", "claire@example.org"]
+        );
+
+        // in an unverified group without admins, nobody can claim to be admin,
+        // neither a member nor someone outside the group
+        receive(4, "bob@example.org", "aDmInS2", "");
+        assert!(admins("aDmInS2").is_empty());
+        receive(
+            5,
+            "claire@example.org",
+            "aDmInS2",
+            "Chat-Group-Admins: claire@example.org\n\
+             Chat-Group-Admin-Added: claire@example.org\n",
+        );
+        assert!(admins("aDmInS2").is_empty());
+        receive(
+            6,
+            "mallory@example.org",
+            "aDmInS2",
+            "Chat-Group-Admins: mallory@example.org\n",
+        );
+        assert!(admins("aDmInS2").is_empty());
+    }
+
+    #[test]
+    fn test_group_description_received() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let raw = |mid: &str, headers: &str| {
+            format!(
+                "From: bob <bob@example.org>\n\
+                 To: alice@example.com, claire@example.org\n\
+                 Subject: foo\n\
+                 Message-ID: <{}@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 Chat-Group-ID: dEsCrIpTiOn\n\
+                 Chat-Group-Name: foo\n\
+                 {}\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello\n",
+                mid, headers
+            )
+        };
+        let description = |chat_id| {
+            Chat::load_from_db(&t.ctx, chat_id)
+                .unwrap()
+                .param
+                .get(Param::GroupDescription)
+                .map(|s| s.to_string())
+        };
+
+        // the description is taken when the group is created
+        let msg = raw("first", "Chat-Group-Description: about foo\n");
+        dc_receive_imf(&t.ctx, msg.as_bytes(), "INBOX", 1, false).unwrap();
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "dEsCrIpTiOn").unwrap();
+        assert_eq!(description(chat_id), Some("about foo".to_string()));
+
+        // a description sent along with other messages does not replace the current one
+        let msg = raw("second", "Chat-Group-Description: outdated\n");
+        dc_receive_imf(&t.ctx, msg.as_bytes(), "INBOX", 2, false).unwrap();
+        assert_eq!(description(chat_id), Some("about foo".to_string()));
+
+        // explicit changes do
+        let msg = raw(
+            "third",
+            "Chat-Group-Description: about bar\nChat-Group-Description-Changed: about foo\n",
+        );
+        dc_receive_imf(&t.ctx, msg.as_bytes(), "INBOX", 3, false).unwrap();
+        assert_eq!(description(chat_id), Some("about bar".to_string()));
+        let msg = raw("fourth", "Chat-Group-Description-Changed: about bar\n");
+        dc_receive_imf(&t.ctx, msg.as_bytes(), "INBOX", 4, false).unwrap();
+        assert_eq!(description(chat_id), None);
+
+        // a group without description learns it from any message
+        let msg = raw("fifth", "Chat-Group-Description: about baz\n");
+        dc_receive_imf(&t.ctx, msg.as_bytes(), "INBOX", 5, false).unwrap();
+        assert_eq!(description(chat_id), Some("about baz".to_string()));
+    }
+
+    #[test]
+    fn test_filter_rules_applied() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        filter::add_rule(
+            &t.ctx,
+            filter::FilterKind::Domain,
+            "",
+            "spam.example.org",
+            FilterAction::Block,
+        )
+        .unwrap();
+        filter::add_rule(
+            &t.ctx,
+            filter::FilterKind::Subject,
+            "",
+            "^unwanted",
+            FilterAction::Trash,
+        )
+        .unwrap();
+        filter::add_rule(
+            &t.ctx,
+            filter::FilterKind::Addr,
+            "",
+            "friend@example.org",
+            FilterAction::Accept,
+        )
+        .unwrap();
+        filter::add_rule(
+            &t.ctx,
+            filter::FilterKind::ListId,
+            "",
+            "*.news.example.org",
+            FilterAction::Archive,
+        )
+        .unwrap();
+
+        let receive = |uid: u32, from: &str, subject: &str, headers: &str| -> (u32, Message) {
+            let raw = format!(
+                "From: {}\n\
+                 To: alice@example.com\n\
+                 Subject: {}\n\
+                 Message-ID: <{}@example.org>\n\
+                 Chat-Version: 1.0\n\
+                 {}\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hello\n",
+                from, subject, uid, headers
+            );
+            dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", uid, false).unwrap();
+            let (_, _, msg_id) =
+                message::rfc724_mid_exists(&t.ctx, &format!("{}@example.org", uid)).unwrap();
+            let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+            (msg.from_id, msg)
+        };
+
+        let (spammer_id, msg) = receive(1, "spammer@spam.example.org", "hi", "");
+        assert!(Contact::load_from_db(&t.ctx, spammer_id)
+            .unwrap()
+            .is_blocked());
+        assert!(msg.chat_id.is_trash());
+
+        let (stranger_id, msg) = receive(2, "stranger@example.org", "unwanted offer", "");
+        assert!(!Contact::load_from_db(&t.ctx, stranger_id)
+            .unwrap()
+            .is_blocked());
+        assert!(msg.chat_id.is_trash());
+
+        let (_, msg) = receive(3, "friend@example.org", "hi", "");
+        assert!(!msg.chat_id.is_special());
+        let chat = Chat::load_from_db(&t.ctx, msg.chat_id).unwrap();
+        assert_eq!(chat.get_visibility(), chat::ChatVisibility::Normal);
+        let (friend_chat_id, blocked) = chat::lookup_by_contact_id(&t.ctx, msg.from_id).unwrap();
+        assert_eq!(friend_chat_id, msg.chat_id);
+        assert_eq!(blocked, Blocked::Not);
+
+        let (_, msg) = receive(
+            4,
+            "daily@news.example.org",
+            "news",
+            "List-Id: Daily <daily.news.example.org>\n",
+        );
+        assert!(!msg.chat_id.is_special());
+        assert_eq!(msg.get_state(), MessageState::InNoticed);
+        let chat = Chat::load_from_db(&t.ctx, msg.chat_id).unwrap();
+        assert_eq!(chat.get_visibility(), chat::ChatVisibility::Archived);
+
+        // without a matching rule, the message goes to the deaddrop
+        let (someone_id, msg) = receive(5, "someone@example.org", "hi", "");
+        assert_eq!(
+            chat::lookup_by_contact_id(&t.ctx, someone_id).unwrap(),
+            (msg.chat_id, Blocked::Deaddrop)
+        );
+    }
 }
@@ -42,6 +42,16 @@ pub(crate) fn dc_truncate(buf: &str, approx_chars: usize, do_unwrap: bool) -> Co
     }
 }
 
+/// Joins the non-empty lines of a text with spaces,
+/// eg. to store it in newline-separated params.
+pub(crate) fn dc_collapse_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// the colors must fulfill some criterions as:
 /// - contrast to black and to white
 /// - work as a text-color
@@ -437,7 +447,7 @@ pub(crate) fn dc_get_next_backup_path(
     // 64 backup files per day should be enough for everyone
     for i in 0..64 {
         let mut path = folder.clone();
-        path.push(format!("{}-{}.bak", stem, i));
+        path.push(format!("{}-{}.tar", stem, i));
         if !path.exists() {
             return Ok(path);
         }
@@ -7,16 +7,22 @@ use mailparse::ParsedMail;
 use num_traits::FromPrimitive;
 
 use crate::aheader::*;
+use crate::chat::{self, Chat, ChatId};
 use crate::config::Config;
+use crate::constants::{KeyGenType, Viewtype, DC_CONTACT_ID_SELF};
+use crate::contact::Contact;
 use crate::context::Context;
 use crate::dc_tools::EmailAddress;
 use crate::error::*;
+use crate::events::Event;
 use crate::headerdef::{HeaderDef, HeaderDefMap};
-use crate::key::{self, Key, KeyPairUse, SignedPublicKey};
+use crate::key::{self, Key, KeyPair, KeyPairUse, SignedPublicKey};
 use crate::keyring::*;
+use crate::message::Message;
 use crate::peerstate::*;
 use crate::pgp;
 use crate::securejoin::handle_degrade_event;
+use crate::stock::StockMessage;
 
 #[derive(Debug)]
 pub struct EncryptHelper {
@@ -211,11 +217,10 @@ fn load_or_generate_self_public_key(
     }
 
     let start = std::time::Instant::now();
-    info!(
-        context,
-        "Generating keypair with {} bits, e={} ...", 2048, 65537,
-    );
-    let keypair = pgp::create_keypair(EmailAddress::new(self_addr.as_ref())?)?;
+    let keygen_type =
+        KeyGenType::from_i32(context.get_config_int(Config::KeyGenType)).unwrap_or_default();
+    info!(context, "Generating keypair with type {}", keygen_type);
+    let keypair = pgp::create_keypair(EmailAddress::new(self_addr.as_ref())?, keygen_type)?;
     key::store_self_keypair(context, &keypair, KeyPairUse::Default)?;
     info!(
         context,
@@ -225,6 +230,48 @@ fn load_or_generate_self_public_key(
     Ok(keypair.public)
 }
 
+/// Returns the addresses of the members of the chat without a known key
+/// if encryption is required for the chat or any of its members.
+///
+/// Returns `None` if messages to the chat may be sent unencrypted,
+/// see `Chat::is_encryption_required()` and `Contact::is_encryption_required()`.
+pub(crate) fn get_missing_keys_if_encryption_required(
+    context: &Context,
+    chat_id: ChatId,
+) -> Result<Option<Vec<String>>> {
+    let chat = Chat::load_from_db(context, chat_id)?;
+    let contacts = chat::get_chat_contacts(context, chat_id)
+        .into_iter()
+        .filter(|contact_id| *contact_id != DC_CONTACT_ID_SELF)
+        .map(|contact_id| Contact::load_from_db(context, contact_id))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    if !chat.is_encryption_required()
+        && !contacts
+            .iter()
+            .any(|contact| contact.is_encryption_required())
+    {
+        return Ok(None);
+    }
+
+    let missing = contacts
+        .iter()
+        .map(|contact| contact.get_addr())
+        .filter(|addr| !has_peer_key(context, addr))
+        .map(|addr| addr.to_string())
+        .collect();
+    Ok(Some(missing))
+}
+
+/// Returns true if a key is known that can be used to encrypt messages to `addr`.
+pub(crate) fn has_peer_key(context: &Context, addr: &str) -> bool {
+    Peerstate::from_addr(context, &context.sql, addr).map_or(false, |peerstate| {
+        peerstate
+            .peek_key(PeerstateVerifiedStatus::Unverified)
+            .is_some()
+    })
+}
+
 /// Returns a reference to the encrypted payload and validates the autocrypt structure.
 fn get_autocrypt_mime<'a, 'b>(mail: &'a ParsedMail<'b>) -> Result<&'a ParsedMail<'b>> {
     ensure!(
@@ -357,12 +404,143 @@ pub fn ensure_secret_key_exists(context: &Context) -> Result<String> {
     Ok(self_addr)
 }
 
+/// Replaces the own key by a newly generated one.
+///
+/// The new key is used for signing and is announced to the peers
+/// by the Autocrypt header of the next messages, from where it is gossiped further.
+/// The old secret keys are kept to decrypt messages encrypted to the old key.
+/// A device message informs about the change.
+///
+/// Returns the fingerprint of the new key.
+pub fn rotate_self_key(context: &Context) -> Result<String> {
+    let self_addr = ensure_secret_key_exists(context)?;
+    let _guard = context.generating_key_mutex.lock().unwrap();
+
+    let start = std::time::Instant::now();
+    let keygen_type =
+        KeyGenType::from_i32(context.get_config_int(Config::KeyGenType)).unwrap_or_default();
+    info!(
+        context,
+        "Generating new keypair with type {} for key rotation ...", keygen_type
+    );
+    let keypair = pgp::create_keypair(EmailAddress::new(&self_addr)?, keygen_type)?;
+    info!(
+        context,
+        "Keypair generated in {:.3}s.",
+        start.elapsed().as_secs()
+    );
+    replace_self_keypair(context, &keypair)
+}
+
+fn replace_self_keypair(context: &Context, keypair: &KeyPair) -> Result<String> {
+    key::store_self_keypair(context, keypair, KeyPairUse::Default)?;
+    let fingerprint = Key::from(keypair.public.clone()).formatted_fingerprint();
+
+    let mut msg = Message::new(Viewtype::Text);
+    msg.text = Some(context.stock_string_repl_str(StockMessage::SelfKeyChanged, &fingerprint));
+    chat::add_device_msg(context, None, Some(&mut msg))?;
+    context.call_cb(Event::SelfKeyChanged);
+    Ok(fingerprint)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use ::pgp::types::KeyTrait;
+
     use crate::test_utils::*;
 
+    #[test]
+    fn test_replace_self_keypair() {
+        let t = dummy_context();
+        let addr = configure_alice_keypair(&t.ctx);
+        let old_key = Key::from_self_public(&t.ctx, &addr, &t.ctx.sql).unwrap();
+
+        let mut keypair = bob_keypair();
+        keypair.addr = EmailAddress::new(&addr).unwrap();
+        let fingerprint = replace_self_keypair(&t.ctx, &keypair).unwrap();
+
+        let new_key = Key::from_self_public(&t.ctx, &addr, &t.ctx.sql).unwrap();
+        assert_ne!(new_key, old_key);
+        assert_eq!(new_key.formatted_fingerprint(), fingerprint);
+        assert_eq!(
+            EncryptHelper::new(&t.ctx).unwrap().public_key,
+            keypair.public
+        );
+
+        // the old key is still available for decrypting
+        let mut keyring = Keyring::default();
+        assert!(keyring.load_self_private_for_decrypting(&t.ctx, &addr, &t.ctx.sql));
+        assert_eq!(keyring.keys().len(), 2);
+        assert_eq!(
+            *keyring.keys()[0],
+            Key::from(keypair.secret.clone()),
+            "the default key comes first"
+        );
+    }
+
+    #[test]
+    fn test_get_missing_keys_if_encryption_required() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let bob_id = Contact::create(&t.ctx, "Bob", "bob@example.net").unwrap();
+        let chat_id = chat::create_by_contact_id(&t.ctx, bob_id).unwrap();
+        assert_eq!(
+            get_missing_keys_if_encryption_required(&t.ctx, chat_id).unwrap(),
+            None
+        );
+
+        Contact::set_encryption_required(&t.ctx, bob_id, true).unwrap();
+        assert_eq!(
+            get_missing_keys_if_encryption_required(&t.ctx, chat_id).unwrap(),
+            Some(vec!["bob@example.net".to_string()])
+        );
+
+        let mut peerstate = Peerstate::new(&t.ctx, "bob@example.net".to_string());
+        peerstate.public_key = Some(Key::from(bob_keypair().public));
+        peerstate.save_to_db(&t.ctx.sql, true).unwrap();
+        assert_eq!(
+            get_missing_keys_if_encryption_required(&t.ctx, chat_id).unwrap(),
+            Some(vec![])
+        );
+
+        Contact::set_encryption_required(&t.ctx, bob_id, false).unwrap();
+        assert_eq!(
+            get_missing_keys_if_encryption_required(&t.ctx, chat_id).unwrap(),
+            None
+        );
+        chat::set_encryption_required(&t.ctx, chat_id, true).unwrap();
+        assert!(Chat::load_from_db(&t.ctx, chat_id)
+            .unwrap()
+            .is_encryption_required());
+        assert_eq!(
+            get_missing_keys_if_encryption_required(&t.ctx, chat_id).unwrap(),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_rotate_self_key_ed25519() {
+        let t = dummy_context();
+        let addr = configure_alice_keypair(&t.ctx);
+        t.ctx
+            .set_config(
+                Config::KeyGenType,
+                Some(&(KeyGenType::Ed25519 as i32).to_string()),
+            )
+            .unwrap();
+
+        let fingerprint = rotate_self_key(&t.ctx).unwrap();
+        let new_key = Key::from_self_public(&t.ctx, &addr, &t.ctx.sql).unwrap();
+        assert_eq!(new_key.formatted_fingerprint(), fingerprint);
+        let public_key = SignedPublicKey::try_from(new_key).unwrap();
+        assert_eq!(
+            public_key.primary_key.algorithm(),
+            ::pgp::crypto::PublicKeyAlgorithm::EdDSA
+        );
+    }
+
     mod ensure_secret_key_exists {
         use super::*;
 
@@ -207,4 +207,25 @@ pub enum Event {
     /// @param data2 (int) contact_id
     #[strum(props(id = "2062"))]
     SecurejoinMemberAdded { chat_id: ChatId, contact_id: u32 },
+
+    /// A filter rule matched an incoming message, see dc_add_filter_rule().
+    /// @param data1 (int) ID of the rule.
+    /// @param data2 (int) ID of the contact that sent the message.
+    #[strum(props(id = "2070"))]
+    FilterRuleMatched { rule_id: u32, contact_id: u32 },
+
+    /// The own key was replaced by e2ee::rotate_self_key().
+    /// @param data1 0
+    /// @param data2 0
+    #[strum(props(id = "2071"))]
+    SelfKeyChanged,
+
+    /// A message was not sent because encryption is required for the chat or its members,
+    /// but no key is known for some of the recipients,
+    /// see dc_set_chat_encryption_required() and dc_set_contact_encryption_required().
+    /// The message is marked as failed and an info message names the recipients without key.
+    /// @param data1 (int) chat_id
+    /// @param data2 (int) msg_id
+    #[strum(props(id = "2072"))]
+    EncryptionRequiredFailed { chat_id: ChatId, msg_id: MsgId },
 }
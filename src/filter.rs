@@ -0,0 +1,466 @@
+//! # Filter rules for incoming messages
+//!
+//! Rules are matched against incoming messages before they are assigned to a chat.
+//! They allow to block spammers, to drop unwanted messages
+//! and to accept or archive contact requests without user interaction.
+
+use std::sync::Arc;
+
+use deltachat_derive::*;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::contact::Contact;
+use crate::context::Context;
+use crate::dc_tools::time;
+use crate::error::Error;
+use crate::headerdef::HeaderDef;
+use crate::mimeparser::MimeMessage;
+
+/// What a filter rule is matched against.
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    ToSql,
+    FromSql,
+    Serialize,
+    Deserialize,
+)]
+#[repr(i32)]
+pub enum FilterKind {
+    /// The sender address matches a glob pattern, eg. `*@news.example.org`.
+    /// `*` matches any number of characters, `?` matches a single character.
+    Addr = 1,
+
+    /// The sender address belongs to the given domain or one of its subdomains.
+    Domain = 2,
+
+    /// The subject matches a regular expression.
+    Subject = 3,
+
+    /// The header given by the rule matches a regular expression.
+    Header = 4,
+
+    /// The List-Id of a mailing list message matches a glob pattern.
+    ListId = 5,
+}
+
+/// What happens to a message a filter rule matches.
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    ToSql,
+    FromSql,
+    Serialize,
+    Deserialize,
+)]
+#[repr(i32)]
+pub enum FilterAction {
+    /// The sender is blocked and the message is moved to the trash.
+    Block = 1,
+
+    /// The message is moved to the trash.
+    Trash = 2,
+
+    /// The message is shown in a normal chat instead of the deaddrop.
+    Accept = 3,
+
+    /// Like `Accept`, but the chat is archived.
+    Archive = 4,
+}
+
+/// A rule evaluated for incoming messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub id: u32,
+    pub kind: FilterKind,
+    /// The name of the header to match, only used for `FilterKind::Header`.
+    pub header: String,
+    pub pattern: String,
+    pub action: FilterAction,
+}
+
+/// A filter rule with its pattern compiled, as cached in the context.
+#[derive(Debug)]
+pub(crate) struct CompiledRule {
+    rule: FilterRule,
+    /// The compiled pattern, `None` for `FilterKind::Domain`.
+    regex: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn new(rule: FilterRule) -> Result<Self, Error> {
+        let regex = match rule.kind {
+            FilterKind::Addr | FilterKind::ListId => Some(glob_to_regex(&rule.pattern)?),
+            FilterKind::Domain => None,
+            FilterKind::Subject | FilterKind::Header => Some(build_regex(&rule.pattern)?),
+        };
+        Ok(CompiledRule { rule, regex })
+    }
+
+    fn matches(&self, mime_parser: &MimeMessage, addr: &str) -> bool {
+        match self.rule.kind {
+            FilterKind::Addr => self.is_match(addr),
+            FilterKind::Domain => {
+                let pattern = self.rule.pattern.trim_start_matches('@').to_lowercase();
+                let domain = addr.rsplit('@').next().unwrap_or_default().to_lowercase();
+                domain == pattern || domain.ends_with(&format!(".{}", pattern))
+            }
+            FilterKind::Subject => mime_parser
+                .get(HeaderDef::Subject)
+                .map_or(false, |subject| self.is_match(subject)),
+            FilterKind::Header => mime_parser
+                .get_header(&self.rule.header)
+                .map_or(false, |value| self.is_match(value)),
+            FilterKind::ListId => mime_parser
+                .get(HeaderDef::ListId)
+                .map_or(false, |list_id| self.is_match(&parse_list_id(list_id))),
+        }
+    }
+
+    fn is_match(&self, value: &str) -> bool {
+        self.regex
+            .as_ref()
+            .map_or(false, |regex| regex.is_match(value))
+    }
+}
+
+/// Adds a filter rule, returns the ID of the new rule.
+///
+/// Rules are evaluated in the order they are added, the first matching rule wins.
+/// `header` is only used for `FilterKind::Header` and should be empty otherwise.
+pub fn add_rule(
+    context: &Context,
+    kind: FilterKind,
+    header: impl AsRef<str>,
+    pattern: impl AsRef<str>,
+    action: FilterAction,
+) -> Result<u32, Error> {
+    let header = header.as_ref().trim();
+    let pattern = pattern.as_ref().trim();
+    check_rule(kind, header, pattern)?;
+    let rule_id = context.sql.with_transaction(|conn| {
+        conn.execute(
+            "INSERT INTO filter_rules (kind, header, pattern, action, timestamp) VALUES (?, ?, ?, ?, ?);",
+            params![kind, header, pattern, action, time()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    })?;
+    invalidate_cache(context);
+    Ok(rule_id as u32)
+}
+
+/// Changes an existing filter rule.
+pub fn update_rule(context: &Context, rule: &FilterRule) -> Result<(), Error> {
+    let header = rule.header.trim();
+    let pattern = rule.pattern.trim();
+    check_rule(rule.kind, header, pattern)?;
+    let updated = context.sql.execute(
+        "UPDATE filter_rules SET kind=?, header=?, pattern=?, action=? WHERE id=?;",
+        params![rule.kind, header, pattern, rule.action, rule.id as i32],
+    )?;
+    invalidate_cache(context);
+    ensure!(updated > 0, "Filter rule {} does not exist", rule.id);
+    Ok(())
+}
+
+/// Deletes a filter rule.
+pub fn delete_rule(context: &Context, rule_id: u32) -> Result<(), Error> {
+    let deleted = context.sql.execute(
+        "DELETE FROM filter_rules WHERE id=?;",
+        params![rule_id as i32],
+    )?;
+    invalidate_cache(context);
+    ensure!(deleted > 0, "Filter rule {} does not exist", rule_id);
+    Ok(())
+}
+
+/// Loads a single filter rule.
+pub fn get_rule(context: &Context, rule_id: u32) -> Result<FilterRule, Error> {
+    context
+        .sql
+        .query_row(
+            "SELECT id, kind, header, pattern, action FROM filter_rules WHERE id=?;",
+            params![rule_id as i32],
+            row_to_rule,
+        )
+        .map_err(|_| format_err!("Filter rule {} does not exist", rule_id))
+}
+
+/// Returns all filter rules in the order they are evaluated.
+pub fn get_rules(context: &Context) -> Vec<FilterRule> {
+    context
+        .sql
+        .query_map(
+            "SELECT id, kind, header, pattern, action FROM filter_rules ORDER BY id;",
+            params![],
+            row_to_rule,
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .unwrap_or_default()
+}
+
+/// Drops the compiled rules cached in the context.
+///
+/// Must be called whenever the rules in the database are changed.
+pub(crate) fn invalidate_cache(context: &Context) {
+    *context.filter_rules.write().unwrap() = None;
+}
+
+/// Returns the compiled rules, compiling them only if they are not cached yet.
+///
+/// Rules that cannot be compiled, eg. because of an invalid regular expression
+/// in an old database, are skipped.
+fn get_compiled_rules(context: &Context) -> Arc<Vec<CompiledRule>> {
+    if let Some(rules) = &*context.filter_rules.read().unwrap() {
+        return Arc::clone(rules);
+    }
+
+    // hold the lock while loading so that an invalidation cannot be overwritten
+    let mut cache = context.filter_rules.write().unwrap();
+    if let Some(rules) = &*cache {
+        return Arc::clone(rules);
+    }
+    let rules: Vec<CompiledRule> = get_rules(context)
+        .into_iter()
+        .filter_map(|rule| {
+            let rule_id = rule.id;
+            match CompiledRule::new(rule) {
+                Ok(rule) => Some(rule),
+                Err(err) => {
+                    warn!(context, "Cannot evaluate filter rule {}: {}", rule_id, err);
+                    None
+                }
+            }
+        })
+        .collect();
+    let rules = Arc::new(rules);
+    *cache = Some(Arc::clone(&rules));
+    rules
+}
+
+/// Returns the first rule matching an incoming message from the given contact.
+pub(crate) fn find_matching_rule(
+    context: &Context,
+    mime_parser: &MimeMessage,
+    from_id: u32,
+) -> Option<FilterRule> {
+    let rules = get_compiled_rules(context);
+    if rules.is_empty() {
+        return None;
+    }
+    let addr = Contact::load_from_db(context, from_id)
+        .map(|contact| contact.get_addr().to_string())
+        .unwrap_or_default();
+
+    rules
+        .iter()
+        .find(|rule| rule.matches(mime_parser, &addr))
+        .map(|rule| rule.rule.clone())
+}
+
+fn row_to_rule(row: &rusqlite::Row) -> rusqlite::Result<FilterRule> {
+    Ok(FilterRule {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        header: row.get(2)?,
+        pattern: row.get(3)?,
+        action: row.get(4)?,
+    })
+}
+
+fn check_rule(kind: FilterKind, header: &str, pattern: &str) -> Result<(), Error> {
+    ensure!(!pattern.is_empty(), "Filter pattern must not be empty");
+    match kind {
+        FilterKind::Addr | FilterKind::ListId => {
+            glob_to_regex(pattern)?;
+        }
+        FilterKind::Domain => {
+            ensure!(
+                !pattern.trim_start_matches('@').is_empty() && !pattern.contains(' '),
+                "Invalid domain: {}",
+                pattern
+            );
+        }
+        FilterKind::Subject => {
+            build_regex(pattern)?;
+        }
+        FilterKind::Header => {
+            ensure!(
+                !header.is_empty() && !header.contains(':'),
+                "Invalid header name: {}",
+                header
+            );
+            build_regex(pattern)?;
+        }
+    }
+    Ok(())
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| format_err!("Invalid regular expression {}: {}", pattern, err))
+}
+
+/// Converts a glob pattern to a regular expression matching the whole string.
+fn glob_to_regex(pattern: &str) -> Result<Regex, Error> {
+    let pattern = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+    build_regex(&format!("^{}$", pattern))
+}
+
+/// Extracts the identifier from a List-Id header, eg. `list.example.org`
+/// from `Some List <list.example.org>`.
+fn parse_list_id(list_id: &str) -> String {
+    match (list_id.rfind('<'), list_id.rfind('>')) {
+        (Some(start), Some(end)) if start < end => list_id[start + 1..end].to_string(),
+        _ => list_id.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_glob_to_regex() {
+        let re = glob_to_regex("*@news.example.org").unwrap();
+        assert!(re.is_match("daily@news.example.org"));
+        assert!(re.is_match("Daily@NEWS.example.org"));
+        assert!(!re.is_match("daily@news.example.org.evil"));
+        assert!(!re.is_match("daily@newsXexample.org"));
+        assert!(glob_to_regex("bob?@example.org")
+            .unwrap()
+            .is_match("bob1@example.org"));
+    }
+
+    #[test]
+    fn test_parse_list_id() {
+        assert_eq!(
+            parse_list_id("Some List <list.example.org>"),
+            "list.example.org"
+        );
+        assert_eq!(parse_list_id("list.example.org"), "list.example.org");
+    }
+
+    #[test]
+    fn test_rule_crud() {
+        let t = dummy_context();
+        assert!(get_rules(&t.ctx).is_empty());
+        assert!(add_rule(&t.ctx, FilterKind::Subject, "", "(", FilterAction::Trash).is_err());
+        assert!(add_rule(&t.ctx, FilterKind::Header, "", "x", FilterAction::Trash).is_err());
+
+        let id1 = add_rule(
+            &t.ctx,
+            FilterKind::Domain,
+            "",
+            "spam.example.org",
+            FilterAction::Block,
+        )
+        .unwrap();
+        let id2 = add_rule(
+            &t.ctx,
+            FilterKind::Header,
+            "X-Mailer",
+            "^Newsletter",
+            FilterAction::Archive,
+        )
+        .unwrap();
+        let rules = get_rules(&t.ctx);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].id, id1);
+        assert_eq!(rules[1].header, "X-Mailer");
+
+        let mut rule = get_rule(&t.ctx, id2).unwrap();
+        rule.action = FilterAction::Accept;
+        update_rule(&t.ctx, &rule).unwrap();
+        assert_eq!(get_rule(&t.ctx, id2).unwrap().action, FilterAction::Accept);
+
+        delete_rule(&t.ctx, id1).unwrap();
+        assert!(delete_rule(&t.ctx, id1).is_err());
+        assert!(get_rule(&t.ctx, id1).is_err());
+        assert_eq!(get_rules(&t.ctx).len(), 1);
+    }
+
+    #[test]
+    fn test_find_matching_rule() {
+        let t = dummy_context();
+        let raw = b"From: Daily News <daily@news.spam.example.org>\n\
+                    To: alice@example.org\n\
+                    Subject: Buy now\n\
+                    List-Id: Daily <daily.news.example.org>\n\
+                    Message-ID: <12345@news.spam.example.org>\n\
+                    Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                    \n\
+                    hello\n";
+        let mime_parser = MimeMessage::from_bytes(&t.ctx, &raw[..]).unwrap();
+        let (from_id, _) = Contact::add_or_lookup(
+            &t.ctx,
+            "Daily News",
+            "daily@news.spam.example.org",
+            crate::contact::Origin::IncomingUnknownFrom,
+        )
+        .unwrap();
+        assert!(find_matching_rule(&t.ctx, &mime_parser, from_id).is_none());
+
+        add_rule(
+            &t.ctx,
+            FilterKind::Subject,
+            "",
+            "^sell",
+            FilterAction::Trash,
+        )
+        .unwrap();
+        assert!(find_matching_rule(&t.ctx, &mime_parser, from_id).is_none());
+
+        let id = add_rule(
+            &t.ctx,
+            FilterKind::ListId,
+            "",
+            "*.news.example.org",
+            FilterAction::Archive,
+        )
+        .unwrap();
+        assert_eq!(
+            find_matching_rule(&t.ctx, &mime_parser, from_id)
+                .unwrap()
+                .id,
+            id
+        );
+
+        // the first matching rule wins
+        delete_rule(&t.ctx, id).unwrap();
+        let id = add_rule(
+            &t.ctx,
+            FilterKind::Domain,
+            "",
+            "spam.example.org",
+            FilterAction::Block,
+        )
+        .unwrap();
+        add_rule(&t.ctx, FilterKind::Subject, "", "^buy", FilterAction::Trash).unwrap();
+        let rule = find_matching_rule(&t.ctx, &mime_parser, from_id).unwrap();
+        assert_eq!(rule.id, id);
+        assert_eq!(rule.action, FilterAction::Block);
+    }
+}
@@ -25,6 +25,8 @@ pub enum HeaderDef {
     ChatGroupId,
     ChatGroupName,
     ChatGroupNameChanged,
+    ChatGroupDescription,
+    ChatGroupDescriptionChanged,
     ChatVerified,
     ChatGroupImage, // deprecated
     ChatGroupAvatar,
@@ -32,6 +34,9 @@ pub enum HeaderDef {
     ChatVoiceMessage,
     ChatGroupMemberRemoved,
     ChatGroupMemberAdded,
+    ChatGroupAdmins,
+    ChatGroupAdminAdded,
+    ChatGroupAdminRemoved,
     ChatContent,
     ChatDuration,
     ChatDispositionNotificationTo,
@@ -181,7 +181,7 @@ impl Imap {
         })
     }
 
-    pub(crate) fn fake_idle(&self, context: &Context, watch_folder: Option<String>) {
+    pub(crate) fn fake_idle(&self, context: &Context, watch_folders: Vec<String>) {
         // Idle using polling. This is also needed if we're not yet configured -
         // in this case, we're waiting for a configure job (and an interrupt).
         task::block_on(async move {
@@ -212,7 +212,8 @@ impl Imap {
                         continue;
                     }
                     if self.config.read().await.can_idle {
-                        // we only fake-idled because network was gone during IDLE, probably
+                        // we only fake-idled because network was gone during IDLE, probably,
+                        // or we poll folders that cannot be idled on; the next fetch gets them
                         break;
                     }
                     info!(context, "fake_idle is connected");
@@ -221,13 +222,20 @@ impl Imap {
                     // will have already fetched the messages so perform_*_fetch
                     // will not find any new.
 
-                    if let Some(ref watch_folder) = watch_folder {
+                    let mut fetched = false;
+                    for watch_folder in &watch_folders {
                         match self.fetch_new_messages(context, watch_folder).await {
                             Ok(res) => {
                                 info!(context, "fetch_new_messages returned {:?}", res);
-                                if res {
-                                    break;
-                                }
+                                fetched |= res;
+                            }
+                            Err(err) if is_folder_error(&err) => {
+                                // a single unusable folder, e.g. a deleted one,
+                                // should neither stop polling the others nor force reconnects
+                                warn!(
+                                    context,
+                                    "could not fetch from folder {}: {}", watch_folder, err
+                                );
                             }
                             Err(err) => {
                                 error!(context, "could not fetch from folder: {}", err);
@@ -235,6 +243,9 @@ impl Imap {
                             }
                         }
                     }
+                    if fetched {
+                        break;
+                    }
                 }
             }
             self.interrupt.lock().await.take();
@@ -274,3 +285,14 @@ impl Imap {
         });
     }
 }
+
+/// Returns true if the error is specific to a folder,
+/// so the connection can still be used for other folders.
+fn is_folder_error(err: &super::Error) -> bool {
+    match err {
+        super::Error::NoMailbox(_) => true,
+        super::Error::SelectFolderError(select_folder::Error::BadFolderName(_))
+        | super::Error::SelectFolderError(select_folder::Error::FolderNotSelectable(_, _)) => true,
+        _ => false,
+    }
+}
@@ -3,7 +3,9 @@
 //! uses [async-email/async-imap](https://github.com/async-email/async-imap)
 //! to implement connect, fetch, delete functionality with standard IMAP servers.
 
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use num_traits::FromPrimitive;
 
@@ -133,6 +135,10 @@ const JUST_UID: &str = "(UID)";
 const BODY_FLAGS: &str = "(FLAGS BODY.PEEK[])";
 const SELECT_ALL: &str = "1:*";
 
+/// If no new messages were fetched, `\\Seen` flags of a folder
+/// are synchronized at most once in this interval.
+const SEEN_FLAGS_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
 #[derive(Debug, Default)]
 pub struct Imap {
     config: RwLock<ImapConfig>,
@@ -141,6 +147,8 @@ pub struct Imap {
     interrupt: Mutex<Option<stop_token::StopSource>>,
     skip_next_idle_wait: AtomicBool,
     should_reconnect: AtomicBool,
+    /// Time of the last synchronization of `\\Seen` flags per folder.
+    last_seen_flags_sync: Mutex<HashMap<String, Instant>>,
 }
 
 #[derive(Debug)]
@@ -184,6 +192,10 @@ struct ImapConfig {
     /// True if the server has MOVE capability as defined in
     /// https://tools.ietf.org/html/rfc6851
     pub can_move: bool,
+
+    /// True if the server has CONDSTORE capability as defined in
+    /// https://tools.ietf.org/html/rfc7162
+    pub can_condstore: bool,
     pub imap_delimiter: char,
 }
 
@@ -202,6 +214,7 @@ impl Default for ImapConfig {
             selected_folder_needs_expunge: false,
             can_idle: false,
             can_move: false,
+            can_condstore: false,
             imap_delimiter: '.',
         }
     }
@@ -356,6 +369,7 @@ impl Imap {
 
         cfg.can_idle = false;
         cfg.can_move = false;
+        cfg.can_condstore = false;
     }
 
     /// Connects to imap account using already-configured parameters.
@@ -417,6 +431,7 @@ impl Imap {
                     } else {
                         let can_idle = caps.has_str("IDLE");
                         let can_move = caps.has_str("MOVE");
+                        let can_condstore = caps.has_str("CONDSTORE");
                         let caps_list = caps.iter().fold(String::new(), |s, c| {
                             if let Capability::Atom(x) = c {
                                 s + &format!(" {}", x)
@@ -427,6 +442,7 @@ impl Imap {
 
                         self.config.write().await.can_idle = can_idle;
                         self.config.write().await.can_move = can_move;
+                        self.config.write().await.can_condstore = can_condstore;
                         *self.connected.lock().await = true;
                         emit_event!(
                             context,
@@ -469,12 +485,46 @@ impl Imap {
         }
         self.setup_handle_if_needed(context).await?;
 
+        let mut new_messages = false;
         while self.fetch_new_messages(context, &watch_folder).await? {
             // We fetch until no more new messages are there.
+            new_messages = true;
+        }
+
+        if self
+            .should_sync_seen_flags(watch_folder, new_messages)
+            .await
+        {
+            if let Err(err) = self.sync_seen_flags(context, watch_folder).await {
+                warn!(
+                    context,
+                    "Failed to sync seen flags from \"{}\": {}", watch_folder, err
+                );
+            }
         }
         Ok(())
     }
 
+    /// Returns true if the `\\Seen` flags of the folder should be synchronized.
+    ///
+    /// This is the case if new messages were fetched, where other devices are likely active,
+    /// or if the last synchronization is longer ago than [SEEN_FLAGS_SYNC_INTERVAL].
+    async fn should_sync_seen_flags(&self, folder: &str, new_messages: bool) -> bool {
+        let mut last_syncs = self.last_seen_flags_sync.lock().await;
+        let now = Instant::now();
+        match last_syncs.get(folder) {
+            Some(last_sync)
+                if !new_messages && now.duration_since(*last_sync) < SEEN_FLAGS_SYNC_INTERVAL =>
+            {
+                false
+            }
+            _ => {
+                last_syncs.insert(folder.to_string(), now);
+                true
+            }
+        }
+    }
+
     fn get_config_last_seen_uid<S: AsRef<str>>(&self, context: &Context, folder: S) -> (u32, u32) {
         let key = format!("imap.mailbox.{}", folder.as_ref());
         if let Some(entry) = context.sql.get_raw_config(context, &key) {
@@ -711,6 +761,109 @@ impl Imap {
         context.sql.set_raw_config(context, &key, Some(&val)).ok();
     }
 
+    fn get_config_last_modseq(&self, context: &Context, folder: &str) -> (u32, u64) {
+        let key = format!("imap.modseq.{}", folder);
+        if let Some(entry) = context.sql.get_raw_config(context, &key) {
+            // the entry has the format `imap.modseq.<folder>=<uidvalidity>:<modseq>`
+            let mut parts = entry.split(':');
+            (
+                parts.next().unwrap_or_default().parse().unwrap_or_default(),
+                parts.next().unwrap_or_default().parse().unwrap_or_default(),
+            )
+        } else {
+            (0, 0)
+        }
+    }
+
+    fn set_config_last_modseq(
+        &self,
+        context: &Context,
+        folder: &str,
+        uidvalidity: u32,
+        modseq: u64,
+    ) {
+        let key = format!("imap.modseq.{}", folder);
+        let val = format!("{}:{}", uidvalidity, modseq);
+
+        context.sql.set_raw_config(context, &key, Some(&val)).ok();
+    }
+
+    /// Synchronizes `\\Seen` flags set on the server, eg. by other devices or webmail,
+    /// to the state of the messages in the database.
+    ///
+    /// If the server supports CONDSTORE, only flags changed since the last synchronisation
+    /// are fetched (`CHANGEDSINCE`), otherwise the flags of all unread messages are fetched.
+    async fn sync_seen_flags(&self, context: &Context, folder: &str) -> Result<()> {
+        let (uid_validity, last_seen_uid) = self.select_with_uidvalidity(context, folder)?;
+        if last_seen_uid == 0 {
+            return Ok(());
+        }
+
+        let can_condstore = self.config.read().await.can_condstore;
+        let (modseq_uid_validity, last_modseq) = self.get_config_last_modseq(context, folder);
+        let (set, query) =
+            if can_condstore && modseq_uid_validity == uid_validity && last_modseq > 0 {
+                (
+                    format!("1:{}", last_seen_uid),
+                    format!("(UID FLAGS) (CHANGEDSINCE {})", last_modseq),
+                )
+            } else {
+                // without CONDSTORE or on the first synchronisation,
+                // only the flags of unread messages are interesting.
+                let uids = message::get_unseen_server_uids(context, folder);
+                if uids.is_empty() {
+                    return Ok(());
+                }
+                let query = if can_condstore {
+                    // MODSEQ is used as the starting point for the next CHANGEDSINCE
+                    "(UID FLAGS MODSEQ)"
+                } else {
+                    "(UID FLAGS)"
+                };
+                (uid_set(&uids), query.to_string())
+            };
+
+        let list = if let Some(ref mut session) = &mut *self.session.lock().await {
+            match session.uid_fetch(set, query).await {
+                Ok(list) => list,
+                Err(err) => {
+                    return Err(Error::FetchFailed(err));
+                }
+            }
+        } else {
+            return Err(Error::NoConnection);
+        };
+
+        let mut seen_uids = Vec::new();
+        let mut new_modseq = last_modseq;
+        for fetch in list.iter() {
+            if let Some(modseq) = fetch.modseq {
+                new_modseq = std::cmp::max(new_modseq, modseq);
+            }
+            let is_seen = fetch.flags().any(|flag| match flag {
+                Flag::Seen => true,
+                _ => false,
+            });
+            if let Some(uid) = fetch.uid {
+                if is_seen {
+                    seen_uids.push(uid);
+                }
+            }
+        }
+
+        let cnt = message::markseen_by_server_uids(context, folder, &seen_uids);
+        if cnt > 0 {
+            info!(
+                context,
+                "{} messages in \"{}\" were seen on another device.", cnt, folder
+            );
+        }
+        if can_condstore && new_modseq != last_modseq {
+            self.set_config_last_modseq(context, folder, uid_validity, new_modseq);
+        }
+        Ok(())
+    }
+
     /// Fetches a single message by server UID.
     ///
     /// If it succeeds, the message should be treated as received even
@@ -1242,6 +1395,31 @@ impl Imap {
     }
 }
 
+/// Builds an IMAP sequence set from sorted UIDs, consecutive UIDs are combined to ranges.
+fn uid_set(uids: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &uid in uids {
+        if let Some(last) = ranges.last_mut() {
+            if last.1 + 1 == uid {
+                last.1 = uid;
+                continue;
+            }
+        }
+        ranges.push((uid, uid));
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}:{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 /// Try to get the folder meaning by the name of the folder only used if the server does not support XLIST.
 // TODO: lots languages missing - maybe there is a list somewhere on other MUAs?
 // however, if we fail to find out the sent-folder,
@@ -1393,6 +1571,25 @@ fn prefetch_should_download(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_should_sync_seen_flags() {
+        let imap = Imap::new();
+        task::block_on(async {
+            assert!(imap.should_sync_seen_flags("INBOX", false).await);
+            assert!(!imap.should_sync_seen_flags("INBOX", false).await);
+            assert!(imap.should_sync_seen_flags("INBOX", true).await);
+            assert!(imap.should_sync_seen_flags("Other", false).await);
+
+            if let Some(long_ago) = Instant::now().checked_sub(SEEN_FLAGS_SYNC_INTERVAL) {
+                imap.last_seen_flags_sync
+                    .lock()
+                    .await
+                    .insert("INBOX".to_string(), long_ago);
+                assert!(imap.should_sync_seen_flags("INBOX", false).await);
+            }
+        });
+    }
+
     #[test]
     fn test_parse_message_id() {
         assert_eq!(
@@ -1404,4 +1601,11 @@ mod tests {
             "Mr.PRUe8HJBoaO.3whNvLCMFU0@testrun.org"
         );
     }
+
+    #[test]
+    fn test_uid_set() {
+        assert_eq!(uid_set(&[]), "");
+        assert_eq!(uid_set(&[7]), "7");
+        assert_eq!(uid_set(&[1, 2, 3, 5, 7, 8]), "1:3,5,7:8");
+    }
 }
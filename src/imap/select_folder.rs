@@ -15,6 +15,9 @@ pub enum Error {
     #[fail(display = "IMAP Folder name invalid: {:?}", _0)]
     BadFolderName(String),
 
+    #[fail(display = "IMAP Folder {:?} cannot be selected: {}", _0, _1)]
+    FolderNotSelectable(String, String),
+
     #[fail(display = "IMAP close/expunge failed: {}", _0)]
     CloseExpungeFailed(#[cause] async_imap::error::Error),
 
@@ -97,6 +100,15 @@ impl Imap {
                     Err(async_imap::error::Error::Validate(_)) => {
                         Err(Error::BadFolderName(folder.as_ref().to_string()))
                     }
+                    Err(async_imap::error::Error::No(response)) => {
+                        // the server refused to select the folder, eg. because it does not exist;
+                        // the connection is still fine
+                        self.config.write().await.selected_folder = None;
+                        Err(Error::FolderNotSelectable(
+                            folder.as_ref().to_string(),
+                            response,
+                        ))
+                    }
                     Err(err) => {
                         self.config.write().await.selected_folder = None;
                         self.trigger_reconnect();
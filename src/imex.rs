@@ -1,10 +1,14 @@
 //! # Import/export module
 
 use core::cmp::{max, min};
+use std::collections::HashSet;
+use std::io::{Read, Write};
 use std::path::Path;
 
 use num_traits::FromPrimitive;
 use rand::{thread_rng, Rng};
+use rusqlite::NO_PARAMS;
+use serde::Serialize;
 
 use crate::blob::BlobObject;
 use crate::chat;
@@ -12,13 +16,16 @@ use crate::chat::delete_and_reset_all_device_msgs;
 use crate::config::Config;
 use crate::configure::*;
 use crate::constants::*;
+use crate::contact::addr_cmp;
 use crate::context::Context;
 use crate::dc_tools::*;
 use crate::e2ee;
 use crate::error::*;
 use crate::events::Event;
+use crate::filter;
 use crate::job::*;
 use crate::key::{self, Key};
+use crate::keyring::Keyring;
 use crate::message::{Message, MsgId};
 use crate::mimeparser::SystemMessage;
 use crate::param::*;
@@ -38,21 +45,38 @@ pub enum ImexMode {
     /// Import private keys found in the directory given as `param1`.
     /// The last imported key is made the default keys unless its name contains the string `legacy`.
     /// Public keys are not imported.
+    /// Keys protected by a passphrase are unlocked using the passphrase given as `param2`.
     ImportSelfKeys = 2,
 
     /// Export a backup to the directory given as `param1`.
     /// The backup contains all contacts, chats, images and other data and device independent settings.
     /// The backup does not contain device dependent settings as ringtones or LED notification settings.
-    /// The name of the backup is typically `delta-chat.<day>.bak`, if more than one backup is create on a day,
-    /// the format is `delta-chat.<day>-<number>.bak`
+    /// The backup is a tar archive with the name `delta-chat-<day>-<number>.tar`
+    /// containing the database file and the blobs.
+    /// If the database is encrypted, the backup is encrypted with the same passphrase.
+    /// If a passphrase is given as `param2`, the backup is additionally encrypted symmetrically
+    /// as a stream of OpenPGP messages, see is_backup_encrypted().
     ExportBackup = 11,
 
     /// `param1` is the file (not: directory) to import. The file is normally
-    /// created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
+    /// created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(),
+    /// backups in the older `.bak` format are supported as well. Importing a backup
     /// is only possible as long as the context is not configured or used in another way.
+    /// Encrypted backups can only be imported into a database opened with the same passphrase.
+    /// Backups encrypted by a passphrase on export are decrypted using the passphrase given as `param2`.
     ImportBackup = 12,
 }
 
+/// Start of backups encrypted by a passphrase,
+/// followed by the data written by pgp::SymmEncryptWriter.
+const BACKUP_ENCRYPTED_MAGIC: &[u8] = b"DC-ENCRYPTED-BACKUP-1\n";
+
+/// Name of the database file inside of tar backups.
+const BACKUP_DBFILE_NAME: &str = "dc_database_backup.sqlite";
+
+/// Directory containing the blobs inside of tar backups.
+const BACKUP_BLOBS_DIR: &str = "blobs_backup";
+
 /// Import/export things.
 /// For this purpose, the function creates a job that is executed in the IMAP-thread then;
 /// this requires to call dc_perform_inbox_jobs() regularly.
@@ -70,12 +94,20 @@ pub enum ImexMode {
 ///
 /// Only one import-/export-progress can run at the same time.
 /// To cancel an import-/export-progress, use dc_stop_ongoing_process().
-pub fn imex(context: &Context, what: ImexMode, param1: Option<impl AsRef<Path>>) {
+pub fn imex(
+    context: &Context,
+    what: ImexMode,
+    param1: Option<impl AsRef<Path>>,
+    param2: Option<&str>,
+) {
     let mut param = Params::new();
     param.set_int(Param::Cmd, what as i32);
     if let Some(param1) = param1 {
         param.set(Param::Arg, param1.as_ref().to_string_lossy());
     }
+    // the passphrase is not stored in the job params
+    // which are written unencrypted to the database
+    *context.imex_passphrase.lock().unwrap() = param2.map(|s| s.to_string());
 
     job_kill_action(context, Action::ImexImap);
     job_add(context, Action::ImexImap, 0, param, 0);
@@ -92,18 +124,14 @@ pub fn has_backup(context: &Context, dir_name: impl AsRef<Path>) -> Result<Strin
             let path = dirent.path();
             let name = dirent.file_name();
             let name = name.to_string_lossy();
-            if name.starts_with("delta-chat") && name.ends_with(".bak") {
-                let sql = Sql::new();
-                if sql.open(context, &path, true) {
-                    let curr_backup_time = sql
-                        .get_raw_config_int(context, "backup_time")
-                        .unwrap_or_default();
+            if name.starts_with("delta-chat") && (name.ends_with(".tar") || name.ends_with(".bak"))
+            {
+                if let Some(curr_backup_time) = get_backup_time(context, &path) {
                     if curr_backup_time > newest_backup_time {
                         newest_backup_path = Some(path);
                         newest_backup_time = curr_backup_time;
                     }
                     info!(context, "backup_time of {} is {}", name, curr_backup_time);
-                    sql.close(&context);
                 }
             }
         }
@@ -114,6 +142,48 @@ pub fn has_backup(context: &Context, dir_name: impl AsRef<Path>) -> Result<Strin
     }
 }
 
+fn is_backup_file_name(name: &str) -> bool {
+    name.ends_with(".tar")
+        || name.ends_with(".bak")
+        || name.ends_with(".tar.partial")
+        || name.ends_with(".bak-tmp")
+}
+
+fn get_backup_time(context: &Context, backup: &Path) -> Option<i64> {
+    if is_backup_encrypted(backup).unwrap_or_default() || is_tar_backup(backup).unwrap_or_default()
+    {
+        // the backup time is stored inside the database which is not directly readable,
+        // use the file time instead
+        let modified = std::fs::metadata(backup).and_then(|m| m.modified()).ok()?;
+        let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+        return Some(since_epoch.as_secs() as i64);
+    }
+
+    let sql = Sql::new();
+    sql.set_passphrase(context.sql.get_passphrase());
+    if sql.open(context, backup, true) {
+        let backup_time = sql
+            .get_raw_config_int(context, "backup_time")
+            .unwrap_or_default();
+        sql.close(&context);
+        Some(backup_time as i64)
+    } else {
+        None
+    }
+}
+
+/// Returns true if the backup is encrypted by a passphrase.
+///
+/// Such backups are created by passing a passphrase to ImexMode::ExportBackup,
+/// the same passphrase is needed for ImexMode::ImportBackup.
+pub fn is_backup_encrypted(backup: impl AsRef<Path>) -> Result<bool> {
+    let mut start = Vec::with_capacity(BACKUP_ENCRYPTED_MAGIC.len());
+    std::fs::File::open(backup.as_ref())?
+        .take(BACKUP_ENCRYPTED_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    Ok(start == BACKUP_ENCRYPTED_MAGIC)
+}
+
 pub fn initiate_key_transfer(context: &Context) -> Result<String> {
     ensure!(context.alloc_ongoing(), "could not allocate ongoing");
     let res = do_initiate_key_transfer(context);
@@ -268,7 +338,7 @@ pub fn continue_key_transfer(context: &Context, msg_id: MsgId, setup_code: &str)
         let file = dc_open_file(context, filename)?;
         let sc = normalize_setup_code(setup_code);
         let armored_key = decrypt_setup_file(context, &sc, file)?;
-        set_self_key(context, &armored_key, true, true)?;
+        set_self_key(context, &armored_key, true, true, None)?;
         maybe_add_bcc_self_device_msg(context)?;
 
         Ok(())
@@ -282,14 +352,38 @@ fn set_self_key(
     armored: &str,
     set_default: bool,
     prefer_encrypt_required: bool,
+    passphrase: Option<&str>,
 ) -> Result<()> {
+    let self_addr = context.get_config(Config::ConfiguredAddr);
+    ensure!(self_addr.is_some(), "Missing self addr");
+    let self_addr = self_addr.unwrap_or_default();
+
     // try hard to only modify key-state
-    let keys = Key::from_armored_string(armored, KeyType::Private)
-        .and_then(|(k, h)| if k.verify() { Some((k, h)) } else { None })
-        .and_then(|(k, h)| k.split_key().map(|pub_key| (k, pub_key, h)));
+    let (private_key, header) = Key::from_armored_string(armored, KeyType::Private)
+        .filter(|(k, _)| k.verify())
+        .ok_or_else(|| format_err!("Not a valid private key"))?;
+    let private_key = match private_key {
+        Key::Secret(secret) => {
+            if pgp::is_secret_key_protected(&secret) {
+                match passphrase {
+                    Some(passphrase) => Key::Secret(pgp::unlock_secret_key(secret, passphrase)?),
+                    None => bail!("Private key is protected by a passphrase"),
+                }
+            } else {
+                Key::Secret(secret)
+            }
+        }
+        Key::Public(_) => bail!("Not a private key"),
+    };
+    if let Err(err) = check_self_key_addr(context, &private_key, &self_addr) {
+        // keys created for a previous address are still accepted,
+        // eg. after an address change
+        warn!(context, "{}", err);
+    }
+    let public_key = private_key
+        .split_key()
+        .ok_or_else(|| format_err!("Cannot get public key from private key"))?;
 
-    ensure!(keys.is_some(), "Not a valid private key");
-    let (private_key, public_key, header) = keys.unwrap();
     let preferencrypt = header.get("Autocrypt-Prefer-Encrypt");
     match preferencrypt.map(|s| s.as_str()) {
         Some(headerval) => {
@@ -311,9 +405,7 @@ fn set_self_key(
         }
     };
 
-    let self_addr = context.get_config(Config::ConfiguredAddr);
-    ensure!(self_addr.is_some(), "Missing self addr");
-    let addr = EmailAddress::new(&self_addr.unwrap_or_default())?;
+    let addr = EmailAddress::new(&self_addr)?;
 
     let (public, secret) = match (public_key, private_key) {
         (Key::Public(p), Key::Secret(s)) => (p, s),
@@ -336,6 +428,51 @@ fn set_self_key(
     Ok(())
 }
 
+/// Checks that the key belongs to the configured address.
+///
+/// Keys exported from other OpenPGP implementations may have several user IDs,
+/// the key belongs to the address if any of them matches;
+/// if it is not the primary user ID, a warning is logged.
+fn check_self_key_addr(context: &Context, key: &Key, self_addr: &str) -> Result<()> {
+    let users = match key {
+        Key::Secret(secret) => &secret.details.users,
+        Key::Public(public) => &public.details.users,
+    };
+    let uid_addr = |uid: &str| -> String {
+        match (uid.rfind('<'), uid.rfind('>')) {
+            (Some(start), Some(end)) if start < end => uid[start + 1..end].trim().to_string(),
+            _ => uid.trim().to_string(),
+        }
+    };
+    let primary_user = users
+        .iter()
+        .find(|user| user.signatures.iter().any(|sig| sig.is_primary()))
+        .or_else(|| users.first());
+
+    if let Some(primary_user) = primary_user {
+        if addr_cmp(uid_addr(primary_user.id.id()), self_addr) {
+            return Ok(());
+        }
+    }
+    ensure!(
+        users
+            .iter()
+            .any(|user| addr_cmp(uid_addr(user.id.id()), self_addr)),
+        "Key does not belong to {}, user IDs: {}",
+        self_addr,
+        users
+            .iter()
+            .map(|user| user.id.id())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    warn!(
+        context,
+        "Primary user ID of imported key does not match {}", self_addr
+    );
+    Ok(())
+}
+
 fn decrypt_setup_file<T: std::io::Read + std::io::Seek>(
     _context: &Context,
     passphrase: &str,
@@ -365,6 +502,8 @@ pub fn JobImexImap(context: &Context, job: &Job) -> Result<()> {
     ensure!(context.alloc_ongoing(), "could not allocate ongoing");
     let what: Option<ImexMode> = job.param.get_int(Param::Cmd).and_then(ImexMode::from_i32);
     let param = job.param.get(Param::Arg).unwrap_or_default();
+    let passphrase = context.imex_passphrase.lock().unwrap().take();
+    let passphrase = passphrase.as_ref().map(|s| s.as_str());
 
     ensure!(!param.is_empty(), "No Import/export dir/file given.");
     info!(context, "Import/export process started.");
@@ -383,9 +522,9 @@ pub fn JobImexImap(context: &Context, job: &Job) -> Result<()> {
     let path = Path::new(param);
     let success = match what {
         Some(ImexMode::ExportSelfKeys) => export_self_keys(context, path),
-        Some(ImexMode::ImportSelfKeys) => import_self_keys(context, path),
-        Some(ImexMode::ExportBackup) => export_backup(context, path),
-        Some(ImexMode::ImportBackup) => import_backup(context, path),
+        Some(ImexMode::ImportSelfKeys) => import_self_keys(context, path, passphrase),
+        Some(ImexMode::ExportBackup) => export_backup(context, path, passphrase),
+        Some(ImexMode::ImportBackup) => import_backup(context, path, passphrase),
         None => {
             bail!("unknown IMEX type");
         }
@@ -405,11 +544,16 @@ pub fn JobImexImap(context: &Context, job: &Job) -> Result<()> {
 }
 
 /// Import Backup
-fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Result<()> {
+fn import_backup(
+    context: &Context,
+    backup_to_import: impl AsRef<Path>,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let backup_to_import = backup_to_import.as_ref();
     info!(
         context,
         "Import \"{}\" to \"{}\".",
-        backup_to_import.as_ref().display(),
+        backup_to_import.display(),
         context.get_dbfile().display()
     );
 
@@ -417,25 +561,115 @@ fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Resul
         !dc_is_configured(context),
         "Cannot import backups to accounts in use."
     );
-    context.sql.close(&context);
-    dc_delete_file(context, context.get_dbfile());
-    ensure!(
-        !context.get_dbfile().exists(),
-        "Cannot delete old database."
-    );
 
-    ensure!(
-        dc_copy_file(context, backup_to_import.as_ref(), context.get_dbfile()),
-        "could not copy file"
-    );
-    /* error already logged */
-    /* re-open copied database file */
-    ensure!(
-        context.sql.open(&context, &context.get_dbfile(), false),
-        "could not re-open db"
-    );
+    if !is_backup_encrypted(backup_to_import)? {
+        return import_backup_file(context, backup_to_import);
+    }
 
-    delete_and_reset_all_device_msgs(&context)?;
+    let decrypted = dbfile_with_suffix(context, "-import");
+    let res = decrypt_backup_file(context, backup_to_import, passphrase, &decrypted)
+        .and_then(|_| import_backup_file(context, &decrypted));
+    dc_delete_file(context, &decrypted);
+    res
+}
+
+/// Decrypts a backup encrypted by a passphrase to `dest`.
+fn decrypt_backup_file(
+    context: &Context,
+    backup: &Path,
+    passphrase: Option<&str>,
+    dest: &Path,
+) -> Result<()> {
+    let passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None => bail!("Backup is encrypted, a passphrase is required."),
+    };
+    let mut file = std::io::BufReader::new(std::fs::File::open(backup)?);
+    let mut magic = vec![0; BACKUP_ENCRYPTED_MAGIC.len()];
+    file.read_exact(&mut magic)?;
+    ensure!(magic == BACKUP_ENCRYPTED_MAGIC, "Backup is not encrypted");
+
+    let res: Result<()> = std::fs::File::create(dest)
+        .map_err(Into::into)
+        .and_then(|dest_file| {
+            pgp::symm_decrypt_stream(passphrase, file, std::io::BufWriter::new(dest_file))
+        });
+    if let Err(err) = res {
+        dc_delete_file(context, dest);
+        bail!("Cannot decrypt backup, wrong passphrase? {}", err);
+    }
+    Ok(())
+}
+
+fn import_backup_file(context: &Context, backup_to_import: &Path) -> Result<()> {
+    if is_tar_backup(backup_to_import)? {
+        import_tar_backup(context, backup_to_import)
+    } else {
+        import_legacy_backup(context, backup_to_import)
+    }
+}
+
+/// Returns true if the backup is a tar archive as written by export_backup(),
+/// older backups are database files containing the blobs in the `backup_blobs` table.
+fn is_tar_backup(backup: &Path) -> Result<bool> {
+    let mut header = Vec::with_capacity(262);
+    std::fs::File::open(backup)?
+        .take(262)
+        .read_to_end(&mut header)?;
+    Ok(header.len() == 262 && &header[257..] == b"ustar")
+}
+
+fn import_tar_backup(context: &Context, backup_to_import: &Path) -> Result<()> {
+    let backup_size = std::fs::metadata(backup_to_import)?.len().max(1);
+    let mut archive = tar::Archive::new(std::fs::File::open(backup_to_import)?);
+    let unpacked_db = dbfile_with_suffix(context, "-import-db");
+    let mut db_imported = false;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if context.shall_stop_ongoing() {
+            bail!("received stop signal");
+        }
+        let permille = max(min(entry.raw_file_position() * 1000 / backup_size, 990), 10);
+        context.call_cb(Event::ImexProgress(permille as usize));
+
+        let path = entry.path()?.into_owned();
+        if !entry.header().entry_type().is_file() {
+            // links could make the blobdir point to arbitrary files
+            warn!(
+                context,
+                "Ignoring {} in backup, not a regular file",
+                path.display()
+            );
+            continue;
+        }
+        if path == Path::new(BACKUP_DBFILE_NAME) {
+            // the database comes first, so it can be checked before any blob is written
+            entry.unpack(&unpacked_db)?;
+            let res = import_backup_database(context, &unpacked_db);
+            dc_delete_file(context, &unpacked_db);
+            res?;
+            db_imported = true;
+        } else if path.starts_with(BACKUP_BLOBS_DIR) {
+            ensure!(db_imported, "Backup does not start with the database.");
+            // use the file name only, paths must not point outside of the blobdir
+            if let Some(file_name) = path.file_name() {
+                entry.unpack(context.get_blobdir().join(file_name))?;
+            }
+        } else {
+            warn!(
+                context,
+                "Ignoring unknown file {} in backup",
+                path.display()
+            );
+        }
+    }
+    ensure!(db_imported, "Backup does not contain a database.");
+    Ok(())
+}
+
+fn import_legacy_backup(context: &Context, backup_to_import: &Path) -> Result<()> {
+    import_backup_database(context, backup_to_import)?;
 
     let total_files_cnt = context
         .sql
@@ -495,18 +729,315 @@ fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Resul
     }
 }
 
+/// Replaces the database by the database file of a backup.
+fn import_backup_database(context: &Context, backup_db: &Path) -> Result<()> {
+    // backups are encrypted with the passphrase of the database they were exported from,
+    // unencrypted backups are imported into encrypted databases as well.
+    let passphrase = context.sql.get_passphrase();
+    let backup_passphrase = if can_open_backup(context, backup_db, passphrase.clone()) {
+        passphrase.clone()
+    } else if passphrase.is_some() && can_open_backup(context, backup_db, None) {
+        None
+    } else {
+        bail!("Cannot open backup, it may be encrypted with another passphrase.");
+    };
+
+    context.sql.close(&context);
+    dc_delete_file(context, context.get_dbfile());
+    ensure!(
+        !context.get_dbfile().exists(),
+        "Cannot delete old database."
+    );
+
+    ensure!(
+        dc_copy_file(context, backup_db, context.get_dbfile()),
+        "could not copy file"
+    );
+    /* error already logged */
+    /* re-open copied database file */
+    context.sql.set_passphrase(backup_passphrase.clone());
+    ensure!(
+        context.sql.open(&context, &context.get_dbfile(), false),
+        "could not re-open db"
+    );
+    if backup_passphrase != passphrase {
+        context
+            .sql
+            .change_passphrase(context, context.get_dbfile(), passphrase)?;
+    }
+
+    // the filter rules of the backup replace the former ones
+    filter::invalidate_cache(context);
+
+    delete_and_reset_all_device_msgs(&context)?;
+    Ok(())
+}
+
+/// Result of verify_backup().
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BackupReport {
+    /// Address of the account the backup belongs to.
+    pub addr: String,
+    /// Time the backup was created, in seconds since epoch.
+    pub backup_time: i64,
+    /// Size of the backup file in bytes.
+    pub size: u64,
+    /// Database version of the backup.
+    pub dbversion: i32,
+    /// Whether the database version can be handled by this version of the core.
+    pub dbversion_supported: bool,
+    /// Result of `PRAGMA integrity_check`, `ok` if the database is intact.
+    pub integrity_check: String,
+    pub msg_count: usize,
+    pub chat_count: usize,
+    /// Blobs referenced by messages, chats or contacts that are not contained in the backup.
+    pub missing_blobs: Vec<String>,
+    /// Whether a message encrypted to the self key can be decrypted using the self private key.
+    pub keypair_ok: bool,
+}
+
+impl BackupReport {
+    /// Returns true if all checks passed and the backup can be imported.
+    pub fn is_ok(&self) -> bool {
+        self.dbversion_supported
+            && self.integrity_check == "ok"
+            && self.missing_blobs.is_empty()
+            && self.keypair_ok
+    }
+}
+
+/// Checks a backup without importing it.
+///
+/// The backup database is opened read-only and checked for integrity,
+/// the blobs referenced by it and the self keypair are checked as well.
+/// `passphrase` is needed for backups encrypted on export, see is_backup_encrypted().
+/// Errors are only returned if the backup cannot be read at all,
+/// failed checks are contained in the returned report.
+pub fn verify_backup(
+    context: &Context,
+    backup: impl AsRef<Path>,
+    passphrase: Option<&str>,
+) -> Result<BackupReport> {
+    let decrypted = dbfile_with_suffix(context, "-verify");
+    let unpacked_db = dbfile_with_suffix(context, "-verify-db");
+    let res = verify_backup_file(
+        context,
+        backup.as_ref(),
+        passphrase,
+        &decrypted,
+        &unpacked_db,
+    );
+    dc_delete_file(context, &decrypted);
+    dc_delete_file(context, &unpacked_db);
+    res
+}
+
+fn verify_backup_file(
+    context: &Context,
+    backup: &Path,
+    passphrase: Option<&str>,
+    decrypted: &Path,
+    unpacked_db: &Path,
+) -> Result<BackupReport> {
+    let size = std::fs::metadata(backup)?.len();
+    let backup = if is_backup_encrypted(backup)? {
+        decrypt_backup_file(context, backup, passphrase, decrypted)?;
+        decrypted
+    } else {
+        backup
+    };
+
+    let mut blobs = HashSet::new();
+    let backup_db = if is_tar_backup(backup)? {
+        let mut archive = tar::Archive::new(std::fs::File::open(backup)?);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.into_owned();
+            if path == Path::new(BACKUP_DBFILE_NAME) {
+                entry.unpack(unpacked_db)?;
+            } else if path.starts_with(BACKUP_BLOBS_DIR) {
+                if let Some(file_name) = path.file_name() {
+                    blobs.insert(file_name.to_string_lossy().into_owned());
+                }
+            }
+        }
+        ensure!(unpacked_db.exists(), "Backup does not contain a database.");
+        unpacked_db
+    } else {
+        backup
+    };
+
+    let sql = Sql::new();
+    sql.set_passphrase(context.sql.get_passphrase());
+    if !sql.open(context, backup_db, true) {
+        sql.set_passphrase(None);
+        ensure!(
+            sql.open(context, backup_db, true),
+            "Cannot open backup, it may be encrypted with another passphrase."
+        );
+    }
+    let report = get_backup_report(context, &sql, size, blobs);
+    sql.close(context);
+    report
+}
+
+fn get_backup_report(
+    context: &Context,
+    sql: &Sql,
+    size: u64,
+    mut blobs: HashSet<String>,
+) -> Result<BackupReport> {
+    let integrity_check: String =
+        sql.query_row("PRAGMA integrity_check;", NO_PARAMS, |row| row.get(0))?;
+    let dbversion = sql
+        .get_raw_config_int(context, "dbversion")
+        .unwrap_or_default();
+    let own_dbversion = context
+        .sql
+        .get_raw_config_int(context, "dbversion")
+        .unwrap_or_default();
+    let addr = sql
+        .get_raw_config(context, "configured_addr")
+        .unwrap_or_default();
+    let backup_time = sql
+        .get_raw_config_int(context, "backup_time")
+        .unwrap_or_default() as i64;
+    let msg_count = sql.query_row(
+        "SELECT COUNT(*) FROM msgs WHERE id>?;",
+        params![DC_MSG_ID_LAST_SPECIAL],
+        |row| row.get::<_, isize>(0),
+    )? as usize;
+    let chat_count = sql.query_row(
+        "SELECT COUNT(*) FROM chats WHERE id>?;",
+        params![DC_CHAT_ID_LAST_SPECIAL],
+        |row| row.get::<_, isize>(0),
+    )? as usize;
+
+    if sql.table_exists("backup_blobs") {
+        // legacy backups contain the blobs in the database
+        sql.query_map(
+            "SELECT file_name FROM backup_blobs;",
+            NO_PARAMS,
+            |row| row.get::<_, String>(0),
+            |rows| {
+                for row in rows {
+                    blobs.insert(row?);
+                }
+                Ok(())
+            },
+        )?;
+    }
+    let mut referenced = HashSet::new();
+    sql::maybe_add_from_param(
+        context,
+        sql,
+        &mut referenced,
+        "SELECT param FROM msgs WHERE chat_id!=3 AND type!=10;",
+        Param::File,
+    );
+    sql::maybe_add_from_param(
+        context,
+        sql,
+        &mut referenced,
+        "SELECT param FROM chats;",
+        Param::ProfileImage,
+    );
+    sql::maybe_add_from_param(
+        context,
+        sql,
+        &mut referenced,
+        "SELECT param FROM contacts;",
+        Param::ProfileImage,
+    );
+    let mut missing_blobs: Vec<String> = referenced.difference(&blobs).cloned().collect();
+    missing_blobs.sort();
+
+    Ok(BackupReport {
+        keypair_ok: check_backup_keypair(context, sql, &addr),
+        addr,
+        backup_time,
+        size,
+        dbversion,
+        dbversion_supported: dbversion <= own_dbversion,
+        integrity_check,
+        msg_count,
+        chat_count,
+        missing_blobs,
+    })
+}
+
+/// Encrypts a test message to the self public key and decrypts it with the self private key.
+fn check_backup_keypair(context: &Context, sql: &Sql, addr: &str) -> bool {
+    let public_key = Key::from_self_public(context, addr, sql);
+    let private_key = Key::from_self_private(context, addr, sql);
+    let (public_key, private_key) = match (public_key, private_key) {
+        (Some(public_key), Some(private_key)) => (public_key, private_key),
+        _ => {
+            warn!(context, "Backup does not contain a self keypair");
+            return false;
+        }
+    };
+
+    let mut public_keyring = Keyring::default();
+    public_keyring.add_ref(&public_key);
+    let mut private_keyring = Keyring::default();
+    private_keyring.add_ref(&private_key);
+    let plain = b"backup keypair check";
+    match pgp::pk_encrypt(plain, &public_keyring, None).and_then(|ctext| {
+        pgp::pk_decrypt(ctext.as_bytes(), &private_keyring, &public_keyring, None)
+    }) {
+        Ok(decrypted) => decrypted == plain,
+        Err(err) => {
+            warn!(
+                context,
+                "Cannot decrypt using the self key of the backup: {}", err
+            );
+            false
+        }
+    }
+}
+
+fn can_open_backup(
+    context: &Context,
+    backup: impl AsRef<Path>,
+    passphrase: Option<String>,
+) -> bool {
+    let sql = Sql::new();
+    sql.set_passphrase(passphrase);
+    let res = sql.open(context, backup.as_ref(), true);
+    if res {
+        sql.close(context);
+    }
+    res
+}
+
+/// Returns a path beside the database file, used for temporary files.
+fn dbfile_with_suffix(context: &Context, suffix: &str) -> std::path::PathBuf {
+    let mut name = context.get_dbfile().as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
 /*******************************************************************************
  * Export backup
  ******************************************************************************/
-/* the FILE_PROGRESS macro calls the callback with the permille of files processed.
-The macro avoids weird values of 0% or 100% while still working. */
-fn export_backup(context: &Context, dir: impl AsRef<Path>) -> Result<()> {
+/// Exports a backup to `dir`.
+///
+/// The backup is a tar archive containing the database file
+/// followed by the blobs in the directory `blobs_backup`.
+/// It is written to a temporary file first and renamed on success,
+/// so an existing file with the final name is always a complete backup.
+/// If a passphrase is given, the archive is encrypted while it is written,
+/// so no unencrypted data is written to `dir`.
+fn export_backup(context: &Context, dir: impl AsRef<Path>, passphrase: Option<&str>) -> Result<()> {
     // get a fine backup file name (the name includes the date so that multiple backup instances are possible)
-    // FIXME: we should write to a temporary file first and rename it on success. this would guarantee the backup is complete.
-    // let dest_path_filename = dc_get_next_backup_file(context, dir, res);
     let now = time();
     let dest_path_filename = dc_get_next_backup_path(dir, now)?;
-    let dest_path_string = dest_path_filename.to_string_lossy().to_string();
+    let tmp_path_filename = dest_path_filename.with_extension("tar.partial");
+    let db_copy = dbfile_with_suffix(context, "-backup");
 
     sql::housekeeping(context);
 
@@ -520,98 +1051,108 @@ fn export_backup(context: &Context, dir: impl AsRef<Path>) -> Result<()> {
         context.get_dbfile().display(),
         dest_path_filename.display(),
     );
-    let copied = dc_copy_file(context, context.get_dbfile(), &dest_path_filename);
+    let copied = dc_copy_file(context, context.get_dbfile(), &db_copy);
     context.sql.open(&context, &context.get_dbfile(), false);
 
     if !copied {
         bail!(
             "could not copy file from '{}' to '{}'",
             context.get_dbfile().display(),
-            dest_path_string
+            db_copy.display()
         );
     }
+
+    let res = set_backup_time(context, &db_copy, now)
+        .and_then(|_| {
+            let file = std::fs::File::create(&tmp_path_filename)?;
+            let file = match passphrase {
+                Some(passphrase) => {
+                    let mut out = std::io::BufWriter::new(file);
+                    out.write_all(BACKUP_ENCRYPTED_MAGIC)?;
+                    let writer = pgp::SymmEncryptWriter::new(passphrase, out);
+                    write_backup_tar(context, &db_copy, writer)?
+                        .finish()?
+                        .into_inner()
+                        .map_err(|err| format_err!("{}", err))?
+                }
+                None => write_backup_tar(context, &db_copy, file)?,
+            };
+            file.sync_all()?;
+            Ok(())
+        })
+        .and_then(|_| {
+            std::fs::rename(&tmp_path_filename, &dest_path_filename)?;
+            Ok(())
+        });
+    dc_delete_file(context, &db_copy);
+    if let Err(err) = res {
+        dc_delete_file(context, &tmp_path_filename);
+        error!(context, "backup failed: {}", err);
+        return Err(err);
+    }
+
+    context.call_cb(Event::ImexFileWritten(dest_path_filename));
+    Ok(())
+}
+
+fn set_backup_time(context: &Context, db_copy: &Path, backup_time: i64) -> Result<()> {
+    // the copied database file is encrypted with the same passphrase, if any
     let dest_sql = Sql::new();
+    dest_sql.set_passphrase(context.sql.get_passphrase());
     ensure!(
-        dest_sql.open(context, &dest_path_filename, false),
+        dest_sql.open(context, db_copy, false),
         "could not open exported database {}",
-        dest_path_string
+        db_copy.display()
     );
-    let res = match add_files_to_export(context, &dest_sql) {
-        Err(err) => {
-            dc_delete_file(context, &dest_path_filename);
-            error!(context, "backup failed: {}", err);
-            Err(err)
-        }
-        Ok(()) => {
-            dest_sql.set_raw_config_int(context, "backup_time", now as i32)?;
-            context.call_cb(Event::ImexFileWritten(dest_path_filename));
-            Ok(())
-        }
-    };
+    let res = dest_sql.set_raw_config_int(context, "backup_time", backup_time as i32);
     dest_sql.close(context);
-
     Ok(res?)
 }
 
-fn add_files_to_export(context: &Context, sql: &Sql) -> Result<()> {
-    // add all files as blobs to the database copy (this does not require
-    // the source to be locked, neigher the destination as it is used only here)
-    if !sql.table_exists("backup_blobs") {
-        sql::execute(
-            context,
-            &sql,
-            "CREATE TABLE backup_blobs (id INTEGER PRIMARY KEY, file_name, file_content);",
-            params![],
-        )?
-    }
-    // copy all files from BLOBDIR into backup-db
-    let mut total_files_cnt = 0;
-    let dir = context.get_blobdir();
-    let dir_handle = std::fs::read_dir(&dir)?;
-    total_files_cnt += dir_handle.filter(|r| r.is_ok()).count();
+/// Writes the tar archive to `out` and returns it.
+fn write_backup_tar<W: Write>(context: &Context, db_copy: &Path, out: W) -> Result<W> {
+    let mut builder = tar::Builder::new(out);
+    builder.append_path_with_name(db_copy, BACKUP_DBFILE_NAME)?;
 
+    let dir = context.get_blobdir();
+    let total_files_cnt = std::fs::read_dir(&dir)?.filter(|r| r.is_ok()).count();
     info!(context, "EXPORT: total_files_cnt={}", total_files_cnt);
-    // scan directory, pass 2: copy files
-    let dir_handle = std::fs::read_dir(&dir)?;
-    let exported_all_files = sql.prepare(
-        "INSERT INTO backup_blobs (file_name, file_content) VALUES (?, ?);",
-        |mut stmt, _| {
-            let mut processed_files_cnt = 0;
-            for entry in dir_handle {
-                let entry = entry?;
-                if context.shall_stop_ongoing() {
-                    return Ok(false);
-                }
-                processed_files_cnt += 1;
-                let permille = max(min(processed_files_cnt * 1000 / total_files_cnt, 990), 10);
-                context.call_cb(Event::ImexProgress(permille));
 
-                let name_f = entry.file_name();
-                let name = name_f.to_string_lossy();
-                if name.starts_with("delta-chat") && name.ends_with(".bak") {
-                    continue;
-                }
-                info!(context, "EXPORT: copying filename={}", name);
-                let curr_path_filename = context.get_blobdir().join(entry.file_name());
-                if let Ok(buf) = dc_read_file(context, &curr_path_filename) {
-                    if buf.is_empty() {
-                        continue;
-                    }
-                    // bail out if we can't insert
-                    stmt.execute(params![name, buf])?;
-                }
-            }
-            Ok(true)
-        },
-    )?;
-    ensure!(exported_all_files, "canceled during export-files");
-    Ok(())
+    for (processed_files_cnt, entry) in std::fs::read_dir(&dir)?.enumerate() {
+        let entry = entry?;
+        if context.shall_stop_ongoing() {
+            bail!("canceled during export-files");
+        }
+        let permille = max(
+            min((processed_files_cnt + 1) * 1000 / total_files_cnt, 990),
+            10,
+        );
+        context.call_cb(Event::ImexProgress(permille));
+
+        let name = entry.file_name();
+        if !entry.file_type()?.is_file() || entry.metadata()?.len() == 0 {
+            continue;
+        }
+        let name_str = name.to_string_lossy();
+        if name_str.starts_with("delta-chat") && is_backup_file_name(&name_str) {
+            // backups exported to the blobdir, including the one being written
+            continue;
+        }
+        info!(context, "EXPORT: copying filename={}", name_str);
+        builder.append_path_with_name(entry.path(), Path::new(BACKUP_BLOBS_DIR).join(name))?;
+    }
+
+    Ok(builder.into_inner()?)
 }
 
 /*******************************************************************************
  * Classic key import
  ******************************************************************************/
-fn import_self_keys(context: &Context, dir: impl AsRef<Path>) -> Result<()> {
+fn import_self_keys(
+    context: &Context,
+    dir: impl AsRef<Path>,
+    passphrase: Option<&str>,
+) -> Result<()> {
     /* hint: even if we switch to import Autocrypt Setup Files, we should leave the possibility to import
     plain ASC keys, at least keys without a password, if we do not want to implement a password entry function.
     Importing ASC keys is useful to use keys in Delta Chat used by any other non-Autocrypt-PGP implementation.
@@ -646,7 +1187,7 @@ fn import_self_keys(context: &Context, dir: impl AsRef<Path>) -> Result<()> {
         match dc_read_file(context, &path_plus_name) {
             Ok(buf) => {
                 let armored = std::string::String::from_utf8_lossy(&buf);
-                if let Err(err) = set_self_key(context, &armored, set_default, false) {
+                if let Err(err) = set_self_key(context, &armored, set_default, false, passphrase) {
                     error!(context, "set_self_key: {}", err);
                     continue;
                 }
@@ -843,4 +1384,306 @@ mod tests {
         assert_eq!(headers.get(HEADER_AUTOCRYPT), Some(&"mutual".to_string()));
         assert!(headers.get(HEADER_SETUPCODE).is_none());
     }
+
+    const S_PROTECTED_KEY: &str = include_str!("../test-data/key/carol-secret-protected.asc");
+
+    #[test]
+    fn test_set_self_key_protected() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("carol@example.org"))
+            .unwrap();
+
+        assert!(set_self_key(&t.ctx, S_PROTECTED_KEY, true, false, None).is_err());
+        assert!(set_self_key(&t.ctx, S_PROTECTED_KEY, true, false, Some("wrong")).is_err());
+        assert!(Key::from_self_public(&t.ctx, "carol@example.org", &t.ctx.sql).is_none());
+
+        set_self_key(
+            &t.ctx,
+            S_PROTECTED_KEY,
+            true,
+            false,
+            Some("correct horse battery staple"),
+        )
+        .unwrap();
+        let key = Key::from_self_private(&t.ctx, "carol@example.org", &t.ctx.sql).unwrap();
+        assert_eq!(
+            key.fingerprint(),
+            "4E16771E469003843B1A4BDDB276DCE43F5BE1B7"
+        );
+        match key {
+            Key::Secret(secret) => {
+                assert!(!pgp::is_secret_key_protected(&secret));
+                assert_eq!(secret.secret_subkeys.len(), 1);
+            }
+            Key::Public(_) => panic!("expected a secret key"),
+        }
+    }
+
+    #[test]
+    fn test_set_self_key_addr_mismatch() {
+        let warnings = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let warnings2 = std::sync::Arc::clone(&warnings);
+        let t = test_context(Some(Box::new(move |_, event| {
+            if let Event::Warning(msg) = event {
+                warnings2.lock().unwrap().push(msg);
+            }
+        })));
+        let key_warnings = || {
+            warnings
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|msg| msg.contains("Key does not belong to"))
+                .count()
+        };
+
+        // matching a secondary user ID is accepted
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("carol@example.net"))
+            .unwrap();
+        assert!(set_self_key(
+            &t.ctx,
+            S_PROTECTED_KEY,
+            true,
+            false,
+            Some("correct horse battery staple")
+        )
+        .is_ok());
+        assert_eq!(key_warnings(), 0);
+
+        // a key for another address is accepted with a warning
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("alice@example.com"))
+            .unwrap();
+        assert!(set_self_key(
+            &t.ctx,
+            S_PROTECTED_KEY,
+            true,
+            false,
+            Some("correct horse battery staple")
+        )
+        .is_ok());
+        assert_eq!(key_warnings(), 1);
+    }
+
+    #[test]
+    fn test_set_self_key_addr_changed() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("alice@example.org"))
+            .unwrap();
+        let armored =
+            Key::from(alice_keypair().secret).to_asc(Some(("Autocrypt-Prefer-Encrypt", "mutual")));
+
+        // keys from key files and Autocrypt Setup Messages are both accepted after an address change
+        assert!(set_self_key(&t.ctx, &armored, true, false, None).is_ok());
+        assert!(set_self_key(&t.ctx, &armored, true, true, None).is_ok());
+    }
+
+    #[test]
+    fn test_imex_passphrase_not_stored() {
+        let t = dummy_context();
+        imex(
+            &t.ctx,
+            ImexMode::ImportSelfKeys,
+            Some(t.dir.path()),
+            Some(" secret\npassphrase "),
+        );
+        let job_param: String = t
+            .ctx
+            .sql
+            .query_get_value(
+                &t.ctx,
+                "SELECT param FROM jobs WHERE action=?;",
+                params![Action::ImexImap],
+            )
+            .unwrap();
+        assert!(!job_param.contains("secret"));
+        assert_eq!(
+            t.ctx.imex_passphrase.lock().unwrap().as_ref().unwrap(),
+            " secret\npassphrase "
+        );
+    }
+
+    #[test]
+    fn test_export_import_encrypted_backup() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let backup_dir = tempfile::tempdir().unwrap();
+
+        export_backup(&t.ctx, backup_dir.path(), Some("backup passphrase")).unwrap();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+        assert!(is_backup_encrypted(&backup).unwrap());
+        let content = std::fs::read(&backup).unwrap();
+        assert!(!content.windows(15).any(|w| w == b"SQLite format 3"));
+        // no unencrypted temporary file is left in the export directory
+        assert_eq!(std::fs::read_dir(backup_dir.path()).unwrap().count(), 1);
+
+        let t2 = dummy_context();
+        assert!(import_backup(&t2.ctx, &backup, None).is_err());
+        assert!(import_backup(&t2.ctx, &backup, Some("wrong")).is_err());
+        assert!(t2.ctx.get_config(Config::ConfiguredAddr).is_none());
+
+        import_backup(&t2.ctx, &backup, Some("backup passphrase")).unwrap();
+        assert_eq!(
+            t2.ctx.get_config(Config::ConfiguredAddr).unwrap(),
+            "alice@example.com"
+        );
+    }
+
+    #[test]
+    fn test_export_unencrypted_backup() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let backup_dir = tempfile::tempdir().unwrap();
+
+        export_backup(&t.ctx, backup_dir.path(), None).unwrap();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+        assert!(!is_backup_encrypted(&backup).unwrap());
+    }
+
+    #[test]
+    fn test_export_import_tar_backup() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        dc_write_file(&t.ctx, t.ctx.get_blobdir().join("image.png"), b"blob").unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+
+        export_backup(&t.ctx, backup_dir.path(), None).unwrap();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+        assert!(backup.ends_with(".tar"));
+        assert!(is_tar_backup(Path::new(&backup)).unwrap());
+        // only the final backup is left in the directory
+        assert_eq!(std::fs::read_dir(backup_dir.path()).unwrap().count(), 1);
+
+        let t2 = dummy_context();
+        import_backup(&t2.ctx, &backup, None).unwrap();
+        assert_eq!(
+            t2.ctx.get_config(Config::ConfiguredAddr).unwrap(),
+            "alice@example.com"
+        );
+        assert_eq!(
+            std::fs::read(t2.ctx.get_blobdir().join("image.png")).unwrap(),
+            b"blob"
+        );
+    }
+
+    #[test]
+    fn test_import_tar_backup_skips_links() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let backup_dir = tempfile::tempdir().unwrap();
+        export_backup(&t.ctx, backup_dir.path(), None).unwrap();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+
+        // copy the backup and add a symlink pointing outside of the blobdir
+        let crafted = backup_dir.path().join("crafted.tar");
+        let mut builder = tar::Builder::new(std::fs::File::create(&crafted).unwrap());
+        let mut archive = tar::Archive::new(std::fs::File::open(&backup).unwrap());
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().into_owned();
+            let mut header = entry.header().clone();
+            builder.append_data(&mut header, path, &mut entry).unwrap();
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_link_name("/etc/passwd").unwrap();
+        header.set_size(0);
+        builder
+            .append_data(
+                &mut header,
+                Path::new(BACKUP_BLOBS_DIR).join("passwd"),
+                std::io::empty(),
+            )
+            .unwrap();
+        builder.into_inner().unwrap();
+
+        let t2 = dummy_context();
+        import_backup(&t2.ctx, &crafted, None).unwrap();
+        assert_eq!(
+            t2.ctx.get_config(Config::ConfiguredAddr).unwrap(),
+            "alice@example.com"
+        );
+        let link = t2.ctx.get_blobdir().join("passwd");
+        assert!(std::fs::symlink_metadata(&link).is_err());
+    }
+
+    #[test]
+    fn test_import_legacy_backup() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup = backup_dir.path().join("delta-chat-2020-01-01-0.bak");
+        t.ctx.sql.close(&t.ctx);
+        std::fs::copy(t.ctx.get_dbfile(), &backup).unwrap();
+
+        let legacy_sql = Sql::new();
+        assert!(legacy_sql.open(&t.ctx, &backup, false));
+        legacy_sql
+            .execute(
+                "CREATE TABLE backup_blobs (id INTEGER PRIMARY KEY, file_name, file_content);",
+                params![],
+            )
+            .unwrap();
+        legacy_sql
+            .execute(
+                "INSERT INTO backup_blobs (file_name, file_content) VALUES (?, ?);",
+                params!["image.png", b"blob".to_vec()],
+            )
+            .unwrap();
+        legacy_sql.close(&t.ctx);
+        assert!(!is_tar_backup(&backup).unwrap());
+
+        let t2 = dummy_context();
+        import_backup(&t2.ctx, &backup, None).unwrap();
+        assert_eq!(
+            t2.ctx.get_config(Config::ConfiguredAddr).unwrap(),
+            "alice@example.com"
+        );
+        assert_eq!(
+            std::fs::read(t2.ctx.get_blobdir().join("image.png")).unwrap(),
+            b"blob"
+        );
+        assert!(!t2.ctx.sql.table_exists("backup_blobs"));
+    }
+
+    #[test]
+    fn test_verify_backup() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let chat_id = chat::create_by_contact_id(&t.ctx, DC_CONTACT_ID_SELF).unwrap();
+        let file = t.ctx.get_blobdir().join("report.txt");
+        dc_write_file(&t.ctx, &file, b"blob").unwrap();
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        chat::prepare_msg(&t.ctx, chat_id, &mut msg).unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+
+        export_backup(&t.ctx, backup_dir.path(), Some("backup passphrase")).unwrap();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+        assert!(verify_backup(&t.ctx, &backup, None).is_err());
+        assert!(verify_backup(&t.ctx, &backup, Some("wrong")).is_err());
+
+        let report = verify_backup(&t.ctx, &backup, Some("backup passphrase")).unwrap();
+        assert!(report.is_ok(), "{:?}", report);
+        assert_eq!(report.addr, "alice@example.com");
+        assert_eq!(report.integrity_check, "ok");
+        assert!(report.chat_count >= 1);
+        assert!(report.msg_count >= 1);
+        assert!(report.backup_time > 0);
+        assert_eq!(report.size, std::fs::metadata(&backup).unwrap().len());
+        assert!(report.missing_blobs.is_empty());
+        assert!(report.keypair_ok);
+
+        // a blob missing in the backup is reported
+        std::fs::remove_file(&backup).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        export_backup(&t.ctx, backup_dir.path(), None).unwrap();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+        let report = verify_backup(&t.ctx, &backup, None).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.missing_blobs, vec!["report.txt".to_string()]);
+    }
 }
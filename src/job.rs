@@ -12,17 +12,19 @@ use rand::{thread_rng, Rng};
 use async_std::task;
 
 use crate::blob::BlobObject;
-use crate::chat::{self, ChatId};
+use crate::chat::{self, Chat, ChatId};
 use crate::config::Config;
 use crate::configure::*;
 use crate::constants::*;
 use crate::contact::Contact;
 use crate::context::{Context, PerformJobsNeeded};
 use crate::dc_tools::*;
+use crate::e2ee;
 use crate::error::{Error, Result};
 use crate::events::Event;
 use crate::imap::*;
 use crate::imex::*;
+use crate::job_thread::get_extra_watch_folders;
 use crate::location;
 use crate::login_param::LoginParam;
 use crate::message::MsgId;
@@ -30,6 +32,7 @@ use crate::message::{self, Message, MessageState};
 use crate::mimefactory::{MimeFactory, RenderedEmail};
 use crate::param::*;
 use crate::sql;
+use crate::stock::StockMessage;
 
 // results in ~3 weeks for the last backoff timespan
 const JOB_RETRIES: u32 = 17;
@@ -79,6 +82,7 @@ pub enum Action {
 
     // Jobs in the INBOX-thread, range from DC_IMAP_THREAD..DC_IMAP_THREAD+999
     Housekeeping = 105, // low priority ...
+    DeleteExpiredOnServer = 106,
     EmptyServer = 107,
     DeleteMsgOnImap = 110,
     MarkseenMdnOnImap = 120,
@@ -108,6 +112,7 @@ impl From<Action> for Thread {
             Unknown => Thread::Unknown,
 
             Housekeeping => Thread::Imap,
+            DeleteExpiredOnServer => Thread::Imap,
             DeleteMsgOnImap => Thread::Imap,
             EmptyServer => Thread::Imap,
             MarkseenMdnOnImap => Thread::Imap,
@@ -350,7 +355,24 @@ impl Job {
         }
 
         let msg = job_try!(Message::load_from_db(context, msg_id));
-        let mimefactory = job_try!(MimeFactory::from_mdn(context, &msg, additional_rfc724_mids));
+
+        // the MDN goes to the sender of the message only,
+        // so the keys of other chat members are not needed
+        let chat = job_try!(Chat::load_from_db(context, msg.chat_id));
+        let e2ee_guaranteed = chat.is_encryption_required() || contact.is_encryption_required();
+        if e2ee_guaranteed && !e2ee::has_peer_key(context, contact.get_addr()) {
+            return Status::Finished(Err(format_err!(
+                "encryption required, but no key for {}",
+                contact.get_addr()
+            )));
+        }
+
+        let mimefactory = job_try!(MimeFactory::from_mdn(
+            context,
+            &msg,
+            additional_rfc724_mids,
+            e2ee_guaranteed
+        ));
         let rendered_msg = job_try!(mimefactory.render());
         let body = rendered_msg.message;
 
@@ -421,6 +443,23 @@ impl Job {
 
         let mut msg = job_try!(Message::load_from_db(context, MsgId::new(self.foreign_id)));
 
+        if self.param.get_bool(Param::KeepLocal).unwrap_or_default() {
+            // only the copy on the server has expired, the local message is kept.
+            // the server message may be shared by several parts, which were
+            // all updated below when the first part was handled.
+            if msg.rfc724_mid.is_empty() || msg.server_uid == 0 {
+                return Status::Finished(Ok(()));
+            }
+            let server_folder = msg.server_folder.as_ref().unwrap();
+            let res =
+                imap_inbox.delete_msg(context, &msg.rfc724_mid, server_folder, &mut msg.server_uid);
+            if res == ImapActionResult::RetryLater {
+                return Status::RetryLater;
+            }
+            message::update_server_uid(context, &msg.rfc724_mid, "", 0);
+            return Status::Finished(Ok(()));
+        }
+
         if !msg.rfc724_mid.is_empty() {
             if message::rfc724_mid_cnt(context, &msg.rfc724_mid) > 1 {
                 info!(
@@ -589,6 +628,18 @@ pub fn perform_sentbox_fetch(context: &Context) {
     );
 }
 
+pub fn perform_extra_fetch(context: &Context) {
+    let use_network = has_extra_watch_folders(context);
+
+    task::block_on(
+        context
+            .extra_thread
+            .write()
+            .unwrap()
+            .fetch(context, use_network),
+    );
+}
+
 pub fn perform_inbox_idle(context: &Context) {
     if *context.perform_inbox_jobs_needed.clone().read().unwrap() {
         info!(
@@ -626,6 +677,24 @@ pub fn perform_sentbox_idle(context: &Context) {
         .idle(context, use_network);
 }
 
+/// Waits until the additional folders are due to be polled again.
+///
+/// IMAP NOTIFY is not supported, so the folders listed in
+/// `Config::ExtraWatchFolders` are not idled on but fetched periodically.
+pub fn perform_extra_idle(context: &Context) {
+    let use_network = has_extra_watch_folders(context);
+
+    context
+        .extra_thread
+        .read()
+        .unwrap()
+        .idle(context, use_network);
+}
+
+fn has_extra_watch_folders(context: &Context) -> bool {
+    !get_extra_watch_folders(context).is_empty()
+}
+
 pub fn interrupt_inbox_idle(context: &Context) {
     info!(context, "interrupt_inbox_idle called");
     // we do not block on trying to obtain the thread lock
@@ -655,6 +724,10 @@ pub fn interrupt_sentbox_idle(context: &Context) {
         .interrupt_idle(context);
 }
 
+pub fn interrupt_extra_idle(context: &Context) {
+    context.extra_thread.read().unwrap().interrupt_idle(context);
+}
+
 pub fn perform_smtp_jobs(context: &Context) {
     let probe_smtp_network = {
         let &(ref lock, _) = &*context.smtp_state.clone();
@@ -781,7 +854,30 @@ pub fn job_send_msg(context: &Context, msg_id: MsgId) -> Result<()> {
     msg.try_calc_and_set_dimensions(context).ok();
 
     /* create message */
-    let needs_encryption = msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default();
+    let mut needs_encryption = msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default();
+
+    // handshake messages are sent unencrypted by design
+    if !needs_encryption && !msg.param.exists(Param::ForcePlaintext) {
+        if let Some(missing) = e2ee::get_missing_keys_if_encryption_required(context, msg.chat_id)?
+        {
+            if !missing.is_empty() {
+                let reason = context.stock_string_repl_str(
+                    StockMessage::EncryptionRequiredNoKey,
+                    missing.join(", "),
+                );
+                message::set_msg_failed(context, msg_id, Some(&reason));
+                chat::add_info_msg(context, msg.chat_id, &reason);
+                context.call_cb(Event::EncryptionRequiredFailed {
+                    chat_id: msg.chat_id,
+                    msg_id,
+                });
+                bail!("encryption required, but no key for {}", missing.join(", "));
+            }
+            needs_encryption = true;
+            msg.param.set_int(Param::GuaranteeE2ee, 1);
+            msg.save_param_to_disk(context);
+        }
+    }
 
     let attach_selfavatar = match chat::shall_attach_selfavatar(context, msg.chat_id) {
         Ok(attach_selfavatar) => attach_selfavatar,
@@ -875,6 +971,36 @@ pub fn job_send_msg(context: &Context, msg_id: MsgId) -> Result<()> {
     Ok(())
 }
 
+/// Schedules the deletion of expired messages from the server
+/// and checks again later as long as `Config::DeleteServerAfter` is set.
+fn delete_expired_on_server(context: &Context) {
+    let delete_server_after = context.get_config_int(Config::DeleteServerAfter);
+    if delete_server_after <= 0 {
+        return;
+    }
+
+    let cnt = message::schedule_server_deletion(context);
+    info!(
+        context,
+        "{} expired messages scheduled for deletion on the server", cnt
+    );
+
+    // check at least hourly, more often for short retention periods
+    // or if there are more messages to delete than scheduled at once
+    let delay = if cnt >= message::SERVER_DELETION_BATCH_SIZE {
+        60
+    } else {
+        i64::from(delete_server_after).min(60 * 60).max(60)
+    };
+    job_add(
+        context,
+        Action::DeleteExpiredOnServer,
+        0,
+        Params::new(),
+        delay,
+    );
+}
+
 pub fn perform_inbox_jobs(context: &Context) {
     info!(context, "dc_perform_inbox_jobs starting.",);
 
@@ -916,6 +1042,12 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                 .read()
                 .unwrap()
                 .suspend(context);
+            context
+                .extra_thread
+                .clone()
+                .read()
+                .unwrap()
+                .suspend(context);
             suspend_smtp_thread(context, true);
         }
 
@@ -951,6 +1083,10 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                         sql::housekeeping(context);
                         Status::Finished(Ok(()))
                     }
+                    Action::DeleteExpiredOnServer => {
+                        delete_expired_on_server(context);
+                        Status::Finished(Ok(()))
+                    }
                 };
 
                 info!(
@@ -979,6 +1115,12 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                 .read()
                 .unwrap()
                 .unsuspend(context);
+            context
+                .extra_thread
+                .clone()
+                .read()
+                .unwrap()
+                .unsuspend(context);
             suspend_smtp_thread(context, false);
             break;
         }
@@ -1222,6 +1364,9 @@ fn load_next_job(context: &Context, thread: Thread, probe_network: bool) -> Opti
 mod tests {
     use super::*;
 
+    use std::sync::{Arc, Mutex};
+
+    use crate::dc_receive_imf::dc_receive_imf;
     use crate::test_utils::*;
 
     fn insert_job(context: &Context, foreign_id: i64) {
@@ -1258,4 +1403,77 @@ mod tests {
         let jobs = load_next_job(&t.ctx, Thread::from(Action::MoveMsg), false);
         assert!(jobs.is_some());
     }
+
+    #[test]
+    fn test_send_msg_encryption_required_no_key() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events2 = Arc::clone(&events);
+        let t = test_context(Some(Box::new(move |_, event| {
+            events2.lock().unwrap().push(event)
+        })));
+        configure_alice_keypair(&t.ctx);
+        let bob_id = Contact::create(&t.ctx, "Bob", "bob@example.net").unwrap();
+        let chat_id = chat::create_by_contact_id(&t.ctx, bob_id).unwrap();
+        Contact::set_encryption_required(&t.ctx, bob_id, true).unwrap();
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("secret".to_string()));
+        assert!(chat::send_msg(&t.ctx, chat_id, &mut msg).is_err());
+
+        let reason = t
+            .ctx
+            .stock_string_repl_str(StockMessage::EncryptionRequiredNoKey, "bob@example.net");
+        let msg = Message::load_from_db(&t.ctx, msg.id).unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutFailed);
+        assert_eq!(msg.param.get(Param::Error), Some(reason.as_str()));
+
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None);
+        let info = Message::load_from_db(&t.ctx, *msgs.last().unwrap()).unwrap();
+        assert!(info.is_info());
+        assert_eq!(info.get_text(), Some(reason));
+
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&Event::EncryptionRequiredFailed {
+                chat_id,
+                msg_id: msg.id
+            }));
+    }
+
+    #[test]
+    fn test_send_mdn_encryption_required_no_key() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let bob_id = Contact::create(&t.ctx, "Bob", "bob@example.net").unwrap();
+        let chat_id = chat::create_by_contact_id(&t.ctx, bob_id).unwrap();
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.com\n\
+              Subject: hello\n\
+              Message-ID: <1234@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Disposition-Notification-To: bob@example.net\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              hello\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .unwrap();
+        let msgs = chat::get_chat_msgs(&t.ctx, chat_id, 0, None);
+        let msg = Message::load_from_db(&t.ctx, *msgs.last().unwrap()).unwrap();
+        assert_eq!(msg.from_id, bob_id);
+
+        Contact::set_encryption_required(&t.ctx, bob_id, true).unwrap();
+        send_mdn(&t.ctx, &msg).unwrap();
+        let mut job = load_next_job(&t.ctx, Thread::Smtp, false).unwrap();
+        assert_eq!(job.action, Action::SendMdn);
+        match job.SendMdn(&t.ctx) {
+            Status::Finished(Err(err)) => assert!(err.to_string().contains("bob@example.net")),
+            status => panic!("unexpected status {:?}", status),
+        }
+    }
 }
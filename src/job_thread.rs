@@ -1,5 +1,6 @@
 use std::sync::{Arc, Condvar, Mutex};
 
+use crate::config::Config;
 use crate::context::Context;
 use crate::error::{Error, Result};
 use crate::imap::Imap;
@@ -101,26 +102,51 @@ impl JobThread {
         let prefix = format!("{}-fetch", self.name);
         match self.imap.connect_configured(context) {
             Ok(()) => {
-                if let Some(watch_folder) = self.get_watch_folder(context) {
-                    let start = std::time::Instant::now();
-                    info!(context, "{} started...", prefix);
-                    let res = self
-                        .imap
-                        .fetch(context, &watch_folder)
-                        .await
-                        .map_err(Into::into);
-                    let elapsed = start.elapsed().as_millis();
-                    info!(context, "{} done in {:.3} ms.", prefix, elapsed);
-
-                    res
-                } else {
-                    Err(Error::WatchFolderNotFound("not-set".to_string()))
+                let watch_folders = self.get_watch_folders(context);
+                if watch_folders.is_empty() && !self.is_extra_thread() {
+                    return Err(Error::WatchFolderNotFound("not-set".to_string()));
+                }
+                let start = std::time::Instant::now();
+                info!(context, "{} started...", prefix);
+                // A single broken folder, eg. one that was deleted on the server,
+                // should not make us reconnect, so only fail if nothing could be fetched.
+                let mut res = Ok(());
+                let mut fetched_any = false;
+                for watch_folder in &watch_folders {
+                    match self.imap.fetch(context, watch_folder).await {
+                        Ok(()) => fetched_any = true,
+                        Err(err) => {
+                            warn!(context, "{} of {} failed: {}", prefix, watch_folder, err);
+                            res = Err(err.into());
+                        }
+                    }
                 }
+                if fetched_any {
+                    res = Ok(());
+                }
+                let elapsed = start.elapsed().as_millis();
+                info!(context, "{} done in {:.3} ms.", prefix, elapsed);
+
+                res
             }
             Err(err) => Err(crate::error::Error::Message(err.to_string())),
         }
     }
 
+    /// Returns true if this thread watches the user-defined list of
+    /// additional folders instead of a single configured folder.
+    fn is_extra_thread(&self) -> bool {
+        self.folder_config_name == Config::ExtraWatchFolders.as_ref()
+    }
+
+    fn get_watch_folders(&self, context: &Context) -> Vec<String> {
+        if self.is_extra_thread() {
+            get_extra_watch_folders(context)
+        } else {
+            self.get_watch_folder(context).into_iter().collect()
+        }
+    }
+
     fn get_watch_folder(&self, context: &Context) -> Option<String> {
         match context.sql.get_raw_config(context, self.folder_config_name) {
             Some(name) => Some(name),
@@ -176,6 +202,10 @@ impl JobThread {
             Ok(()) => {
                 if !self.imap.can_idle() {
                     true // we have to do fake_idle
+                } else if self.is_extra_thread() {
+                    // IMAP NOTIFY is not available, so the additional
+                    // folders are polled on a schedule instead.
+                    true
                 } else {
                     let watch_folder = self.get_watch_folder(context);
                     info!(context, "{} started...", prefix);
@@ -198,10 +228,113 @@ impl JobThread {
             }
         };
         if do_fake_idle {
-            let watch_folder = self.get_watch_folder(context);
-            self.imap.fake_idle(context, watch_folder);
+            let watch_folders = self.get_watch_folders(context);
+            self.imap.fake_idle(context, watch_folders);
         }
 
         self.state.0.lock().unwrap().using_handle = false;
     }
 }
+
+/// Splits a list of folder names, one per line.
+///
+/// Commas are not used as separators as they are valid in IMAP mailbox names.
+pub(crate) fn parse_folder_list(folders: &str) -> Vec<String> {
+    folders
+        .lines()
+        .map(|folder| folder.trim())
+        .filter(|folder| !folder.is_empty())
+        .map(|folder| folder.to_string())
+        .collect()
+}
+
+/// Returns the folders set by `Config::ExtraWatchFolders`
+/// that are not already watched by the inbox, DeltaChat or sentbox thread.
+///
+/// Fetching a folder from two threads would race on its last seen UID
+/// and could process messages twice.
+pub(crate) fn get_extra_watch_folders(context: &Context) -> Vec<String> {
+    let folders = match context.get_config(Config::ExtraWatchFolders) {
+        Some(folders) => parse_folder_list(&folders),
+        None => return Vec::new(),
+    };
+    let mut watched = vec![context
+        .sql
+        .get_raw_config(context, "configured_inbox_folder")
+        .unwrap_or_else(|| "INBOX".to_string())];
+    if context.get_config_bool(Config::MvboxWatch) {
+        watched.extend(
+            context
+                .sql
+                .get_raw_config(context, "configured_mvbox_folder"),
+        );
+    }
+    if context.get_config_bool(Config::SentboxWatch) {
+        watched.extend(
+            context
+                .sql
+                .get_raw_config(context, "configured_sentbox_folder"),
+        );
+    }
+    folders
+        .into_iter()
+        .filter(|folder| {
+            // the name INBOX is case-insensitive, RFC 3501 5.1
+            !watched.iter().any(|watched| {
+                watched == folder
+                    || (watched.eq_ignore_ascii_case("INBOX")
+                        && folder.eq_ignore_ascii_case("INBOX"))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_parse_folder_list() {
+        assert!(parse_folder_list("").is_empty());
+        assert!(parse_folder_list(" \n\n ").is_empty());
+        assert_eq!(parse_folder_list("Lists"), vec!["Lists"]);
+        assert_eq!(
+            parse_folder_list("Lists\r\n Work/Projects\nINBOX.Family, Friends\n"),
+            vec!["Lists", "Work/Projects", "INBOX.Family, Friends"]
+        );
+    }
+
+    #[test]
+    fn test_get_extra_watch_folders() {
+        let t = dummy_context();
+        assert!(get_extra_watch_folders(&t.ctx).is_empty());
+
+        t.ctx
+            .sql
+            .set_raw_config(&t.ctx, "configured_mvbox_folder", Some("DeltaChat"))
+            .unwrap();
+        t.ctx
+            .sql
+            .set_raw_config(&t.ctx, "configured_sentbox_folder", Some("Sent"))
+            .unwrap();
+        t.ctx
+            .set_config(
+                Config::ExtraWatchFolders,
+                Some("Lists\ninbox\nDeltaChat\nSent\nINBOX.Family"),
+            )
+            .unwrap();
+        assert_eq!(
+            get_extra_watch_folders(&t.ctx),
+            vec!["Lists", "INBOX.Family"]
+        );
+
+        // the sentbox is only skipped while the sentbox thread watches it
+        t.ctx.set_config(Config::SentboxWatch, Some("0")).unwrap();
+        assert_eq!(
+            get_extra_watch_folders(&t.ctx),
+            vec!["Lists", "Sent", "INBOX.Family"]
+        );
+    }
+}
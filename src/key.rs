@@ -7,6 +7,7 @@ use std::path::Path;
 use pgp::composed::Deserializable;
 use pgp::ser::Serialize;
 use pgp::types::{KeyTrait, SecretKeyTrait};
+use sha2::{Digest, Sha256};
 
 use crate::constants::*;
 use crate::context::Context;
@@ -422,6 +423,41 @@ pub fn dc_normalize_fingerprint(fp: &str) -> String {
         .collect()
 }
 
+/// Compute the safety number of two keys given by their fingerprints.
+///
+/// Each fingerprint is hashed to 30 digits, the two halves are sorted,
+/// so both parties get the same number regardless of the order of the fingerprints.
+/// The result is formatted as 12 blocks of 5 digits, 4 blocks per line.
+pub fn dc_safety_number(fingerprint1: &str, fingerprint2: &str) -> String {
+    let mut halves = [
+        safety_number_half(fingerprint1),
+        safety_number_half(fingerprint2),
+    ];
+    halves.sort();
+
+    let mut res = String::new();
+    for (i, block) in halves.iter().flatten().enumerate() {
+        if i > 0 && i % 4 == 0 {
+            res += "\n";
+        } else if i > 0 {
+            res += " ";
+        }
+        res += block;
+    }
+    res
+}
+
+fn safety_number_half(fingerprint: &str) -> Vec<String> {
+    let digest = Sha256::digest(dc_normalize_fingerprint(fingerprint).as_bytes());
+    digest[..30]
+        .chunks(5)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+            format!("{:05}", value % 100_000)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -518,6 +554,26 @@ i8pcjGO+IZffvyZJVRWfVooBJmWWbPB1pueo3tx8w3+fcuzpxz+RLFKaPyqXO+dD
         );
     }
 
+    #[test]
+    fn test_safety_number() {
+        let alice = Key::from(alice_keypair().public).fingerprint();
+        let bob = Key::from(bob_keypair().public).fingerprint();
+
+        let number = dc_safety_number(&alice, &bob);
+        assert_eq!(number, dc_safety_number(&bob, &alice));
+        assert_eq!(
+            number,
+            dc_safety_number(&dc_format_fingerprint(&bob), &alice)
+        );
+        assert_eq!(number.lines().count(), 3);
+        assert_eq!(number.split_whitespace().count(), 12);
+        assert!(number
+            .split_whitespace()
+            .all(|block| block.len() == 5 && block.chars().all(|c| c.is_ascii_digit())));
+
+        assert_ne!(number, dc_safety_number(&alice, &alice));
+    }
+
     #[test]
     fn test_from_slice_roundtrip() {
         let public_key = Key::from(KEYPAIR.public.clone());
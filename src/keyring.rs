@@ -27,19 +27,38 @@ impl<'a> Keyring<'a> {
         &self.keys
     }
 
+    /// Adds all own secret keys, so that messages encrypted
+    /// to keys replaced by `e2ee::rotate_self_key()` can still be decrypted.
+    ///
+    /// Returns false if there is no secret key at all.
     pub fn load_self_private_for_decrypting(
         &mut self,
         context: &Context,
         self_addr: impl AsRef<str>,
         sql: &Sql,
     ) -> bool {
-        sql.query_get_value(
-            context,
-            "SELECT private_key FROM keypairs ORDER BY addr=? DESC, is_default DESC;",
-            &[self_addr.as_ref()],
-        )
-        .and_then(|blob: Vec<u8>| Key::from_slice(&blob, KeyType::Private))
-        .map(|key| self.add_owned(key))
-        .is_some()
+        let blobs = sql
+            .query_map(
+                "SELECT private_key FROM keypairs ORDER BY addr=? DESC, is_default DESC, id DESC;",
+                &[self_addr.as_ref()],
+                |row| row.get::<_, Vec<u8>>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .unwrap_or_else(|err| {
+                warn!(context, "Cannot load secret keys: {}", err);
+                Vec::new()
+            });
+
+        let mut loaded = false;
+        for blob in blobs {
+            if let Some(key) = Key::from_slice(&blob, KeyType::Private) {
+                self.add_owned(key);
+                loaded = true;
+            }
+        }
+        loaded
     }
 }
@@ -35,7 +35,8 @@ pub mod configure;
 pub mod constants;
 pub mod contact;
 pub mod context;
-mod e2ee;
+pub mod e2ee;
+pub mod filter;
 mod imap;
 mod imap_client;
 pub mod imex;
@@ -61,7 +62,7 @@ mod simplify;
 mod smtp;
 pub mod sql;
 pub mod stock;
-mod token;
+pub mod token;
 #[macro_use]
 mod dehtml;
 
@@ -1,12 +1,15 @@
 //! Location handling
 
 use bitflags::bitflags;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use quick_xml;
 use quick_xml::events::{BytesEnd, BytesStart, BytesText};
+use serde_json::json;
 
 use crate::chat::{self, ChatId};
 use crate::config::Config;
 use crate::constants::*;
+use crate::contact::Contact;
 use crate::context::*;
 use crate::dc_tools::*;
 use crate::error::Error;
@@ -31,6 +34,10 @@ pub struct Location {
     pub chat_id: ChatId,
     pub marker: Option<String>,
     pub independent: u32,
+    /// Name of a point of interest.
+    pub label: Option<String>,
+    /// Description of a point of interest, typically the address.
+    pub description: Option<String>,
 }
 
 impl Location {
@@ -56,6 +63,8 @@ bitflags! {
         const WHEN = 0x04;
         const POINT = 0x08;
         const COORDINATES = 0x10;
+        const NAME = 0x20;
+        const DESCRIPTION = 0x40;
     }
 }
 
@@ -98,7 +107,17 @@ impl Kml {
     }
 
     fn text_cb<B: std::io::BufRead>(&mut self, event: &BytesText, reader: &quick_xml::Reader<B>) {
-        if self.tag.contains(KmlTag::WHEN) || self.tag.contains(KmlTag::COORDINATES) {
+        if self.tag.contains(KmlTag::NAME) || self.tag.contains(KmlTag::DESCRIPTION) {
+            let val = event.unescape_and_decode(reader).unwrap_or_default();
+            let val = val.trim();
+            if !val.is_empty() {
+                if self.tag.contains(KmlTag::NAME) {
+                    self.curr.label = Some(val.to_string());
+                } else {
+                    self.curr.description = Some(val.to_string());
+                }
+            }
+        } else if self.tag.contains(KmlTag::WHEN) || self.tag.contains(KmlTag::COORDINATES) {
             let val = event.unescape_and_decode(reader).unwrap_or_default();
 
             let val = val
@@ -144,6 +163,8 @@ impl Kml {
                     .push(std::mem::replace(&mut self.curr, Location::new()));
             }
             self.tag = KmlTag::UNDEFINED;
+        } else if (tag == "name" || tag == "description") && self.tag.contains(KmlTag::PLACEMARK) {
+            self.tag = KmlTag::PLACEMARK;
         };
     }
 
@@ -166,7 +187,13 @@ impl Kml {
             self.curr.timestamp = 0;
             self.curr.latitude = 0.0;
             self.curr.longitude = 0.0;
-            self.curr.accuracy = 0.0
+            self.curr.accuracy = 0.0;
+            self.curr.label = None;
+            self.curr.description = None;
+        } else if tag == "name" && self.tag == KmlTag::PLACEMARK {
+            self.tag = KmlTag::PLACEMARK | KmlTag::NAME
+        } else if tag == "description" && self.tag == KmlTag::PLACEMARK {
+            self.tag = KmlTag::PLACEMARK | KmlTag::DESCRIPTION
         } else if tag == "timestamp" && self.tag.contains(KmlTag::PLACEMARK) {
             self.tag = KmlTag::PLACEMARK | KmlTag::TIMESTAMP
         } else if tag == "when" && self.tag.contains(KmlTag::TIMESTAMP) {
@@ -313,7 +340,8 @@ pub fn get_range(
         .sql
         .query_map(
             "SELECT l.id, l.latitude, l.longitude, l.accuracy, l.timestamp, l.independent, \
-             COALESCE(m.id, 0) AS msg_id, l.from_id, l.chat_id, COALESCE(m.txt, '') AS txt \
+             COALESCE(m.id, 0) AS msg_id, l.from_id, l.chat_id, COALESCE(m.txt, '') AS txt, \
+             l.label, l.description \
              FROM locations l  LEFT JOIN msgs m ON l.id=m.location_id  WHERE (? OR l.chat_id=?) \
              AND (? OR l.from_id=?) \
              AND (l.independent=1 OR (l.timestamp>=? AND l.timestamp<=?)) \
@@ -345,6 +373,8 @@ pub fn get_range(
                     contact_id: row.get(7)?,
                     chat_id: row.get(8)?,
                     marker,
+                    label: row.get::<_, Option<String>>(10)?.filter(|s| !s.is_empty()),
+                    description: row.get::<_, Option<String>>(11)?.filter(|s| !s.is_empty()),
                 };
                 Ok(loc)
             },
@@ -364,6 +394,205 @@ fn is_marker(txt: &str) -> bool {
     txt.len() == 1 && !txt.starts_with(' ')
 }
 
+/// Maximum time in seconds between two locations of the same contact
+/// before an exported track is split into a new segment.
+pub const EXPORT_SEGMENT_MAX_GAP: i64 = 30 * 60;
+
+/// Locations of one contact, split into segments of consecutive points.
+#[derive(Debug, Clone, Default)]
+struct Track<'a> {
+    contact_id: u32,
+    segments: Vec<Vec<&'a Location>>,
+}
+
+/// Splits the streamed locations into one track per contact.
+///
+/// Independent locations are skipped as they do not belong to a stream.
+/// A new segment is started whenever two consecutive locations of a contact
+/// are more than `max_gap` seconds apart.
+fn get_tracks(locations: &[Location], max_gap: i64) -> Vec<Track> {
+    let mut streamed = locations
+        .iter()
+        .filter(|l| l.independent == 0)
+        .collect::<Vec<_>>();
+    streamed.sort_by_key(|l| (l.contact_id, l.timestamp, l.location_id));
+
+    let mut tracks: Vec<Track> = Vec::new();
+    for location in streamed {
+        let new_track = match tracks.last() {
+            Some(track) => track.contact_id != location.contact_id,
+            None => true,
+        };
+        if new_track {
+            tracks.push(Track {
+                contact_id: location.contact_id,
+                segments: vec![Vec::new()],
+            });
+        }
+        let track = tracks.last_mut().unwrap();
+
+        let new_segment = match track.segments.last().and_then(|s| s.last()) {
+            Some(prev) => location.timestamp - prev.timestamp > max_gap,
+            None => false,
+        };
+        if new_segment {
+            track.segments.push(Vec::new());
+        }
+        track.segments.last_mut().unwrap().push(location);
+    }
+    tracks
+}
+
+fn get_track_name(context: &Context, contact_id: u32) -> String {
+    Contact::load_from_db(context, contact_id)
+        .map(|contact| contact.get_name_n_addr())
+        .unwrap_or_else(|_| format!("Contact#{}", contact_id))
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Returns the locations of a chat and contact in the given time range as GPX 1.1.
+///
+/// Streamed locations are exported as one track per contact,
+/// independent locations as waypoints.
+/// Parameters are the same as for [get_range].
+pub fn get_gpx(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: u32,
+    timestamp_from: i64,
+    timestamp_to: i64,
+) -> String {
+    let locations = get_range(context, chat_id, contact_id, timestamp_from, timestamp_to);
+
+    let mut ret = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                   <gpx version=\"1.1\" creator=\"Delta Chat\" \
+                   xmlns=\"http://www.topografix.com/GPX/1/1\" \
+                   xmlns:dc=\"https://delta.chat/gpx/1\">\n"
+        .to_string();
+
+    for location in locations.iter().rev().filter(|l| l.independent != 0) {
+        ret += &format!(
+            "<wpt lat=\"{}\" lon=\"{}\"><time>{}</time>{}<extensions><dc:accuracy>{}</dc:accuracy></extensions></wpt>\n",
+            location.latitude,
+            location.longitude,
+            get_kml_timestamp(location.timestamp),
+            location
+                .marker
+                .as_ref()
+                .map(|marker| format!("<name>{}</name>", xml_escape(marker)))
+                .unwrap_or_default(),
+            location.accuracy,
+        );
+    }
+
+    for track in get_tracks(&locations, EXPORT_SEGMENT_MAX_GAP) {
+        ret += &format!(
+            "<trk><name>{}</name>\n",
+            xml_escape(&get_track_name(context, track.contact_id))
+        );
+        for segment in track.segments {
+            ret += "<trkseg>\n";
+            for location in segment {
+                ret += &format!(
+                    "<trkpt lat=\"{}\" lon=\"{}\"><time>{}</time><extensions><dc:accuracy>{}</dc:accuracy></extensions></trkpt>\n",
+                    location.latitude,
+                    location.longitude,
+                    get_kml_timestamp(location.timestamp),
+                    location.accuracy,
+                );
+            }
+            ret += "</trkseg>\n";
+        }
+        ret += "</trk>\n";
+    }
+    ret += "</gpx>";
+    ret
+}
+
+/// Returns the locations of a chat and contact in the given time range
+/// as a GeoJSON FeatureCollection.
+///
+/// Streamed locations are exported as one `MultiLineString` feature per contact,
+/// one line per segment, with timestamps and accuracies in the feature properties.
+/// Independent locations are exported as `Point` features.
+/// Parameters are the same as for [get_range].
+pub fn get_geojson(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: u32,
+    timestamp_from: i64,
+    timestamp_to: i64,
+) -> String {
+    let locations = get_range(context, chat_id, contact_id, timestamp_from, timestamp_to);
+    let mut features = Vec::new();
+
+    for location in locations.iter().rev().filter(|l| l.independent != 0) {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [location.longitude, location.latitude],
+            },
+            "properties": {
+                "contact_id": location.contact_id,
+                "name": location.marker,
+                "timestamp": location.timestamp,
+                "accuracy": location.accuracy,
+            },
+        }));
+    }
+
+    for track in get_tracks(&locations, EXPORT_SEGMENT_MAX_GAP) {
+        let coordinates = track
+            .segments
+            .iter()
+            .map(|segment| {
+                segment
+                    .iter()
+                    .map(|l| vec![l.longitude, l.latitude])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let timestamps = track
+            .segments
+            .iter()
+            .map(|segment| segment.iter().map(|l| l.timestamp).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let accuracies = track
+            .segments
+            .iter()
+            .map(|segment| segment.iter().map(|l| l.accuracy).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "MultiLineString",
+                "coordinates": coordinates,
+            },
+            "properties": {
+                "contact_id": track.contact_id,
+                "name": get_track_name(context, track.contact_id),
+                "timestamps": timestamps,
+                "accuracies": accuracies,
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+    .to_string()
+}
+
 pub fn delete_all(context: &Context) -> Result<(), Error> {
     sql::execute(context, &context.sql, "DELETE FROM locations;", params![])?;
     context.call_cb(Event::LocationChanged(None));
@@ -396,7 +625,7 @@ pub fn get_kml(context: &Context, chat_id: ChatId) -> Result<(String, u32), Erro
             self_addr,
         );
 
-        context.sql.query_map(
+        let locations = context.sql.query_map(
             "SELECT id, latitude, longitude, accuracy, timestamp \
              FROM locations  WHERE from_id=? \
              AND timestamp>=? \
@@ -406,30 +635,40 @@ pub fn get_kml(context: &Context, chat_id: ChatId) -> Result<(String, u32), Erro
              ORDER BY timestamp;",
             params![DC_CONTACT_ID_SELF, locations_send_begin, locations_last_sent, DC_CONTACT_ID_SELF],
             |row| {
-                let location_id: i32 = row.get(0)?;
-                let latitude: f64 = row.get(1)?;
-                let longitude: f64 = row.get(2)?;
-                let accuracy: f64 = row.get(3)?;
-                let timestamp = get_kml_timestamp(row.get(4)?);
-
-                Ok((location_id, latitude, longitude, accuracy, timestamp))
+                let loc = Location {
+                    location_id: row.get(0)?,
+                    latitude: row.get(1)?,
+                    longitude: row.get(2)?,
+                    accuracy: row.get(3)?,
+                    timestamp: row.get(4)?,
+                    contact_id: DC_CONTACT_ID_SELF,
+                    chat_id,
+                    ..Default::default()
+                };
+                Ok(loc)
             },
-            |rows| {
-                for row in rows {
-                    let (location_id, latitude, longitude, accuracy, timestamp) = row?;
-                    ret += &format!(
-                        "<Placemark><Timestamp><when>{}</when></Timestamp><Point><coordinates accuracy=\"{}\">{},{}</coordinates></Point></Placemark>\n",
-                        timestamp,
-                        accuracy,
-                        longitude,
-                        latitude
-                    );
-                    location_count += 1;
-                    last_added_location_id = location_id as u32;
-                }
-                Ok(())
-            }
+            |locations| locations.collect::<Result<Vec<_>, _>>().map_err(Into::into),
         )?;
+
+        if let Some(last) = locations.last() {
+            last_added_location_id = last.location_id;
+        }
+        let locations = simplify_track(
+            &locations,
+            f64::from(context.get_config_int(Config::LocationSimplifyTolerance)),
+            f64::from(context.get_config_int(Config::LocationStationaryRadius)),
+        );
+
+        for location in &locations {
+            ret += &format!(
+                "<Placemark><Timestamp><when>{}</when></Timestamp><Point><coordinates accuracy=\"{}\">{},{}</coordinates></Point></Placemark>\n",
+                get_kml_timestamp(location.timestamp),
+                location.accuracy,
+                location.longitude,
+                location.latitude
+            );
+            location_count += 1;
+        }
         ret += "</Document>\n</kml>";
     }
 
@@ -438,6 +677,129 @@ pub fn get_kml(context: &Context, chat_id: ChatId) -> Result<(String, u32), Erro
     Ok((ret, last_added_location_id))
 }
 
+/// Mean earth radius in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Projects `location` onto a plane touching the earth at `origin`.
+///
+/// Returns the x and y offsets from `origin` in meters.
+/// This is exact enough for the short distances between consecutive locations.
+fn project(origin: &Location, location: &Location) -> (f64, f64) {
+    let x = (location.longitude - origin.longitude).to_radians()
+        * origin.latitude.to_radians().cos()
+        * EARTH_RADIUS;
+    let y = (location.latitude - origin.latitude).to_radians() * EARTH_RADIUS;
+    (x, y)
+}
+
+/// Returns the distance between two locations in meters.
+fn distance(a: &Location, b: &Location) -> f64 {
+    let (x, y) = project(a, b);
+    x.hypot(y)
+}
+
+/// Returns the distance in meters between `location`
+/// and the line segment from `start` to `end`.
+fn segment_distance(location: &Location, start: &Location, end: &Location) -> f64 {
+    let (px, py) = project(start, location);
+    let (ex, ey) = project(start, end);
+    let len2 = ex * ex + ey * ey;
+    if len2 <= 0.0 {
+        return px.hypot(py);
+    }
+    let t = ((px * ex + py * ey) / len2).max(0.0).min(1.0);
+    (px - t * ex).hypot(py - t * ey)
+}
+
+/// Marks the locations to keep using the Douglas-Peucker algorithm.
+///
+/// The first and the last location are always kept.
+fn douglas_peucker(locations: &[&Location], tolerance: f64, keep: &mut [bool]) {
+    if locations.is_empty() {
+        return;
+    }
+    let last = locations.len() - 1;
+    keep[0] = true;
+    keep[last] = true;
+
+    let mut ranges = vec![(0, last)];
+    while let Some((first, last)) = ranges.pop() {
+        let mut max_distance = 0.0;
+        let mut max_index = first;
+        for (i, location) in locations.iter().enumerate().take(last).skip(first + 1) {
+            let d = segment_distance(location, locations[first], locations[last]);
+            if d > max_distance {
+                max_distance = d;
+                max_index = i;
+            }
+        }
+        if max_distance > tolerance {
+            keep[max_index] = true;
+            ranges.push((first, max_index));
+            ranges.push((max_index, last));
+        }
+    }
+}
+
+/// Reduces the number of locations of a track before it is sent.
+///
+/// First, runs of near-stationary locations, that is locations not farther than
+/// `stationary_radius` meters from the first location of the run,
+/// are collapsed to the first and the last location of the run,
+/// so the times of arrival and departure are kept.
+/// Then the track is simplified so that no dropped location is farther than
+/// `tolerance` meters away from the resulting track.
+///
+/// The first and the last location and all locations with a marker are always kept.
+/// A value of `0` disables the respective step.
+pub fn simplify_track(
+    locations: &[Location],
+    tolerance: f64,
+    stationary_radius: f64,
+) -> Vec<Location> {
+    let mut candidates = Vec::with_capacity(locations.len());
+    let mut i = 0;
+    while i < locations.len() {
+        let anchor = &locations[i];
+        candidates.push(anchor);
+        let mut j = i + 1;
+        while stationary_radius > 0.0
+            && j < locations.len()
+            && locations[j].marker.is_none()
+            && distance(anchor, &locations[j]) <= stationary_radius
+        {
+            j += 1;
+        }
+        if j - 1 > i {
+            candidates.push(&locations[j - 1]);
+        }
+        i = j;
+    }
+
+    if tolerance <= 0.0 {
+        return candidates.into_iter().cloned().collect();
+    }
+
+    // markers split the track into parts that are simplified independently
+    let mut keep = vec![false; candidates.len()];
+    if !candidates.is_empty() {
+        keep[0] = true;
+    }
+    let mut start = 0;
+    for (end, location) in candidates.iter().enumerate().skip(1) {
+        if end == candidates.len() - 1 || location.marker.is_some() {
+            douglas_peucker(&candidates[start..=end], tolerance, &mut keep[start..=end]);
+            start = end;
+        }
+    }
+
+    candidates
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(location, keep)| if keep { Some(location.clone()) } else { None })
+        .collect()
+}
+
 fn get_kml_timestamp(utc: i64) -> String {
     // Returns a string formatted as YYYY-MM-DDTHH:MM:SSZ. The trailing `Z` indicates UTC.
     chrono::NaiveDateTime::from_timestamp(utc, 0)
@@ -445,23 +807,71 @@ fn get_kml_timestamp(utc: i64) -> String {
         .to_string()
 }
 
-pub fn get_message_kml(timestamp: i64, latitude: f64, longitude: f64) -> String {
+/// Returns the KML attached to a message with a location.
+///
+/// Points of interest, which have a label, keep the exact position;
+/// other positions are rounded to two decimals.
+pub fn get_message_kml(
+    timestamp: i64,
+    latitude: f64,
+    longitude: f64,
+    label: Option<&str>,
+    description: Option<&str>,
+) -> String {
+    let coordinates = if label.is_some() {
+        format!("{},{}", longitude, latitude)
+    } else {
+        format!("{:.2},{:.2}", longitude, latitude)
+    };
     format!(
         "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
          <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n\
          <Document>\n\
          <Placemark>\
+         {}{}\
          <Timestamp><when>{}</when></Timestamp>\
-         <Point><coordinates>{:.2},{:.2}</coordinates></Point>\
+         <Point><coordinates>{}</coordinates></Point>\
          </Placemark>\n\
          </Document>\n\
          </kml>",
+        label
+            .map(|label| format!("<name>{}</name>", xml_escape(label)))
+            .unwrap_or_default(),
+        description
+            .map(|description| format!("<description>{}</description>", xml_escape(description)))
+            .unwrap_or_default(),
         get_kml_timestamp(timestamp),
-        longitude,
-        latitude,
+        coordinates,
     )
 }
 
+/// Returns a `geo:` URI (RFC 5870) for the given position.
+///
+/// If a label is given, it is added as a query as understood by most map apps.
+/// The URI is added to the text of point-of-interest messages
+/// so that classic MUAs can link to the location.
+pub fn get_geo_uri(latitude: f64, longitude: f64, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!(
+            "geo:{lat},{lng}?q={lat},{lng}({})",
+            utf8_percent_encode(label, NON_ALPHANUMERIC),
+            lat = latitude,
+            lng = longitude
+        ),
+        None => format!("geo:{},{}", latitude, longitude),
+    }
+}
+
+/// Removes a `geo:` URI added by [get_geo_uri] from the end of a message text.
+pub fn remove_geo_uri(text: &str) -> &str {
+    let text = text.trim_end();
+    match text.rfind('\n') {
+        Some(pos) if text[pos + 1..].starts_with("geo:") => text[..pos].trim_end(),
+        None if text.starts_with("geo:") => "",
+        _ => text,
+    }
+}
+
 pub fn set_kml_sent_timestamp(
     context: &Context,
     chat_id: ChatId,
@@ -505,8 +915,8 @@ pub fn save(
         .prepare2(
             "SELECT id FROM locations WHERE timestamp=? AND from_id=?",
             "INSERT INTO locations\
-             (timestamp, from_id, chat_id, latitude, longitude, accuracy, independent) \
-             VALUES (?,?,?,?,?,?,?);",
+             (timestamp, from_id, chat_id, latitude, longitude, accuracy, independent, label, description) \
+             VALUES (?,?,?,?,?,?,?,?,?);",
             |mut stmt_test, mut stmt_insert, conn| {
                 let mut newest_timestamp = 0;
                 let mut newest_location_id = 0;
@@ -524,6 +934,8 @@ pub fn save(
                             location.longitude,
                             location.accuracy,
                             independent,
+                            location.label.as_ref().map_or("", String::as_str),
+                            location.description.as_ref().map_or("", String::as_str),
                         ])?;
 
                         if location.timestamp > newest_timestamp {
@@ -706,4 +1118,236 @@ mod tests {
         assert!(locations_ref[1].accuracy < 2.6f64);
         assert_eq!(locations_ref[1].timestamp, 1544739072);
     }
+
+    #[test]
+    fn test_get_tracks() {
+        let loc = |location_id, contact_id, timestamp, independent| Location {
+            location_id,
+            contact_id,
+            timestamp,
+            independent,
+            latitude: 1.0,
+            longitude: 2.0,
+            ..Default::default()
+        };
+        let locations = vec![
+            loc(6, 2, 5000, 0),
+            loc(5, 1, 1000 + EXPORT_SEGMENT_MAX_GAP + 1, 0),
+            loc(4, 1, 1000, 0),
+            loc(3, 1, 900, 1),
+            loc(2, 1, 500, 0),
+            loc(1, 2, 100, 0),
+        ];
+
+        let tracks = get_tracks(&locations, EXPORT_SEGMENT_MAX_GAP);
+        assert_eq!(tracks.len(), 2);
+
+        assert_eq!(tracks[0].contact_id, 1);
+        assert_eq!(tracks[0].segments.len(), 2);
+        let ids = tracks[0].segments[0]
+            .iter()
+            .map(|l| l.location_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 4]);
+        assert_eq!(tracks[0].segments[1].len(), 1);
+        assert_eq!(tracks[0].segments[1][0].location_id, 5);
+
+        assert_eq!(tracks[1].contact_id, 2);
+        assert_eq!(tracks[1].segments.len(), 1);
+        assert_eq!(tracks[1].segments[0].len(), 2);
+    }
+
+    #[test]
+    fn test_export() {
+        let t = dummy_context();
+        let chat_id = ChatId::new(10);
+        let locations = [
+            Location {
+                latitude: 53.5,
+                longitude: 9.5,
+                accuracy: 12.5,
+                timestamp: 1_000_000,
+                ..Default::default()
+            },
+            Location {
+                latitude: 53.6,
+                longitude: 9.6,
+                accuracy: 2.5,
+                timestamp: 1_000_060,
+                ..Default::default()
+            },
+            Location {
+                latitude: 53.7,
+                longitude: 9.7,
+                accuracy: 5.0,
+                timestamp: 1_000_060 + EXPORT_SEGMENT_MAX_GAP + 1,
+                ..Default::default()
+            },
+        ];
+        save(&t.ctx, chat_id, 20, &locations, false).unwrap();
+
+        let gpx = get_gpx(&t.ctx, chat_id, 0, 0, 0);
+        assert!(gpx.starts_with("<?xml"));
+        assert_eq!(gpx.matches("<trk>").count(), 1);
+        assert_eq!(gpx.matches("<trkseg>").count(), 2);
+        assert_eq!(gpx.matches("<trkpt ").count(), 3);
+        assert!(gpx.contains("<trkpt lat=\"53.5\" lon=\"9.5\"><time>1970-01-12T13:46:40Z</time>"));
+        assert!(gpx.contains("<dc:accuracy>12.5</dc:accuracy>"));
+        assert!(gpx.ends_with("</gpx>"));
+
+        let geojson: serde_json::Value =
+            serde_json::from_str(&get_geojson(&t.ctx, chat_id, 0, 0, 0)).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["geometry"]["type"], "MultiLineString");
+        assert_eq!(features[0]["geometry"]["coordinates"][0][1][0], 9.6);
+        assert_eq!(features[0]["geometry"]["coordinates"][0][1][1], 53.6);
+        assert_eq!(features[0]["geometry"]["coordinates"][1][0][1], 53.7);
+        assert_eq!(features[0]["properties"]["contact_id"], 20);
+        assert_eq!(features[0]["properties"]["accuracies"][0][0], 12.5);
+        assert_eq!(features[0]["properties"]["timestamps"][0][1], 1_000_060);
+
+        assert!(get_gpx(&t.ctx, chat_id, 21, 0, 0).contains("<gpx "));
+        assert!(!get_gpx(&t.ctx, chat_id, 21, 0, 0).contains("<trk>"));
+    }
+
+    /// Checks that every location is within `max_error` meters of the simplified track.
+    fn assert_error_bound(locations: &[Location], simplified: &[Location], max_error: f64) {
+        for location in locations {
+            let error = simplified
+                .windows(2)
+                .map(|w| segment_distance(location, &w[0], &w[1]))
+                .fold(std::f64::INFINITY, f64::min);
+            assert!(
+                error <= max_error,
+                "location {} is {}m off the track",
+                location.location_id,
+                error
+            );
+        }
+    }
+
+    fn zigzag_track() -> Vec<Location> {
+        (0..200)
+            .map(|i| Location {
+                location_id: i,
+                // about 11m per step to the north with about 3m noise to the east
+                latitude: 53.0 + f64::from(i) * 0.0001,
+                longitude: 9.0 + if i % 2 == 0 { 0.0 } else { 0.000_05 },
+                timestamp: 1_000_000 + i64::from(i) * 60,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_simplify_track() {
+        let locations = zigzag_track();
+
+        let simplified = simplify_track(&locations, 0.0, 0.0);
+        assert_eq!(simplified.len(), locations.len());
+
+        let simplified = simplify_track(&locations, 10.0, 0.0);
+        assert_eq!(simplified.len(), 2);
+        assert_eq!(simplified[0].location_id, 0);
+        assert_eq!(simplified[1].location_id, 199);
+        assert_error_bound(&locations, &simplified, 10.0);
+
+        let simplified = simplify_track(&locations, 1.0, 0.0);
+        assert!(simplified.len() > 2);
+        assert_error_bound(&locations, &simplified, 1.0);
+    }
+
+    #[test]
+    fn test_simplify_track_keeps_markers() {
+        let mut locations = zigzag_track();
+        locations[42].marker = Some("x".to_string());
+        locations[150].marker = Some("y".to_string());
+
+        let simplified = simplify_track(&locations, 10.0, 5.0);
+        let ids = simplified.iter().map(|l| l.location_id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 42, 150, 199]);
+        assert_eq!(simplified[1].marker, Some("x".to_string()));
+        assert_error_bound(&locations, &simplified, 10.0 + 5.0);
+    }
+
+    #[test]
+    fn test_simplify_track_stationary() {
+        let mut locations = Vec::new();
+        for i in 0..50 {
+            // jitter of about 1m around the same position
+            locations.push(Location {
+                location_id: i,
+                latitude: 53.0 + if i % 3 == 0 { 0.000_01 } else { 0.0 },
+                longitude: 9.0,
+                timestamp: 1_000_000 + i64::from(i) * 60,
+                ..Default::default()
+            });
+        }
+        locations.push(Location {
+            location_id: 50,
+            latitude: 53.01,
+            longitude: 9.0,
+            timestamp: 1_000_000 + 50 * 60,
+            ..Default::default()
+        });
+
+        let simplified = simplify_track(&locations, 0.0, 5.0);
+        let ids = simplified.iter().map(|l| l.location_id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 49, 50]);
+        assert_error_bound(&locations, &simplified, 5.0);
+
+        assert!(simplify_track(&[], 10.0, 5.0).is_empty());
+        assert_eq!(simplify_track(&locations[..1], 10.0, 5.0).len(), 1);
+    }
+
+    #[test]
+    fn test_message_kml_poi() {
+        let context = dummy_context();
+
+        let xml = get_message_kml(
+            1_551_906_597,
+            53.790302,
+            9.42311,
+            Some("Bar & Grill"),
+            Some("Main St. 1, Springfield"),
+        );
+        let kml = Kml::parse(&context.ctx, xml.as_bytes()).expect("parsing failed");
+        assert_eq!(kml.locations.len(), 1);
+        let location = &kml.locations[0];
+        assert_eq!(location.label, Some("Bar & Grill".to_string()));
+        assert_eq!(
+            location.description,
+            Some("Main St. 1, Springfield".to_string())
+        );
+        assert!(xml.contains("<coordinates>9.42311,53.790302</coordinates>"));
+        assert!(location.latitude > 53.79 && location.latitude < 53.8);
+        assert!(location.longitude > 9.42 && location.longitude < 9.43);
+        assert_eq!(location.timestamp, 1_551_906_597);
+
+        // positions without a point of interest are rounded
+        let xml = get_message_kml(1_551_906_597, 53.790302, 9.42311, None, None);
+        assert!(xml.contains("<coordinates>9.42,53.79</coordinates>"));
+        let kml = Kml::parse(&context.ctx, xml.as_bytes()).expect("parsing failed");
+        assert_eq!(kml.locations.len(), 1);
+        assert_eq!(kml.locations[0].label, None);
+        assert_eq!(kml.locations[0].description, None);
+    }
+
+    #[test]
+    fn test_geo_uri() {
+        assert_eq!(get_geo_uri(53.5, 9.25, None), "geo:53.5,9.25");
+        assert_eq!(
+            get_geo_uri(53.5, 9.25, Some("Bar & Grill")),
+            "geo:53.5,9.25?q=53.5,9.25(Bar%20%26%20Grill)"
+        );
+
+        assert_eq!(remove_geo_uri("geo:53.5,9.25"), "");
+        assert_eq!(
+            remove_geo_uri("See you there!\n\ngeo:53.5,9.25?q=53.5,9.25(Bar)\n"),
+            "See you there!"
+        );
+        assert_eq!(remove_geo_uri("Hello\nworld"), "Hello\nworld");
+    }
 }
@@ -7,6 +7,7 @@ use failure::Fail;
 use serde::{Deserialize, Serialize};
 
 use crate::chat::{self, Chat, ChatId};
+use crate::config::Config;
 use crate::constants::*;
 use crate::contact::*;
 use crate::context::*;
@@ -395,6 +396,54 @@ impl Message {
         self.param.set_float(Param::SetLongitude, longitude);
     }
 
+    /// Set a point of interest, a location with a name, that should be bound to the message object.
+    ///
+    /// Works as [Message::set_location], but the location is sent together with
+    /// the given label and an optional description, typically the address.
+    /// For recipients not using Delta Chat, a `geo:` URI is added to the text.
+    ///
+    /// @param latitude North-south position of the location.
+    /// @param longitude East-west position of the location.
+    /// @param label Name of the place.
+    /// @param description Description of the place, typically the address.
+    pub fn set_poi(
+        &mut self,
+        latitude: f64,
+        longitude: f64,
+        label: impl AsRef<str>,
+        description: Option<&str>,
+    ) {
+        if latitude == 0.0 && longitude == 0.0 {
+            return;
+        }
+
+        // params are separated by newlines, so multi-line addresses are collapsed
+        self.set_location(latitude, longitude);
+        self.param
+            .set(Param::PoiLabel, dc_collapse_lines(label.as_ref()));
+        match description {
+            Some(description) => self
+                .param
+                .set(Param::PoiDescription, dc_collapse_lines(description)),
+            None => self.param.remove(Param::PoiDescription),
+        };
+    }
+
+    /// Check if the message shares a point of interest set by [Message::set_poi].
+    pub fn is_poi(&self) -> bool {
+        self.param.exists(Param::PoiLabel)
+    }
+
+    /// Returns the name of the point of interest bound to the message, if any.
+    pub fn get_poi_label(&self) -> Option<&str> {
+        self.param.get(Param::PoiLabel)
+    }
+
+    /// Returns the description of the point of interest bound to the message, if any.
+    pub fn get_poi_description(&self) -> Option<&str> {
+        self.param.get(Param::PoiDescription)
+    }
+
     pub fn get_timestamp(&self) -> i64 {
         if 0 != self.timestamp_sent {
             self.timestamp_sent
@@ -1064,6 +1113,188 @@ pub fn markseen_msgs(context: &Context, msg_ids: &[MsgId]) -> bool {
     true
 }
 
+/// Returns the server UIDs of unread messages in the given folder.
+pub(crate) fn get_unseen_server_uids(
+    context: &Context,
+    server_folder: impl AsRef<str>,
+) -> Vec<u32> {
+    context
+        .sql
+        .query_map(
+            concat!(
+                "SELECT server_uid FROM msgs",
+                " WHERE server_folder=? AND server_uid>0 AND chat_id>9 AND state IN (?, ?)",
+                " ORDER BY server_uid"
+            ),
+            params![
+                server_folder.as_ref(),
+                MessageState::InFresh,
+                MessageState::InNoticed
+            ],
+            |row| row.get::<_, u32>(0),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .unwrap_or_default()
+}
+
+/// Maximum number of messages scheduled for deletion from the server at once.
+pub(crate) const SERVER_DELETION_BATCH_SIZE: usize = 100;
+
+/// Schedules the deletion of messages from the server that were received more than
+/// `Config::DeleteServerAfter` seconds ago, the local copies are kept.
+///
+/// The newest Autocrypt Setup Message is not deleted
+/// as it may still wait for the setup code to be entered;
+/// older ones are superseded by it and expire as usual.
+/// At most [SERVER_DELETION_BATCH_SIZE] messages are scheduled per call.
+/// Returns the number of messages scheduled for deletion.
+pub(crate) fn schedule_server_deletion(context: &Context) -> usize {
+    let delete_server_after = context.get_config_int(Config::DeleteServerAfter);
+    if delete_server_after <= 0 {
+        return 0;
+    }
+    let threshold = time() - i64::from(delete_server_after);
+
+    let msgs = context.sql.query_map(
+        concat!(
+            "SELECT id FROM msgs",
+            " WHERE server_uid>0 AND timestamp_rcvd<?",
+            " AND id NOT IN (SELECT foreign_id FROM jobs WHERE action=?)",
+            " ORDER BY timestamp_rcvd LIMIT ?"
+        ),
+        params![
+            threshold,
+            Action::DeleteMsgOnImap,
+            SERVER_DELETION_BATCH_SIZE as i64
+        ],
+        |row| row.get::<_, MsgId>(0),
+        |rows| {
+            rows.collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(Into::into)
+        },
+    );
+    let msgs = match msgs {
+        Ok(msgs) => msgs,
+        Err(err) => {
+            warn!(context, "schedule_server_deletion failed: {}", err);
+            return 0;
+        }
+    };
+
+    let newest_setup_msg_id = get_newest_setup_msg_id(context);
+    let mut cnt = 0;
+    for msg_id in msgs {
+        if Some(msg_id) == newest_setup_msg_id {
+            continue;
+        }
+        let mut job_param = Params::new();
+        job_param.set_int(Param::KeepLocal, 1);
+        job_add(
+            context,
+            Action::DeleteMsgOnImap,
+            msg_id.to_u32() as i32,
+            job_param,
+            0,
+        );
+        cnt += 1;
+    }
+    cnt
+}
+
+/// Returns the newest Autocrypt Setup Message still present on the server.
+fn get_newest_setup_msg_id(context: &Context) -> Option<MsgId> {
+    context
+        .sql
+        .query_map(
+            concat!(
+                "SELECT id, param FROM msgs",
+                " WHERE server_uid>0 AND from_id=?",
+                " ORDER BY timestamp_rcvd DESC, id DESC"
+            ),
+            params![DC_CONTACT_ID_SELF],
+            |row| Ok((row.get::<_, MsgId>(0)?, row.get::<_, String>(1)?)),
+            |rows| {
+                for row in rows {
+                    let (msg_id, param) = row?;
+                    let param: Params = param.parse().unwrap_or_default();
+                    if param.get_cmd() == SystemMessage::AutocryptSetupMessage {
+                        return Ok(Some(msg_id));
+                    }
+                }
+                Ok(None)
+            },
+        )
+        .unwrap_or_default()
+}
+
+/// Marks messages as seen that are flagged `\\Seen` on the server,
+/// eg. because they were read on another device or in a webmail.
+///
+/// In contrast to markseen_msgs(), no MDNs are sent
+/// and the flags on the server are not touched as they are already set.
+/// Returns the number of messages marked as seen.
+pub(crate) fn markseen_by_server_uids(
+    context: &Context,
+    server_folder: impl AsRef<str>,
+    server_uids: &[u32],
+) -> usize {
+    if server_uids.is_empty() {
+        return 0;
+    }
+
+    let msgs = context.sql.prepare(
+        concat!(
+            "SELECT id, chat_id FROM msgs",
+            " WHERE server_folder=? AND server_uid=? AND chat_id>9 AND state IN (?, ?)"
+        ),
+        |mut stmt, _| {
+            let mut res = Vec::new();
+            for server_uid in server_uids {
+                let rows = stmt.query_map(
+                    params![
+                        server_folder.as_ref(),
+                        *server_uid,
+                        MessageState::InFresh,
+                        MessageState::InNoticed
+                    ],
+                    |row| Ok((row.get::<_, MsgId>(0)?, row.get::<_, ChatId>(1)?)),
+                )?;
+                for row in rows {
+                    res.push(row?);
+                }
+            }
+            Ok(res)
+        },
+    );
+    let msgs = match msgs {
+        Ok(msgs) => msgs,
+        Err(err) => {
+            warn!(context, "markseen_by_server_uids failed: {}", err);
+            return 0;
+        }
+    };
+
+    for (msg_id, _) in msgs.iter() {
+        update_msg_state(context, *msg_id, MessageState::InSeen);
+        info!(context, "Message {} seen on server.", msg_id);
+    }
+
+    // a single event per chat, many messages may be seen at once
+    let mut chat_ids: Vec<ChatId> = msgs.iter().map(|(_, chat_id)| *chat_id).collect();
+    chat_ids.sort();
+    chat_ids.dedup();
+    for chat_id in chat_ids {
+        context.call_cb(Event::MsgsChanged {
+            chat_id,
+            msg_id: MsgId::new(0),
+        });
+    }
+    msgs.len()
+}
+
 pub fn update_msg_state(context: &Context, msg_id: MsgId, state: MessageState) -> bool {
     sql::execute(
         context,
@@ -1436,6 +1667,167 @@ mod tests {
         assert_eq!(_msg2.get_filemime(), None);
     }
 
+    #[test]
+    fn test_poi_multiline_description() {
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+        ctx.set_config(Config::ConfiguredAddr, Some("self@example.com"))
+            .unwrap();
+        let contact = Contact::create(ctx, "", "dest@example.com").unwrap();
+        let chat = chat::create_by_contact_id(ctx, contact).unwrap();
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("meet me here".to_string()));
+        msg.set_poi(
+            53.5,
+            9.25,
+            "Bar\n& Grill",
+            Some("Main St. 1\r\n\n20095 Hamburg\n"),
+        );
+        let msg_id = chat::prepare_msg(ctx, chat, &mut msg).unwrap();
+
+        let msg = Message::load_from_db(ctx, msg_id).unwrap();
+        assert!(msg.is_poi());
+        assert_eq!(msg.get_poi_label(), Some("Bar & Grill"));
+        assert_eq!(msg.get_poi_description(), Some("Main St. 1 20095 Hamburg"));
+        assert_eq!(msg.get_text(), Some("meet me here".to_string()));
+    }
+
+    #[test]
+    fn test_markseen_by_server_uids() {
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events2 = std::sync::Arc::clone(&events);
+        let d = test::test_context(Some(Box::new(move |_, event| {
+            if let Event::MsgsChanged { .. } = event {
+                events2.lock().unwrap().push(event);
+            }
+        })));
+        let ctx = &d.ctx;
+        let contact = Contact::create(ctx, "", "dest@example.com").unwrap();
+        let chat_id = chat::create_by_contact_id(ctx, contact).unwrap();
+        ctx.sql
+            .execute(
+                "INSERT INTO msgs (chat_id, state, server_folder, server_uid, rfc724_mid)
+                 VALUES (?, ?, 'INBOX', 42, 'fresh@example.com');",
+                params![chat_id, MessageState::InFresh],
+            )
+            .unwrap();
+        let msg_id = MsgId::new(sql::get_rowid(
+            ctx,
+            &ctx.sql,
+            "msgs",
+            "rfc724_mid",
+            "fresh@example.com",
+        ));
+
+        assert_eq!(markseen_by_server_uids(ctx, "INBOX", &[41]), 0);
+        assert_eq!(markseen_by_server_uids(ctx, "Sent", &[42]), 0);
+        assert_eq!(
+            Message::load_from_db(ctx, msg_id).unwrap().get_state(),
+            MessageState::InFresh
+        );
+
+        assert_eq!(markseen_by_server_uids(ctx, "INBOX", &[41, 42]), 1);
+        assert_eq!(
+            Message::load_from_db(ctx, msg_id).unwrap().get_state(),
+            MessageState::InSeen
+        );
+        // no MDN or IMAP job is added for messages seen on the server
+        assert!(!ctx.sql.exists("SELECT id FROM jobs;", params![]).unwrap());
+
+        // already seen messages are not changed again
+        assert_eq!(markseen_by_server_uids(ctx, "INBOX", &[42]), 0);
+
+        // several messages seen at once result in one event per chat
+        events.lock().unwrap().clear();
+        for uid in 43..46 {
+            ctx.sql
+                .execute(
+                    "INSERT INTO msgs (chat_id, state, server_folder, server_uid)
+                     VALUES (?, ?, 'INBOX', ?);",
+                    params![chat_id, MessageState::InFresh, uid],
+                )
+                .unwrap();
+        }
+        assert_eq!(markseen_by_server_uids(ctx, "INBOX", &[43, 44, 45]), 3);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![Event::MsgsChanged {
+                chat_id,
+                msg_id: MsgId::new(0)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_schedule_server_deletion() {
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+        let contact = Contact::create(ctx, "", "dest@example.com").unwrap();
+        let chat_id = chat::create_by_contact_id(ctx, contact).unwrap();
+        let now = time();
+        for (rfc724_mid, timestamp_rcvd, server_uid, param) in &[
+            ("old@example.com", now - 7200, 1, ""),
+            // sent long ago, but only received now
+            ("new@example.com", now, 2, ""),
+            ("deleted@example.com", now - 7200, 0, ""),
+            // superseded by the newer setup message
+            ("setup-old@example.com", now - 9000, 3, "S=6"),
+            ("setup@example.com", now - 7200, 4, "S=6"),
+        ] {
+            ctx.sql
+                .execute(
+                    "INSERT INTO msgs (chat_id, from_id, timestamp, timestamp_rcvd, server_folder, server_uid, rfc724_mid, param)
+                     VALUES (?, ?, ?, ?, 'INBOX', ?, ?, ?);",
+                    params![chat_id, DC_CONTACT_ID_SELF, now - 7200, *timestamp_rcvd, *server_uid, *rfc724_mid, *param],
+                )
+                .unwrap();
+        }
+
+        // nothing is deleted by default
+        assert_eq!(schedule_server_deletion(ctx), 0);
+
+        ctx.set_config(Config::DeleteServerAfter, Some("3600"))
+            .unwrap();
+        assert_eq!(schedule_server_deletion(ctx), 2);
+        let get_job_param = |rfc724_mid: &str| {
+            let msg_id = MsgId::new(sql::get_rowid(
+                ctx,
+                &ctx.sql,
+                "msgs",
+                "rfc724_mid",
+                rfc724_mid,
+            ));
+            ctx.sql
+                .query_get_value::<_, String>(
+                    ctx,
+                    "SELECT param FROM jobs WHERE action=? AND foreign_id=?;",
+                    params![Action::DeleteMsgOnImap, msg_id],
+                )
+                .map(|job_param| job_param.parse::<Params>().unwrap())
+        };
+        let job_param = get_job_param("old@example.com").unwrap();
+        assert_eq!(job_param.get_bool(Param::KeepLocal), Some(true));
+        assert!(get_job_param("setup-old@example.com").is_some());
+        assert!(get_job_param("setup@example.com").is_none());
+
+        // messages are not scheduled twice
+        assert_eq!(schedule_server_deletion(ctx), 0);
+
+        // a backlog of old messages is scheduled in batches
+        for i in 0..SERVER_DELETION_BATCH_SIZE + 10 {
+            ctx.sql
+                .execute(
+                    "INSERT INTO msgs (chat_id, timestamp_rcvd, server_folder, server_uid, rfc724_mid)
+                     VALUES (?, ?, 'INBOX', ?, ?);",
+                    params![chat_id, now - 7200, 100 + i as i64, format!("{}@example.com", i)],
+                )
+                .unwrap();
+        }
+        assert_eq!(schedule_server_deletion(ctx), SERVER_DELETION_BATCH_SIZE);
+        assert_eq!(schedule_server_deletion(ctx), 10);
+    }
+
     #[test]
     pub fn test_get_summarytext_by_raw() {
         let d = test::dummy_context();
@@ -26,8 +26,15 @@ const UPPER_LIMIT_FILE_SIZE: u64 = 49 * 1024 * 1024 / 4 * 3;
 
 #[derive(Debug, Clone)]
 pub enum Loaded {
-    Message { chat: Chat },
-    MDN { additional_msg_ids: Vec<String> },
+    Message {
+        chat: Chat,
+    },
+    MDN {
+        additional_msg_ids: Vec<String>,
+        /// Whether the MDN must be encrypted,
+        /// otherwise MDNs are sent unencrypted.
+        e2ee_guaranteed: bool,
+    },
 }
 
 /// Helper to construct mime messages.
@@ -166,6 +173,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         context: &'a Context,
         msg: &'b Message,
         additional_msg_ids: Vec<String>,
+        e2ee_guaranteed: bool,
     ) -> Result<Self, Error> {
         ensure!(!msg.chat_id.is_special(), "Invalid chat id");
 
@@ -185,7 +193,10 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                 contact.get_addr().to_string(),
             )],
             timestamp: dc_create_smeared_timestamp(context),
-            loaded: Loaded::MDN { additional_msg_ids },
+            loaded: Loaded::MDN {
+                additional_msg_ids,
+                e2ee_guaranteed,
+            },
             msg,
             in_reply_to: String::default(),
             references: String::default(),
@@ -217,7 +228,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
     fn is_e2ee_guaranteed(&self) -> bool {
         match &self.loaded {
             Loaded::Message { chat } => {
-                if chat.typ == Chattype::VerifiedGroup {
+                if chat.is_verified() {
                     return true;
                 }
 
@@ -238,14 +249,16 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
 
                 false
             }
-            Loaded::MDN { .. } => false,
+            Loaded::MDN {
+                e2ee_guaranteed, ..
+            } => *e2ee_guaranteed,
         }
     }
 
     fn min_verified(&self) -> PeerstateVerifiedStatus {
         match &self.loaded {
             Loaded::Message { chat } => {
-                if chat.typ == Chattype::VerifiedGroup {
+                if chat.is_verified() {
                     PeerstateVerifiedStatus::BidirectVerified
                 } else {
                     PeerstateVerifiedStatus::Unverified
@@ -258,7 +271,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
     fn should_force_plaintext(&self) -> i32 {
         match &self.loaded {
             Loaded::Message { chat } => {
-                if chat.typ == Chattype::VerifiedGroup {
+                if chat.is_verified() {
                     0
                 } else {
                     self.msg
@@ -267,7 +280,15 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                         .unwrap_or_default()
                 }
             }
-            Loaded::MDN { .. } => ForcePlaintext::NoAutocryptHeader as i32,
+            Loaded::MDN {
+                e2ee_guaranteed, ..
+            } => {
+                if *e2ee_guaranteed {
+                    0
+                } else {
+                    ForcePlaintext::NoAutocryptHeader as i32
+                }
+            }
         }
     }
 
@@ -358,6 +379,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         // - Chat-*, except Chat-Version
         // - Secure-Join*
         // - Subject
+        // - From and To, these are also added to the unencrypted outer message
         let mut protected_headers: Vec<Header> = Vec::new();
 
         // All other headers
@@ -474,12 +496,21 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             render_rfc724_mid(&rfc724_mid),
         ));
 
-        unprotected_headers.push(Header::new_with_value("To".into(), to).unwrap());
-        unprotected_headers.push(Header::new_with_value("From".into(), vec![from]).unwrap());
+        let to_header = Header::new_with_value("To".into(), to).unwrap();
+        let from_header = Header::new_with_value("From".into(), vec![from]).unwrap();
+        unprotected_headers.push(to_header.clone());
+        unprotected_headers.push(from_header.clone());
 
         let mut is_gossiped = false;
 
         let outer_message = if is_encrypted {
+            // Clients not supporting protected headers would only see the placeholder subject
+            // of the outer message, so show them the real subject in a legacy display part.
+            message = PartBuilder::new()
+                .message_type(MimeMultipartType::Mixed)
+                .child(render_legacy_display_part(&subject_str).build())
+                .child(message.build());
+
             // Add gossip headers in chats with multiple recipients
             if peerstates.len() > 1 && self.should_do_gossip() {
                 for peerstate in peerstates.iter().filter_map(|(state, _)| state.as_ref()) {
@@ -494,6 +525,8 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             }
 
             // Store protected headers in the inner message.
+            protected_headers.push(to_header);
+            protected_headers.push(from_header);
             for header in protected_headers.into_iter() {
                 message = message.header(header);
             }
@@ -587,7 +620,13 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         let latitude = self.msg.param.get_float(Param::SetLatitude)?;
         let longitude = self.msg.param.get_float(Param::SetLongitude)?;
 
-        let kml_file = location::get_message_kml(self.msg.timestamp_sort, latitude, longitude);
+        let kml_file = location::get_message_kml(
+            self.msg.timestamp_sort,
+            latitude,
+            longitude,
+            self.msg.get_poi_label(),
+            self.msg.get_poi_description(),
+        );
         let part = PartBuilder::new()
             .content_type(
                 &"application/vnd.google-earth.kml+xml"
@@ -602,6 +641,13 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         Some(part)
     }
 
+    fn get_poi_geo_uri(&self) -> Option<String> {
+        let label = self.msg.get_poi_label()?;
+        let latitude = self.msg.param.get_float(Param::SetLatitude)?;
+        let longitude = self.msg.param.get_float(Param::SetLongitude)?;
+        Some(location::get_geo_uri(latitude, longitude, Some(label)))
+    }
+
     fn get_location_kml_part(&mut self) -> Result<PartBuilder, Error> {
         let (kml_content, last_added_location_id) =
             location::get_kml(self.context, self.msg.chat_id)?;
@@ -650,6 +696,24 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             let encoded = encode_words(&chat.name);
             protected_headers.push(Header::new("Chat-Group-Name".into(), encoded));
 
+            // like the name, the description is sent with every message,
+            // so that members added later learn it as well
+            if !chat.get_description().is_empty() {
+                protected_headers.push(Header::new(
+                    "Chat-Group-Description".into(),
+                    encode_words(chat.get_description()),
+                ));
+            }
+
+            let admins = chat::get_group_admins(context, chat.id)
+                .into_iter()
+                .filter_map(|contact_id| Contact::get_by_id(context, contact_id).ok())
+                .map(|contact| contact.get_addr().to_string())
+                .collect::<Vec<_>>();
+            if !admins.is_empty() {
+                protected_headers.push(Header::new("Chat-Group-Admins".into(), admins.join(", ")));
+            }
+
             match command {
                 SystemMessage::MemberRemovedFromGroup => {
                     let email_to_remove = self.msg.param.get(Param::Arg).unwrap_or_default();
@@ -691,6 +755,26 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                         value_to_add.into(),
                     ));
                 }
+                SystemMessage::GroupDescriptionChanged => {
+                    let old_description = self.msg.param.get(Param::Arg).unwrap_or_default();
+                    protected_headers.push(Header::new(
+                        "Chat-Group-Description-Changed".into(),
+                        encode_words(old_description),
+                    ));
+                }
+                SystemMessage::GroupAdminsChanged => {
+                    let addr = self.msg.param.get(Param::Arg).unwrap_or_default();
+                    if !addr.is_empty() {
+                        protected_headers.push(Header::new(
+                            if self.msg.param.get_int(Param::Arg2).unwrap_or_default() != 0 {
+                                "Chat-Group-Admin-Added".into()
+                            } else {
+                                "Chat-Group-Admin-Removed".into()
+                            },
+                            addr.into(),
+                        ));
+                    }
+                }
                 SystemMessage::GroupImageChanged => {
                     protected_headers.push(Header::new(
                         "Chat-Content".to_string(),
@@ -827,6 +911,12 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                 ""
             }
         };
+        // classic MUAs do not understand message.kml, add a geo: URI they may link
+        let final_text = match self.get_poi_geo_uri() {
+            Some(geo_uri) if final_text.is_empty() => geo_uri,
+            Some(geo_uri) => format!("{}\r\n\r\n{}", final_text, geo_uri),
+            None => final_text.to_string(),
+        };
 
         let footer = &self.selfstatus;
         let message_text = format!(
@@ -910,7 +1000,7 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
         // RFC 6522, this also requires the `report-type` parameter which is equal
         // to the MIME subtype of the second body part of the multipart/report
         //
-        // currently, we do not send MDNs encrypted:
+        // currently, we do not send MDNs encrypted unless encryption is required:
         // - in a multi-device-setup that is not set up properly, MDNs would disturb the communication as they
         //   are send automatically which may lead to spreading outdated Autocrypt headers.
         // - they do not carry any information but the Message-ID
@@ -1004,6 +1094,20 @@ fn wrapped_base64_encode(buf: &[u8]) -> String {
         .join("\r\n")
 }
 
+/// Renders the legacy display part of the protected headers.
+///
+/// The part is shown inline by clients that do not support protected headers
+/// and ignored by clients that do.
+fn render_legacy_display_part(subject: &str) -> PartBuilder {
+    PartBuilder::new()
+        .header((
+            "Content-Type".to_string(),
+            "text/rfc822-headers; charset=utf-8; protected-headers=\"v1\"".to_string(),
+        ))
+        .header(("Content-Disposition".to_string(), "inline".to_string()))
+        .body(format!("Subject: {}\r\n", subject))
+}
+
 fn build_body_file(
     context: &Context,
     msg: &Message,
@@ -1154,6 +1258,13 @@ pub fn needs_encoding(to_check: impl AsRef<str>) -> bool {
 mod tests {
     use super::*;
 
+    use crate::aheader::EncryptPreference;
+    use crate::dc_receive_imf::dc_receive_imf;
+    use crate::headerdef::HeaderDef;
+    use crate::key::Key;
+    use crate::mimeparser::MimeMessage;
+    use crate::test_utils::*;
+
     #[test]
     fn test_render_email_address() {
         let display_name = "ä space";
@@ -1206,6 +1317,106 @@ mod tests {
         assert_eq!(wrapped_base64_encode(input), output);
     }
 
+    #[test]
+    fn test_render_legacy_display_part() {
+        let part = render_legacy_display_part("hello wörld")
+            .build()
+            .as_string();
+        let mail = mailparse::parse_mail(part.as_bytes()).unwrap();
+        assert_eq!(mail.ctype.mimetype, "text/rfc822-headers");
+        assert_eq!(
+            mail.ctype.params.get("protected-headers"),
+            Some(&"v1".to_string())
+        );
+        assert_eq!(mail.get_body().unwrap().trim(), "Subject: hello wörld");
+    }
+
+    /// Replaces the given top-level headers, these are not protected by the encryption.
+    fn replace_outer_headers(message: &[u8], replacements: &[(&str, &str)]) -> Vec<u8> {
+        let message = String::from_utf8_lossy(message);
+        let end = message.find("\r\n\r\n").unwrap();
+        let headers = message[..end]
+            .split("\r\n")
+            .map(|line| {
+                replacements
+                    .iter()
+                    .find(|(name, _)| line.starts_with(&format!("{}: ", name)))
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .unwrap_or_else(|| line.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        format!("{}{}", headers, &message[end..]).into_bytes()
+    }
+
+    #[test]
+    fn test_protected_headers_roundtrip() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+        configure_bob_keypair(&bob.ctx);
+
+        let mut peerstate = Peerstate::new(&alice.ctx, "bob@example.net".to_string());
+        let key = Key::from(bob_keypair().public);
+        peerstate.public_key_fingerprint = Some(key.fingerprint());
+        peerstate.public_key = Some(key);
+        peerstate.prefer_encrypt = EncryptPreference::Mutual;
+        peerstate.save_to_db(&alice.ctx.sql, true).unwrap();
+        let bob_id = Contact::create(&alice.ctx, "Bob", "bob@example.net").unwrap();
+        let chat_id = chat::create_by_contact_id(&alice.ctx, bob_id).unwrap();
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("hi".to_string()));
+        let msg_id = chat::prepare_msg(&alice.ctx, chat_id, &mut msg).unwrap();
+        let msg = Message::load_from_db(&alice.ctx, msg_id).unwrap();
+        let rendered = MimeFactory::from_msg(&alice.ctx, &msg, false)
+            .unwrap()
+            .render()
+            .unwrap();
+        assert!(rendered.is_encrypted);
+
+        // the protected headers win over the forged top-level headers,
+        // the legacy display part is not shown
+        let forged = replace_outer_headers(
+            &rendered.message,
+            &[
+                ("From", "Mallory <mallory@example.org>"),
+                ("To", "<eve@example.org>"),
+            ],
+        );
+        let parsed = MimeMessage::from_bytes(&bob.ctx, &forged).unwrap();
+        assert!(parsed
+            .get(HeaderDef::From_)
+            .unwrap()
+            .contains("alice@example.com"));
+        assert!(parsed
+            .get(HeaderDef::To)
+            .unwrap()
+            .contains("bob@example.net"));
+        assert_eq!(parsed.get(HeaderDef::Subject).unwrap(), "Chat: hi");
+        assert_eq!(parsed.parts.len(), 1);
+        assert_eq!(parsed.parts[0].typ, Viewtype::Text);
+        assert_eq!(parsed.parts[0].msg, "hi");
+
+        // an encrypted MDN is parsed the same way
+        dc_receive_imf(&bob.ctx, &rendered.message, "INBOX", 1, false).unwrap();
+        let (_, _, received_id) = message::rfc724_mid_exists(&bob.ctx, &msg.rfc724_mid).unwrap();
+        let received = Message::load_from_db(&bob.ctx, received_id).unwrap();
+        let rendered = MimeFactory::from_mdn(&bob.ctx, &received, Vec::new(), true)
+            .unwrap()
+            .render()
+            .unwrap();
+        assert!(rendered.is_encrypted);
+        let forged = replace_outer_headers(&rendered.message, &[("Subject", "forged")]);
+        let parsed = MimeMessage::from_bytes(&alice.ctx, &forged).unwrap();
+        assert_eq!(
+            parsed.get(HeaderDef::Subject).unwrap(),
+            alice.ctx.stock_str(StockMessage::ReadRcpt).as_ref()
+        );
+        assert!(parsed.parts.is_empty());
+        assert_eq!(parsed.reports.len(), 1);
+    }
+
     #[test]
     fn test_needs_encoding() {
         assert!(!needs_encoding(""));
@@ -78,6 +78,8 @@ pub enum SystemMessage {
     SecurejoinMessage = 7,
     LocationStreamingEnabled = 8,
     LocationOnly = 9,
+    GroupAdminsChanged = 10,
+    GroupDescriptionChanged = 11,
 }
 
 impl Default for SystemMessage {
@@ -280,6 +282,38 @@ impl MimeMessage {
         }
     }
 
+    /// Processes messages sharing a point of interest, a location with a name.
+    ///
+    /// The name and description are added to the parameters of the parts
+    /// and the `geo:` URI added for classic MUAs is removed from the text.
+    fn parse_poi(&mut self) {
+        let location = match self
+            .message_kml
+            .as_ref()
+            .and_then(|kml| kml.locations.first())
+        {
+            Some(location) => location,
+            None => return,
+        };
+        let label = match location.label {
+            Some(ref label) => label,
+            None => return,
+        };
+
+        // the values come from the sender and params are separated by newlines
+        let label = dc_collapse_lines(label);
+        let description = location.description.as_ref().map(|d| dc_collapse_lines(d));
+        for part in self.parts.iter_mut() {
+            part.param.set(Param::PoiLabel, &label);
+            if let Some(ref description) = description {
+                part.param.set(Param::PoiDescription, description);
+            }
+            if part.typ == Viewtype::Text {
+                part.msg = location::remove_geo_uri(&part.msg).to_string();
+            }
+        }
+    }
+
     fn parse_headers(&mut self, context: &Context) -> Result<()> {
         self.parse_system_message_headers(context)?;
         self.parse_avatar_headers();
@@ -322,6 +356,7 @@ impl MimeMessage {
         }
 
         self.parse_attachments();
+        self.parse_poi();
 
         // See if an MDN is requested from the other side
         if !self.decrypting_failed && !self.parts.is_empty() {
@@ -403,6 +438,11 @@ impl MimeMessage {
         self.header.get(headerdef.get_headername())
     }
 
+    /// Returns the value of an arbitrary header, the name is case-insensitive.
+    pub fn get_header(&self, name: &str) -> Option<&String> {
+        self.header.get(&name.to_lowercase())
+    }
+
     fn parse_first_addr(&self, context: &Context, headerdef: HeaderDef) -> Option<MailAddr> {
         if let Some(value) = self.get(headerdef.clone()) {
             match mailparse::addrparse(&value) {
@@ -757,14 +797,17 @@ impl MimeMessage {
             .and_then(|msgid| parse_message_id(msgid))
     }
 
+    /// Merges `fields` into `headers`.
+    ///
+    /// Existing values are only overwritten by headers that may be protected,
+    /// so the values from the encrypted part are preferred
+    /// over the values from the unencrypted top-level.
     fn merge_headers(headers: &mut HashMap<String, String>, fields: &[mailparse::MailHeader<'_>]) {
         for field in fields {
             if let Ok(key) = field.get_key() {
                 // lowercasing all headers is technically not correct, but makes things work better
                 let key = key.to_lowercase();
-                if !headers.contains_key(&key) || // key already exists, only overwrite known types (protected headers)
-                    is_known(&key) || key.starts_with("chat-")
-                {
+                if !headers.contains_key(&key) || is_protectable(&key) {
                     if let Ok(value) = field.get_value() {
                         headers.insert(key, value);
                     }
@@ -940,6 +983,11 @@ fn is_known(key: &str) -> bool {
     }
 }
 
+/// Returns true if the header may be sent in the protected headers of an encrypted message.
+fn is_protectable(key: &str) -> bool {
+    is_known(key) || key.starts_with("chat-") || key.starts_with("secure-join")
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Part {
     pub typ: Viewtype,
@@ -1241,6 +1289,65 @@ mod tests {
         assert_eq!(mimeparser.parts.len(), 1);
     }
 
+    #[test]
+    fn test_merge_protected_headers() {
+        let (outer, _) = mailparse::parse_headers(
+            b"Subject: ...\n\
+              From: alice@example.org\n\
+              Secure-Join-Group: outer\n\
+              Chat-Version: 1.0\n\
+              Test-Header: outer\n\
+              \n",
+        )
+        .unwrap();
+        let (inner, _) = mailparse::parse_headers(
+            b"Content-Type: text/plain; protected-headers=\"v1\"\n\
+              Subject: Chat: hello\n\
+              From: Alice <alice@example.org>\n\
+              Secure-Join-Group: inner\n\
+              Test-Header: inner\n\
+              \n",
+        )
+        .unwrap();
+
+        let mut headers = Default::default();
+        MimeMessage::merge_headers(&mut headers, &outer);
+        MimeMessage::merge_headers(&mut headers, &inner);
+
+        assert_eq!(headers.get("subject").unwrap(), "Chat: hello");
+        assert_eq!(headers.get("from").unwrap(), "Alice <alice@example.org>");
+        assert_eq!(headers.get("secure-join-group").unwrap(), "inner");
+        assert_eq!(headers.get("chat-version").unwrap(), "1.0");
+        assert_eq!(headers.get("test-header").unwrap(), "outer");
+    }
+
+    #[test]
+    fn test_mimeparser_legacy_display_part() {
+        let context = dummy_context();
+        let raw = b"From: hello\n\
+                    Content-Type: multipart/mixed; boundary=\"==break==\"; protected-headers=\"v1\"\n\
+                    Subject: hello subject\n\
+                    \n\
+                    --==break==\n\
+                    Content-Type: text/rfc822-headers; protected-headers=\"v1\"\n\
+                    Content-Disposition: inline\n\
+                    \n\
+                    Subject: hello subject\n\
+                    \n\
+                    --==break==\n\
+                    Content-Type: text/plain\n\
+                    \n\
+                    test1\n\
+                    \n\
+                    --==break==--\n\
+                    \n";
+
+        let mimeparser = MimeMessage::from_bytes(&context.ctx, &raw[..]).unwrap();
+        assert_eq!(mimeparser.get_subject(), Some("hello subject".into()));
+        assert_eq!(mimeparser.parts.len(), 1);
+        assert_eq!(mimeparser.parts[0].msg, "test1");
+    }
+
     #[test]
     fn test_mimeparser_with_avatars() {
         let t = dummy_context();
@@ -1325,6 +1432,54 @@ Content-Disposition: attachment; filename=\"message.kml\"\n\
         assert_eq!(mimeparser.parts.len(), 1);
     }
 
+    #[test]
+    fn test_mimeparser_poi() {
+        let context = dummy_context();
+        let raw = b"Chat-Version: 1.0\n\
+From: foo <foo@example.org>\n\
+To: bar <bar@example.org>\n\
+Subject: Chat: Meet me here\n\
+Content-Type: multipart/mixed; boundary=\"==break==\"\n\
+\n\
+\n\
+--==break==\n\
+Content-Type: text/plain; charset=utf-8\n\
+\n\
+Meet me here\n\
+\n\
+geo:53.5,9.25?q=53.5,9.25(Bar%20%26%20Grill)\n\
+\n\
+--==break==\n\
+Content-Type: application/vnd.google-earth.kml+xml\n\
+Content-Disposition: attachment; filename=\"message.kml\"\n\
+\n\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n\
+<Document>\n\
+<Placemark><name>Bar &amp; Grill</name><description>Main St. 1</description><Timestamp><when>2019-03-06T21:09:57Z</when></Timestamp><Point><coordinates>9.25,53.5</coordinates></Point></Placemark>\n\
+</Document>\n\
+</kml>\n\
+\n\
+--==break==--\n\
+;";
+
+        let mimeparser = MimeMessage::from_bytes(&context.ctx, &raw[..]).unwrap();
+        let kml = mimeparser.message_kml.as_ref().unwrap();
+        assert_eq!(kml.locations.len(), 1);
+        assert_eq!(kml.locations[0].label, Some("Bar & Grill".to_string()));
+
+        assert_eq!(mimeparser.parts.len(), 1);
+        assert_eq!(mimeparser.parts[0].msg, "Meet me here");
+        assert_eq!(
+            mimeparser.parts[0].param.get(Param::PoiLabel),
+            Some("Bar & Grill")
+        );
+        assert_eq!(
+            mimeparser.parts[0].param.get(Param::PoiDescription),
+            Some("Main St. 1")
+        );
+    }
+
     #[test]
     fn test_parse_mdn() {
         let context = dummy_context();
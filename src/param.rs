@@ -88,6 +88,12 @@ pub enum Param {
     /// For Jobs
     SetLongitude = b'n',
 
+    /// For Messages: name of a point of interest bound to the message
+    PoiLabel = b'b',
+
+    /// For Messages: description of a point of interest bound to the message
+    PoiDescription = b'B',
+
     /// For Jobs
     ServerFolder = b'Z',
 
@@ -97,6 +103,9 @@ pub enum Param {
     /// For Jobs
     AlsoMove = b'M',
 
+    /// For Jobs: 1 if only the copy on the server is deleted
+    KeepLocal = b'k',
+
     /// For Jobs: space-separated list of message recipients
     Recipients = b'R',
 
@@ -106,6 +115,15 @@ pub enum Param {
     /// For Groups and Contacts
     ProfileImage = b'i',
 
+    /// For Groups: description of the group
+    GroupDescription = b't',
+
+    /// For Chats: 1 if a one-to-one chat is verified
+    Verified = b'v',
+
+    /// For Chats and Contacts: 1 if messages must not be sent unencrypted
+    EncryptionRequired = b'q',
+
     /// For Chats
     Selftalk = b'K',
 
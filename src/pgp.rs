@@ -8,14 +8,17 @@ use std::io::Cursor;
 use pgp::armor::BlockType;
 use pgp::composed::{
     Deserializable, KeyType as PgpKeyType, Message, SecretKeyParamsBuilder, SignedPublicKey,
-    SignedPublicSubKey, SignedSecretKey, SubkeyParamsBuilder,
+    SignedPublicSubKey, SignedSecretKey, SignedSecretSubKey, SubkeyParamsBuilder,
 };
-use pgp::crypto::{HashAlgorithm, SymmetricKeyAlgorithm};
+use pgp::crypto::{HashAlgorithm, PublicKeyAlgorithm, SymmetricKeyAlgorithm};
+use pgp::packet;
 use pgp::types::{
-    CompressionAlgorithm, KeyTrait, Mpi, PublicKeyTrait, SecretKeyTrait, StringToKey,
+    CompressionAlgorithm, KeyTrait, Mpi, PublicKeyTrait, PublicParams, SecretKeyTrait,
+    SecretParams, StringToKey,
 };
 use rand::{thread_rng, CryptoRng, Rng};
 
+use crate::constants::KeyGenType;
 use crate::dc_tools::EmailAddress;
 use crate::error::Result;
 use crate::key::*;
@@ -147,10 +150,22 @@ pub struct KeyPair {
 }
 
 /// Create a new key pair.
-pub(crate) fn create_keypair(addr: EmailAddress) -> std::result::Result<KeyPair, PgpKeygenError> {
+///
+/// For [KeyGenType::Ed25519] an Ed25519 primary key for signing
+/// and a Curve25519 subkey for encryption is generated,
+/// otherwise both keys are RSA 2048 keys.
+pub(crate) fn create_keypair(
+    addr: EmailAddress,
+    keygen_type: KeyGenType,
+) -> std::result::Result<KeyPair, PgpKeygenError> {
+    let (signing_key_type, encryption_key_type) = match keygen_type {
+        KeyGenType::Rsa2048 | KeyGenType::Default => (PgpKeyType::Rsa(2048), PgpKeyType::Rsa(2048)),
+        KeyGenType::Ed25519 => (PgpKeyType::EdDSA, PgpKeyType::ECDH),
+    };
+
     let user_id = format!("<{}>", addr);
     let key_params = SecretKeyParamsBuilder::default()
-        .key_type(PgpKeyType::Rsa(2048))
+        .key_type(signing_key_type)
         .can_create_certificates(true)
         .can_sign(true)
         .primary_user_id(user_id)
@@ -173,7 +188,7 @@ pub(crate) fn create_keypair(addr: EmailAddress) -> std::result::Result<KeyPair,
         ])
         .subkey(
             SubkeyParamsBuilder::default()
-                .key_type(PgpKeyType::Rsa(2048))
+                .key_type(encryption_key_type)
                 .can_encrypt(true)
                 .passphrase(None)
                 .build()
@@ -205,6 +220,91 @@ pub(crate) fn create_keypair(addr: EmailAddress) -> std::result::Result<KeyPair,
     })
 }
 
+/// Removes the passphrase protection from a secret key and its subkeys.
+///
+/// Keys exported from other OpenPGP implementations as GnuPG or Thunderbird
+/// are usually protected by a passphrase,
+/// while we store our own keys unprotected.
+/// Keys and subkeys that are not protected are taken as they are.
+pub fn unlock_secret_key(key: SignedSecretKey, passphrase: &str) -> Result<SignedSecretKey> {
+    let SignedSecretKey {
+        primary_key,
+        details,
+        public_subkeys,
+        secret_subkeys,
+    } = key;
+
+    let public = primary_key.public_key();
+    let primary_key = packet::SecretKey::new(
+        public.clone(),
+        unlock_secret_params(
+            primary_key.secret_params(),
+            passphrase,
+            public.algorithm(),
+            public.public_params(),
+        )?,
+    );
+
+    let secret_subkeys = secret_subkeys
+        .into_iter()
+        .map(|subkey| {
+            let public = subkey.key.public_key();
+            let key = packet::SecretSubkey::new(
+                public.clone(),
+                unlock_secret_params(
+                    subkey.key.secret_params(),
+                    passphrase,
+                    public.algorithm(),
+                    public.public_params(),
+                )?,
+            );
+            Ok(SignedSecretSubKey {
+                key,
+                signatures: subkey.signatures,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let key = SignedSecretKey {
+        primary_key,
+        details,
+        public_subkeys,
+        secret_subkeys,
+    };
+    key.verify()?;
+    Ok(key)
+}
+
+fn unlock_secret_params(
+    params: &SecretParams,
+    passphrase: &str,
+    algorithm: PublicKeyAlgorithm,
+    public_params: &PublicParams,
+) -> Result<SecretParams> {
+    match params {
+        SecretParams::Plain(_) => Ok(params.clone()),
+        SecretParams::Encrypted(encrypted) => {
+            let plain = encrypted
+                .unlock(|| passphrase.to_string(), algorithm, public_params)
+                .map_err(|_| format_err!("Wrong passphrase or unsupported key protection"))?;
+            Ok(SecretParams::Plain(plain))
+        }
+    }
+}
+
+/// Returns true if the secret key or one of its subkeys is protected by a passphrase.
+pub fn is_secret_key_protected(key: &SignedSecretKey) -> bool {
+    let is_encrypted = |params: &SecretParams| match params {
+        SecretParams::Encrypted(_) => true,
+        SecretParams::Plain(_) => false,
+    };
+    is_encrypted(key.primary_key.secret_params())
+        || key
+            .secret_subkeys
+            .iter()
+            .any(|subkey| is_encrypted(subkey.key.secret_params()))
+}
+
 /// Select public key or subkey to use for encryption.
 ///
 /// First, tries to use subkeys. If none of the subkeys are suitable
@@ -306,7 +406,14 @@ pub fn pk_decrypt(
                 .collect();
 
             for pkey in &pkeys {
-                if dec_msg.verify(&pkey.primary_key).is_ok() {
+                // The signature may be made by the primary key or by a signing subkey,
+                // RSA and Ed25519 keys are both handled by rPGP.
+                if dec_msg.verify(&pkey.primary_key).is_ok()
+                    || pkey
+                        .public_subkeys
+                        .iter()
+                        .any(|subkey| dec_msg.verify(subkey).is_ok())
+                {
                     let fp = hex::encode_upper(pkey.fingerprint());
                     ret_signature_fingerprints.insert(fp);
                 }
@@ -351,6 +458,186 @@ pub fn symm_decrypt<T: std::io::Read + std::io::Seek>(
     }
 }
 
+/// Maximum number of plaintext bytes encrypted into a single message by [SymmEncryptWriter].
+const STREAM_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Length of the random ID identifying an encrypted stream.
+const STREAM_ID_LEN: usize = 16;
+
+/// Length of the header prepended to the plaintext of every chunk:
+/// the stream ID, a 8-byte big-endian sequence number and a final-chunk flag.
+const STREAM_HEADER_LEN: usize = STREAM_ID_LEN + 8 + 1;
+
+/// Writer for symmetric encryption of data that does not need to fit into memory.
+///
+/// rPGP only builds complete messages in memory, so the data is split into chunks,
+/// each encrypted into a binary OpenPGP message written with a 4-byte big-endian length prefix.
+/// Every chunk carries the stream ID, its position and whether it is the last one
+/// inside the encrypted data, so [symm_decrypt_stream] detects reordered,
+/// duplicated, dropped or foreign chunks as well as truncated data.
+///
+/// [SymmEncryptWriter::finish] must be called to write the last chunk.
+pub struct SymmEncryptWriter<W: io::Write> {
+    passphrase: String,
+    out: W,
+    stream_id: [u8; STREAM_ID_LEN],
+    seq: u64,
+    chunk_size: usize,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> SymmEncryptWriter<W> {
+    pub fn new(passphrase: &str, out: W) -> Self {
+        Self::with_chunk_size(passphrase, out, STREAM_CHUNK_SIZE)
+    }
+
+    fn with_chunk_size(passphrase: &str, out: W, chunk_size: usize) -> Self {
+        let mut stream_id = [0u8; STREAM_ID_LEN];
+        thread_rng().fill(&mut stream_id);
+        SymmEncryptWriter {
+            passphrase: passphrase.to_string(),
+            out,
+            stream_id,
+            seq: 0,
+            chunk_size,
+            buf: Vec::new(),
+        }
+    }
+
+    fn write_chunk(&mut self, last: bool) -> Result<()> {
+        use pgp::ser::Serialize;
+        use std::io::Write;
+
+        let mut plain = Vec::with_capacity(STREAM_HEADER_LEN + self.buf.len());
+        plain.extend_from_slice(&self.stream_id);
+        plain.extend_from_slice(&self.seq.to_be_bytes());
+        plain.push(last as u8);
+        plain.append(&mut self.buf);
+
+        let mut rng = thread_rng();
+        let lit_msg = Message::new_literal_bytes("", &plain);
+        drop(plain);
+        let s2k = StringToKey::new_default(&mut rng);
+        let passphrase = &self.passphrase;
+        let msg = lit_msg
+            .encrypt_with_password(&mut rng, s2k, Default::default(), || passphrase.clone())?;
+        let encrypted = msg.to_bytes()?;
+        self.out
+            .write_all(&(encrypted.len() as u32).to_be_bytes())?;
+        self.out.write_all(&encrypted)?;
+        self.seq += 1;
+        Ok(())
+    }
+
+    /// Writes the last chunk and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        use std::io::Write;
+
+        self.write_chunk(true)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: io::Write> io::Write for SymmEncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let len = std::cmp::min(data.len(), self.chunk_size - self.buf.len());
+        self.buf.extend_from_slice(&data[..len]);
+        if self.buf.len() == self.chunk_size {
+            self.write_chunk(false)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // only complete chunks are written, the rest is written by finish()
+        Ok(())
+    }
+}
+
+/// Symmetric encryption of data that does not need to fit into memory,
+/// see [SymmEncryptWriter].
+pub fn symm_encrypt_stream<R: io::Read, W: io::Write>(
+    passphrase: &str,
+    mut plain: R,
+    out: W,
+) -> Result<()> {
+    let mut writer = SymmEncryptWriter::new(passphrase, out);
+    io::copy(&mut plain, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Symmetric decryption of data written by [SymmEncryptWriter].
+///
+/// Data is written to `out` as it is decrypted,
+/// on errors, `out` may contain a part of the data.
+pub fn symm_decrypt_stream<R: io::Read, W: io::Write>(
+    passphrase: &str,
+    mut ctext: R,
+    mut out: W,
+) -> Result<()> {
+    use std::io::{Read, Write};
+
+    let mut stream_id: Option<Vec<u8>> = None;
+    let mut seq = 0u64;
+    loop {
+        let mut len = [0u8; 4];
+        ctext
+            .read_exact(&mut len)
+            .map_err(|err| format_err!("Encrypted data is truncated: {}", err))?;
+        let len = u64::from(u32::from_be_bytes(len));
+
+        let mut encrypted = Vec::new();
+        (&mut ctext).take(len).read_to_end(&mut encrypted)?;
+        ensure!(encrypted.len() as u64 == len, "Encrypted data is truncated");
+        let enc_msg = Message::from_bytes(Cursor::new(encrypted))?;
+        let decryptor = enc_msg.decrypt_with_password(|| passphrase.into())?;
+        let msgs = decryptor.collect::<pgp::errors::Result<Vec<_>>>()?;
+        ensure!(!msgs.is_empty(), "No valid messages found");
+        let content = msgs[0].get_content()?.unwrap_or_default();
+        ensure!(
+            content.len() >= STREAM_HEADER_LEN,
+            "Encrypted chunk is too short"
+        );
+
+        let (header, data) = content.split_at(STREAM_HEADER_LEN);
+        let id = &header[..STREAM_ID_LEN];
+        match &stream_id {
+            Some(expected) => ensure!(
+                expected.as_slice() == id,
+                "Encrypted chunk belongs to a different stream"
+            ),
+            None => stream_id = Some(id.to_vec()),
+        }
+        let chunk_seq =
+            u64::from_be_bytes(header[STREAM_ID_LEN..STREAM_HEADER_LEN - 1].try_into()?);
+        ensure!(
+            chunk_seq == seq,
+            "Encrypted chunk {} is out of order",
+            chunk_seq
+        );
+        seq += 1;
+
+        out.write_all(data)?;
+        match header[STREAM_HEADER_LEN - 1] {
+            0 => continue,
+            1 => break,
+            flag => bail!("Invalid chunk flag {}", flag),
+        }
+    }
+
+    // nothing may follow the last chunk
+    let mut rest = [0u8; 1];
+    ensure!(
+        ctext.read(&mut rest)? == 0,
+        "Unexpected data after the end of the encrypted data"
+    );
+    out.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,11 +674,50 @@ mod tests {
     #[test]
     #[ignore] // is too expensive
     fn test_create_keypair() {
-        let keypair0 = create_keypair(EmailAddress::new("foo@bar.de").unwrap()).unwrap();
-        let keypair1 = create_keypair(EmailAddress::new("two@zwo.de").unwrap()).unwrap();
+        let keypair0 = create_keypair(
+            EmailAddress::new("foo@bar.de").unwrap(),
+            KeyGenType::Default,
+        )
+        .unwrap();
+        let keypair1 = create_keypair(
+            EmailAddress::new("two@zwo.de").unwrap(),
+            KeyGenType::Default,
+        )
+        .unwrap();
         assert_ne!(keypair0.public, keypair1.public);
     }
 
+    #[test]
+    fn test_create_keypair_ed25519() {
+        let keypair = create_keypair(
+            EmailAddress::new("foo@bar.de").unwrap(),
+            KeyGenType::Ed25519,
+        )
+        .unwrap();
+        assert_eq!(
+            keypair.public.primary_key.algorithm(),
+            pgp::crypto::PublicKeyAlgorithm::EdDSA
+        );
+        assert!(!keypair.public.is_encryption_key());
+
+        let pk = select_pk_for_encryption(&keypair.public).unwrap();
+        assert_eq!(pk.algorithm(), pgp::crypto::PublicKeyAlgorithm::ECDH);
+        assert!(match pk {
+            SignedPublicKeyOrSubkey::Subkey(_) => true,
+            SignedPublicKeyOrSubkey::Key(_) => false,
+        });
+
+        // The keys survive a roundtrip through the database representation.
+        let public = Key::from(keypair.public.clone());
+        let secret = Key::from(keypair.secret.clone());
+        assert!(public.verify());
+        assert!(secret.verify());
+        assert_eq!(
+            Key::from_slice(&public.to_bytes(), crate::constants::KeyType::Public),
+            Some(public)
+        );
+    }
+
     /// [Key] objects to use in tests.
     struct TestKeys {
         alice_secret: Key,
@@ -556,4 +882,161 @@ mod tests {
         .unwrap();
         assert_eq!(plain, CLEARTEXT);
     }
+
+    /// Encrypts [CLEARTEXT] to `recipients` signed by `signer`,
+    /// decrypts it with each of `decryptors` and checks the signature with `signer_public`.
+    fn check_roundtrip(
+        recipients: &[&Key],
+        signer: &Key,
+        signer_public: &Key,
+        decryptors: &[&Key],
+    ) {
+        let mut keyring = Keyring::default();
+        for key in recipients {
+            keyring.add_ref(key);
+        }
+        let ctext = pk_encrypt(CLEARTEXT, &keyring, Some(signer)).unwrap();
+
+        for decryptor in decryptors {
+            let mut decrypt_keyring = Keyring::default();
+            decrypt_keyring.add_ref(decryptor);
+            let mut sig_check_keyring = Keyring::default();
+            sig_check_keyring.add_ref(signer_public);
+            let mut valid_signatures: HashSet<String> = Default::default();
+            let plain = pk_decrypt(
+                ctext.as_bytes(),
+                &decrypt_keyring,
+                &sig_check_keyring,
+                Some(&mut valid_signatures),
+            )
+            .unwrap();
+            assert_eq!(plain, CLEARTEXT);
+            assert_eq!(valid_signatures.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_ed25519() {
+        let carol = create_keypair(
+            EmailAddress::new("carol@example.org").unwrap(),
+            KeyGenType::Ed25519,
+        )
+        .unwrap();
+        let carol_secret = Key::from(carol.secret);
+        let carol_public = Key::from(carol.public);
+
+        // Ed25519 only.
+        check_roundtrip(
+            &[&carol_public],
+            &carol_secret,
+            &carol_public,
+            &[&carol_secret],
+        );
+
+        // Signed with Ed25519, encrypted to RSA and Curve25519.
+        check_roundtrip(
+            &[&KEYS.alice_public, &carol_public],
+            &carol_secret,
+            &carol_public,
+            &[&KEYS.alice_secret, &carol_secret],
+        );
+
+        // Signed with RSA, encrypted to RSA and Curve25519.
+        check_roundtrip(
+            &[&KEYS.alice_public, &carol_public],
+            &KEYS.alice_secret,
+            &KEYS.alice_public,
+            &[&KEYS.alice_secret, &carol_secret],
+        );
+
+        // A Curve25519 key cannot decrypt messages not encrypted to it.
+        let mut keyring = Keyring::default();
+        keyring.add_ref(&KEYS.alice_public);
+        let ctext = pk_encrypt(CLEARTEXT, &keyring, Some(&carol_secret)).unwrap();
+        let mut decrypt_keyring = Keyring::default();
+        decrypt_keyring.add_ref(&carol_secret);
+        assert!(pk_decrypt(
+            ctext.as_bytes(),
+            &decrypt_keyring,
+            &Keyring::default(),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_symm_stream_roundtrip() {
+        let plain = b"hello backup".repeat(1000);
+        let mut ctext = Vec::new();
+        symm_encrypt_stream("secret", &plain[..], &mut ctext).unwrap();
+        assert!(!ctext.windows(12).any(|w| w == b"hello backup"));
+
+        let mut decrypted = Vec::new();
+        symm_decrypt_stream("secret", &ctext[..], &mut decrypted).unwrap();
+        assert_eq!(decrypted, plain);
+
+        assert!(symm_decrypt_stream("wrong", &ctext[..], &mut Vec::new()).is_err());
+        let truncated = &ctext[..ctext.len() - 4];
+        assert!(symm_decrypt_stream("secret", truncated, &mut Vec::new()).is_err());
+
+        // empty data is encrypted to an empty last chunk
+        let mut ctext = Vec::new();
+        symm_encrypt_stream("secret", &b""[..], &mut ctext).unwrap();
+        let mut decrypted = Vec::new();
+        symm_decrypt_stream("secret", &ctext[..], &mut decrypted).unwrap();
+        assert!(decrypted.is_empty());
+    }
+
+    /// Splits data written by [SymmEncryptWriter] into its length-prefixed chunks.
+    fn split_chunks(mut ctext: &[u8]) -> Vec<Vec<u8>> {
+        let mut chunks = Vec::new();
+        while !ctext.is_empty() {
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&ctext[..4]);
+            let len = 4 + u32::from_be_bytes(len) as usize;
+            chunks.push(ctext[..len].to_vec());
+            ctext = &ctext[len..];
+        }
+        chunks
+    }
+
+    #[test]
+    fn test_symm_stream_tampering() {
+        use std::io::Write;
+
+        let encrypt = |plain: &[u8]| {
+            let mut writer = SymmEncryptWriter::with_chunk_size("secret", Vec::new(), 100);
+            writer.write_all(plain).unwrap();
+            split_chunks(&writer.finish().unwrap())
+        };
+        let decrypt = |chunks: &[&Vec<u8>]| {
+            let ctext: Vec<u8> = chunks.iter().flat_map(|c| c.iter().cloned()).collect();
+            let mut decrypted = Vec::new();
+            symm_decrypt_stream("secret", &ctext[..], &mut decrypted).map(|_| decrypted)
+        };
+
+        let plain = b"0123456789".repeat(25);
+        let chunks = encrypt(&plain);
+        assert_eq!(chunks.len(), 3);
+        let (c0, c1, c2) = (&chunks[0], &chunks[1], &chunks[2]);
+        assert_eq!(decrypt(&[c0, c1, c2]).unwrap(), plain);
+
+        // reordered, duplicated and dropped chunks
+        assert!(decrypt(&[c1, c0, c2]).is_err());
+        assert!(decrypt(&[c0, c0, c1, c2]).is_err());
+        assert!(decrypt(&[c0, c2]).is_err());
+        // truncated before the last chunk
+        assert!(decrypt(&[c0, c1]).is_err());
+        // data after the last chunk
+        assert!(decrypt(&[c0, c1, c2, c2]).is_err());
+
+        // chunks of another stream encrypted with the same passphrase
+        let other = encrypt(&plain);
+        assert!(decrypt(&[c0, &other[1], c2]).is_err());
+        // the last chunk of a shorter stream cannot end the stream early
+        let short = encrypt(b"0123456789");
+        assert_eq!(short.len(), 1);
+        assert!(decrypt(&[&short[0]]).is_ok());
+        assert!(decrypt(&[c0, &short[0]]).is_err());
+    }
 }
@@ -18,6 +18,7 @@ use reqwest::Url;
 use serde::Deserialize;
 
 const OPENPGP4FPR_SCHEME: &str = "OPENPGP4FPR:"; // yes: uppercase
+pub(crate) const INVITE_URL_PREFIX: &str = "https://i.delta.chat/#";
 const DCACCOUNT_SCHEME: &str = "DCACCOUNT:";
 const MAILTO_SCHEME: &str = "mailto:";
 const MATMSG_SCHEME: &str = "MATMSG:";
@@ -47,6 +48,8 @@ pub fn check_qr(context: &Context, qr: impl AsRef<str>) -> Lot {
 
     if qr.starts_with(OPENPGP4FPR_SCHEME) {
         decode_openpgp(context, qr)
+    } else if starts_with_ignore_case(qr, INVITE_URL_PREFIX) {
+        decode_invite_url(context, qr)
     } else if qr.starts_with(DCACCOUNT_SCHEME) {
         decode_account(context, qr)
     } else if qr.starts_with(MAILTO_SCHEME) {
@@ -185,6 +188,17 @@ fn decode_openpgp(context: &Context, qr: &str) -> Lot {
     lot
 }
 
+/// scheme: `https://i.delta.chat/#FINGERPRINT&a=ADDR&n=NAME&i=INVITENUMBER&s=AUTH`
+///     or: `https://i.delta.chat/#FINGERPRINT&a=ADDR&g=GROUPNAME&x=GROUPID&i=INVITENUMBER&s=AUTH`
+///
+/// The invite URL carries the same data as an `OPENPGP4FPR:` QR code in its fragment,
+/// so it is decoded by rewriting it to that form.
+fn decode_invite_url(context: &Context, qr: &str) -> Lot {
+    let payload = &qr[INVITE_URL_PREFIX.len()..];
+    let qr = format!("{}{}", OPENPGP4FPR_SCHEME, payload.replacen('&', "#", 1));
+    decode_openpgp(context, &qr)
+}
+
 /// scheme: `DCACCOUNT:https://example.org/new_email?t=1w_7wDjgjelxeX884x96v3`
 fn decode_account(_context: &Context, qr: &str) -> Lot {
     let payload = &qr[DCACCOUNT_SCHEME.len()..];
@@ -384,6 +398,14 @@ impl Lot {
     }
 }
 
+/// Returns true if `string` starts with the ASCII `pattern`, ignoring case.
+fn starts_with_ignore_case(string: &str, pattern: &str) -> bool {
+    string
+        .get(..pattern.len())
+        .map(|prefix| prefix.eq_ignore_ascii_case(pattern))
+        .unwrap_or_default()
+}
+
 /// URL decodes a given address, does basic email validation on the result.
 fn normalize_address(addr: &str) -> Result<String, Error> {
     // urldecoding is needed at least for OPENPGP4FPR but should not hurt in the other cases
@@ -547,6 +569,33 @@ mod tests {
         assert_eq!(contact.get_name(), "Jörn P. P.");
     }
 
+    #[test]
+    fn test_decode_invite_url() {
+        let ctx = dummy_context();
+
+        let res = check_qr(
+            &ctx.ctx,
+            "https://i.delta.chat/#79252762C34C5096AF57958F4FC3D21A81B0F0A7&a=cli%40deltachat.de&g=test%20%3F+test%20%21&x=h-0oKQf2CDK&i=9JEXlxAqGM0&s=0V7LzL9cxRL"
+        );
+        assert_eq!(res.get_state(), LotState::QrAskVerifyGroup);
+        assert_ne!(res.get_id(), 0);
+        assert_eq!(res.get_text1().unwrap(), "test ? test !");
+        assert_eq!(res.get_text2().unwrap(), "h-0oKQf2CDK");
+        assert_eq!(res.invitenumber, Some("9JEXlxAqGM0".to_string()));
+        assert_eq!(res.auth, Some("0V7LzL9cxRL".to_string()));
+
+        let res = check_qr(
+            &ctx.ctx,
+            "HTTPS://I.DELTA.CHAT/#79252762C34C5096AF57958F4FC3D21A81B0F0A7&a=cli%40deltachat.de&n=J%C3%B6rn%20P.+P.&i=TbnwJ6lSvD5&s=0ejvbdFSQxB"
+        );
+        assert_eq!(res.get_state(), LotState::QrAskVerifyContact);
+        let contact = Contact::get_by_id(&ctx.ctx, res.get_id()).unwrap();
+        assert_eq!(contact.get_addr(), "cli@deltachat.de");
+
+        let res = check_qr(&ctx.ctx, "https://i.delta.chat/#12345678901234567890");
+        assert_eq!(res.get_state(), LotState::QrError);
+    }
+
     #[test]
     fn test_decode_openpgp_without_addr() {
         let ctx = dummy_context();
@@ -8,17 +8,19 @@ use crate::config::*;
 use crate::constants::*;
 use crate::contact::*;
 use crate::context::Context;
+use crate::dc_tools::time;
 use crate::e2ee::*;
 use crate::error::Error;
 use crate::events::Event;
 use crate::headerdef::HeaderDef;
-use crate::key::{dc_normalize_fingerprint, Key};
+use crate::key::{dc_normalize_fingerprint, dc_safety_number, Key};
 use crate::lot::LotState;
 use crate::message::Message;
 use crate::mimeparser::*;
 use crate::param::*;
 use crate::peerstate::*;
-use crate::qr::check_qr;
+use crate::qr::{check_qr, INVITE_URL_PREFIX};
+use crate::sql;
 use crate::stock::StockMessage;
 use crate::token;
 
@@ -50,35 +52,38 @@ macro_rules! inviter_progress {
     };
 }
 
-macro_rules! get_qr_attr {
-    ($context:tt, $attr:ident) => {
-        $context
-            .bob
-            .read()
-            .unwrap()
-            .qr_scan
-            .as_ref()
-            .unwrap()
-            .$attr
-            .as_ref()
-            .unwrap()
-    };
+pub fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> Option<String> {
+    get_securejoin_qr(context, group_chat_id, None)
 }
 
-pub fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> Option<String> {
+/// Creates a new invite and returns the QR code text offering it.
+///
+/// Unlike the invite of [dc_get_securejoin_qr], the invite can be limited:
+/// it cannot be used after the `expires` timestamp
+/// or after `max_uses` joins; pass 0 for no limit.
+/// Active invites can be listed using [token::get_invites]
+/// and revoked using [token::revoke_invite].
+pub fn dc_create_securejoin_invite(
+    context: &Context,
+    group_chat_id: ChatId,
+    expires: i64,
+    max_uses: u32,
+) -> Option<String> {
+    get_securejoin_qr(context, group_chat_id, Some((expires, max_uses)))
+}
+
+fn get_securejoin_qr(
+    context: &Context,
+    group_chat_id: ChatId,
+    new_invite_limits: Option<(i64, u32)>,
+) -> Option<String> {
     /*=======================================================
     ====             Alice - the inviter side            ====
     ====   Step 1 in "Setup verified contact" protocol   ====
     =======================================================*/
 
-    let fingerprint: String;
-
     ensure_secret_key_exists(context).ok();
 
-    // invitenumber will be used to allow starting the handshake,
-    // auth will be used to verify the fingerprint
-    let invitenumber = token::lookup_or_new(context, token::Namespace::InviteNumber, group_chat_id);
-    let auth = token::lookup_or_new(context, token::Namespace::Auth, group_chat_id);
     let self_addr = match context.get_config(Config::ConfiguredAddr) {
         Some(addr) => addr,
         None => {
@@ -89,38 +94,62 @@ pub fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> Option<
 
     let self_name = context.get_config(Config::Displayname).unwrap_or_default();
 
-    fingerprint = match get_self_fingerprint(context) {
+    let fingerprint = match get_self_fingerprint(context) {
         Some(fp) => fp,
         None => {
             return None;
         }
     };
 
+    let group = if !group_chat_id.is_unset() {
+        if let Ok(chat) = Chat::load_from_db(context, group_chat_id) {
+            if !chat::may_manage_group(context, group_chat_id, DC_CONTACT_ID_SELF) {
+                error!(
+                    context,
+                    "Only group admins can get a QR-code for chat-id {}", group_chat_id,
+                );
+                return None;
+            }
+            Some(chat)
+        } else {
+            error!(context, "Cannot get QR-code for chat-id {}", group_chat_id,);
+            return None;
+        }
+    } else {
+        None
+    };
+
+    // invitenumber will be used to allow starting the handshake,
+    // auth will be used to verify the fingerprint;
+    // the invite is created only now, so that failed requests do not leave unused invites
+    let invite = match new_invite_limits {
+        Some((expires, max_uses)) => {
+            token::create_invite(context, group_chat_id, expires, max_uses)
+        }
+        None => token::lookup_or_new_invite(context, group_chat_id),
+    };
+    let invitenumber = invite.invitenumber;
+    let auth = invite.auth;
+
     let self_addr_urlencoded =
         utf8_percent_encode(&self_addr, NON_ALPHANUMERIC_WITHOUT_DOT).to_string();
     let self_name_urlencoded =
         utf8_percent_encode(&self_name, NON_ALPHANUMERIC_WITHOUT_DOT).to_string();
 
-    let qr = if !group_chat_id.is_unset() {
+    let qr = if let Some(chat) = group {
         // parameters used: a=g=x=i=s=
-        if let Ok(chat) = Chat::load_from_db(context, group_chat_id) {
-            let group_name = chat.get_name();
-            let group_name_urlencoded =
-                utf8_percent_encode(&group_name, NON_ALPHANUMERIC).to_string();
-
-            Some(format!(
-                "OPENPGP4FPR:{}#a={}&g={}&x={}&i={}&s={}",
-                fingerprint,
-                self_addr_urlencoded,
-                &group_name_urlencoded,
-                &chat.grpid,
-                &invitenumber,
-                &auth,
-            ))
-        } else {
-            error!(context, "Cannot get QR-code for chat-id {}", group_chat_id,);
-            return None;
-        }
+        let group_name = chat.get_name();
+        let group_name_urlencoded = utf8_percent_encode(&group_name, NON_ALPHANUMERIC).to_string();
+
+        Some(format!(
+            "OPENPGP4FPR:{}#a={}&g={}&x={}&i={}&s={}",
+            fingerprint,
+            self_addr_urlencoded,
+            &group_name_urlencoded,
+            &chat.grpid,
+            &invitenumber,
+            &auth,
+        ))
     } else {
         // parameters used: a=n=i=s=
         Some(format!(
@@ -134,6 +163,17 @@ pub fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> Option<
     qr
 }
 
+/// Returns an https invite link offering the same invitation as [dc_get_securejoin_qr].
+///
+/// The link carries the fingerprint, the invite number, the auth token
+/// and the group data in its fragment, so it is never sent to a web server;
+/// [check_qr] decodes it the same way as the QR code.
+pub fn dc_get_securejoin_invite_url(context: &Context, group_chat_id: ChatId) -> Option<String> {
+    let qr = dc_get_securejoin_qr(context, group_chat_id)?;
+    let payload = qr.trim_start_matches("OPENPGP4FPR:").replacen('#', "&", 1);
+    Some(format!("{}{}", INVITE_URL_PREFIX, payload))
+}
+
 fn get_self_fingerprint(context: &Context) -> Option<String> {
     if let Some(self_addr) = context.get_config(Config::ConfiguredAddr) {
         if let Some(key) = Key::from_self_public(context, self_addr, &context.sql) {
@@ -143,89 +183,180 @@ fn get_self_fingerprint(context: &Context) -> Option<String> {
     None
 }
 
+/// State of a join on the joiner side.
+///
+/// The state is persisted in the `bobstate` table,
+/// so an interrupted handshake resumes when the next handshake message
+/// of the inviter arrives, even after a restart.
+/// There is one state per inviter, so joins to different inviters can run in parallel.
+/// States that are finished or stuck are removed by `delete_expired_bobstates()`.
+#[derive(Debug, Clone, PartialEq)]
+struct BobState {
+    /// The inviter.
+    contact_id: u32,
+    /// The handshake message expected next from the inviter,
+    /// `DC_VC_AUTH_REQUIRED` or `DC_VC_CONTACT_CONFIRM`;
+    /// 0 if the handshake is done and the group is about to be created.
+    expects: i32,
+    fingerprint: String,
+    invitenumber: String,
+    auth: String,
+    /// Group to join, empty for Setup-Contact joins.
+    grpid: String,
+}
+
+impl BobState {
+    fn load(context: &Context, contact_id: u32) -> Option<BobState> {
+        context
+            .sql
+            .query_row(
+                "SELECT expects, fingerprint, invitenumber, auth, grpid FROM bobstate WHERE contact_id=?;",
+                params![contact_id as i32],
+                |row| {
+                    Ok(BobState {
+                        contact_id,
+                        expects: row.get(0)?,
+                        fingerprint: row.get(1)?,
+                        invitenumber: row.get(2)?,
+                        auth: row.get(3)?,
+                        grpid: row.get(4)?,
+                    })
+                },
+            )
+            .ok()
+    }
+
+    /// Loads the state of a handshake with the inviter that is still running.
+    fn load_running(context: &Context, contact_id: u32) -> Option<BobState> {
+        BobState::load(context, contact_id).filter(|state| state.expects != 0)
+    }
+
+    /// Saves the state, replacing any former join with the same inviter.
+    fn save(&self, context: &Context) -> Result<(), Error> {
+        context.sql.execute(
+            "INSERT OR REPLACE INTO bobstate (contact_id, expects, fingerprint, invitenumber, auth, grpid, timestamp) \
+             VALUES (?, ?, ?, ?, ?, ?, ?);",
+            params![
+                self.contact_id as i32,
+                self.expects,
+                self.fingerprint,
+                self.invitenumber,
+                self.auth,
+                self.grpid,
+                time()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn set_expects(&mut self, context: &Context, expects: i32) -> Result<(), Error> {
+        self.expects = expects;
+        self.save(context)
+    }
+
+    fn delete(context: &Context, contact_id: u32) {
+        sql::execute(
+            context,
+            &context.sql,
+            "DELETE FROM bobstate WHERE contact_id=?;",
+            params![contact_id as i32],
+        )
+        .ok();
+    }
+
+    fn is_group_join(&self) -> bool {
+        !self.grpid.is_empty()
+    }
+}
+
+/// Seconds after which a join that is still waiting for the inviter is given up.
+const BOBSTATE_RUNNING_TIMEOUT: i64 = 7 * 24 * 60 * 60;
+
+/// Seconds after which the state of a finished group join is removed.
+///
+/// Normally, the state is removed by `dc_join_securejoin()` once the group is created,
+/// this is only needed if the app was stopped before.
+const BOBSTATE_DONE_TIMEOUT: i64 = 60 * 60;
+
+/// Removes the states of joins that are finished or did not progress for too long.
+pub(crate) fn delete_expired_bobstates(context: &Context) {
+    let now = time();
+    sql::execute(
+        context,
+        &context.sql,
+        "DELETE FROM bobstate WHERE (expects=0 AND timestamp<?) OR timestamp<?;",
+        params![now - BOBSTATE_DONE_TIMEOUT, now - BOBSTATE_RUNNING_TIMEOUT],
+    )
+    .ok();
+}
+
+/// Aborts a running join with the given inviter, if any.
+///
+/// Messages of the inviter that arrive later are ignored.
+/// If `dc_join_securejoin()` is still waiting for a group to be created, it returns.
+/// Returns true if there was a join to abort.
+pub fn dc_abort_securejoin(context: &Context, contact_id: u32) -> bool {
+    if BobState::load(context, contact_id).is_some() {
+        info!(context, "Aborting secure-join with contact {}.", contact_id);
+        BobState::delete(context, contact_id);
+        true
+    } else {
+        false
+    }
+}
+
 /// Take a scanned QR-code and do the setup-contact/join-group handshake.
 /// See the ffi-documentation for more details.
 pub fn dc_join_securejoin(context: &Context, qr: &str) -> ChatId {
-    let cleanup =
-        |context: &Context, contact_chat_id: ChatId, ongoing_allocated: bool, join_vg: bool| {
-            let mut bob = context.bob.write().unwrap();
-            bob.expects = 0;
-            let ret_chat_id: ChatId = if bob.status == DC_BOB_SUCCESS {
-                if join_vg {
-                    chat::get_chat_id_by_grpid(
-                        context,
-                        bob.qr_scan.as_ref().unwrap().text2.as_ref().unwrap(),
-                    )
-                    .unwrap_or((ChatId::new(0), false, Blocked::Not))
-                    .0
-                } else {
-                    contact_chat_id
-                }
-            } else {
-                ChatId::new(0)
-            };
-            bob.qr_scan = None;
-
-            if ongoing_allocated {
-                context.free_ongoing();
-            }
-            ret_chat_id
-        };
-
     /*========================================================
     ====             Bob - the joiner's side             =====
     ====   Step 2 in "Setup verified contact" protocol   =====
     ========================================================*/
 
-    let mut contact_chat_id = ChatId::new(0);
-    let mut join_vg: bool = false;
-
     info!(context, "Requesting secure-join ...",);
     ensure_secret_key_exists(context).ok();
-    if !context.alloc_ongoing() {
-        return cleanup(&context, contact_chat_id, false, join_vg);
-    }
+    delete_expired_bobstates(context);
     let qr_scan = check_qr(context, &qr);
     if qr_scan.state != LotState::QrAskVerifyContact && qr_scan.state != LotState::QrAskVerifyGroup
     {
         error!(context, "Unknown QR code.",);
-        return cleanup(&context, contact_chat_id, true, join_vg);
+        return ChatId::new(0);
     }
-    contact_chat_id = match chat::create_by_contact_id(context, qr_scan.id) {
+    let contact_chat_id = match chat::create_by_contact_id(context, qr_scan.id) {
         Ok(chat_id) => chat_id,
         Err(_) => {
             error!(context, "Unknown contact.");
-            return cleanup(&context, contact_chat_id, true, join_vg);
+            return ChatId::new(0);
         }
     };
-    if context.shall_stop_ongoing() {
-        return cleanup(&context, contact_chat_id, true, join_vg);
-    }
-    join_vg = qr_scan.get_state() == LotState::QrAskVerifyGroup;
-    {
-        let mut bob = context.bob.write().unwrap();
-        bob.status = 0;
-        bob.qr_scan = Some(qr_scan);
+    let join_vg = qr_scan.get_state() == LotState::QrAskVerifyGroup;
+    let mut state = BobState {
+        contact_id: qr_scan.id,
+        expects: DC_VC_AUTH_REQUIRED,
+        fingerprint: qr_scan.fingerprint.clone().unwrap_or_default(),
+        invitenumber: qr_scan.invitenumber.clone().unwrap_or_default(),
+        auth: qr_scan.auth.clone().unwrap_or_default(),
+        grpid: if join_vg {
+            qr_scan.text2.clone().unwrap_or_default()
+        } else {
+            String::new()
+        },
+    };
+
+    // for a group-join, wait until the secure-join is done and the group is created;
+    // this can be cancelled using dc_stop_ongoing_process()
+    if join_vg && !context.alloc_ongoing() {
+        return ChatId::new(0);
     }
-    if fingerprint_equals_sender(
-        context,
-        context
-            .bob
-            .read()
-            .unwrap()
-            .qr_scan
-            .as_ref()
-            .unwrap()
-            .fingerprint
-            .as_ref()
-            .unwrap(),
-        contact_chat_id,
-    ) {
+
+    if fingerprint_equals_sender(context, &state.fingerprint, contact_chat_id) {
         // the scanned fingerprint matches Alice's key,
         // we can proceed to step 4b) directly and save two mails
         info!(context, "Taking protocol shortcut.");
-        context.bob.write().unwrap().expects = DC_VC_CONTACT_CONFIRM;
-        joiner_progress!(context, chat_id_2_contact_id(context, contact_chat_id), 400);
+        if let Err(err) = state.set_expects(context, DC_VC_CONTACT_CONFIRM) {
+            error!(context, "Failed to save secure-join state: {}", err);
+        }
+        joiner_progress!(context, state.contact_id, 400);
         let own_fingerprint = get_self_fingerprint(context).unwrap_or_default();
 
         // Bob -> Alice
@@ -237,38 +368,62 @@ pub fn dc_join_securejoin(context: &Context, qr: &str) -> ChatId {
             } else {
                 "vc-request-with-auth"
             },
-            get_qr_attr!(context, auth).to_string(),
+            &state.auth,
             Some(own_fingerprint),
-            if join_vg {
-                get_qr_attr!(context, text2).to_string()
-            } else {
-                "".to_string()
-            },
+            &state.grpid,
         );
     } else {
-        context.bob.write().unwrap().expects = DC_VC_AUTH_REQUIRED;
+        if let Err(err) = state.save(context) {
+            error!(context, "Failed to save secure-join state: {}", err);
+        }
 
         // Bob -> Alice
         send_handshake_msg(
             context,
             contact_chat_id,
             if join_vg { "vg-request" } else { "vc-request" },
-            get_qr_attr!(context, invitenumber),
+            &state.invitenumber,
             None,
             "",
         );
     }
 
     if join_vg {
-        // for a group-join, wait until the secure-join is done and the group is created
+        let group_chat_id = || {
+            chat::get_chat_id_by_grpid(context, &state.grpid)
+                .ok()
+                .map(|(chat_id, _, _)| chat_id)
+                .filter(|chat_id| chat::is_contact_in_chat(context, *chat_id, DC_CONTACT_ID_SELF))
+        };
+        let mut ret_chat_id = ChatId::new(0);
         while !context.shall_stop_ongoing() {
+            match BobState::load(context, state.contact_id) {
+                // the handshake failed
+                None => break,
+                // replaced by another join with the same inviter
+                Some(current) if current.grpid != state.grpid => break,
+                Some(current) if current.expects == 0 => {
+                    if let Some(chat_id) = group_chat_id() {
+                        ret_chat_id = chat_id;
+                        break;
+                    }
+                }
+                Some(_) => {}
+            }
             std::thread::sleep(std::time::Duration::from_millis(200));
         }
-        cleanup(&context, contact_chat_id, true, join_vg)
+        // the join is done, failed or was cancelled
+        if BobState::load(context, state.contact_id)
+            .map(|current| current.grpid == state.grpid)
+            .unwrap_or_default()
+        {
+            BobState::delete(context, state.contact_id);
+        }
+        context.free_ongoing();
+        ret_chat_id
     } else {
         // for a one-to-one-chat, the chat is already known, return the chat-id,
         // the verification runs in background
-        context.free_ongoing();
         contact_chat_id
     }
 }
@@ -376,9 +531,8 @@ pub(crate) enum HandshakeMessage {
 
 /// Handle incoming secure-join handshake.
 ///
-/// This function will update the securejoin state in [Context::bob]
-/// and also terminate the ongoing process using
-/// [Context::stop_ongoing] as required by the protocol.
+/// On the joiner side, this function updates the persisted state
+/// of the join with the sender of the message as required by the protocol.
 ///
 /// A message which results in [Err] will be hidden from the user but
 /// not deleted, it may be a valid message for something else we are
@@ -443,9 +597,24 @@ pub(crate) fn handle_securejoin_handshake(
                     return Ok(HandshakeMessage::Ignore);
                 }
             };
-            if !token::exists(context, token::Namespace::InviteNumber, &invitenumber) {
-                warn!(context, "Secure-join denied (bad invitenumber).");
-                return Ok(HandshakeMessage::Ignore);
+            match token::get_invite_state(context, token::Namespace::InviteNumber, &invitenumber) {
+                token::InviteState::Active => {}
+                token::InviteState::Unknown => {
+                    warn!(context, "Secure-join denied (bad invitenumber).");
+                    return Ok(HandshakeMessage::Ignore);
+                }
+                token::InviteState::Inactive => {
+                    warn!(context, "Secure-join denied (invite expired or revoked).");
+                    send_handshake_msg(
+                        context,
+                        contact_chat_id,
+                        &format!("{}-invite-invalid", &step[..2]),
+                        "",
+                        None,
+                        "",
+                    );
+                    return Ok(HandshakeMessage::Done);
+                }
             }
             info!(context, "Secure-join requested.",);
 
@@ -469,21 +638,19 @@ pub(crate) fn handle_securejoin_handshake(
             ========================================================*/
 
             // verify that Alice's Autocrypt key and fingerprint matches the QR-code
-            let cond = {
-                let bob = context.bob.read().unwrap();
-                let scan = bob.qr_scan.as_ref();
-                scan.is_none()
-                    || bob.expects != DC_VC_AUTH_REQUIRED
-                    || join_vg && scan.unwrap().state != LotState::QrAskVerifyGroup
+            let mut state = match BobState::load_running(context, contact_id) {
+                Some(state)
+                    if state.expects == DC_VC_AUTH_REQUIRED && join_vg == state.is_group_join() =>
+                {
+                    state
+                }
+                _ => {
+                    warn!(context, "auth-required message out of sync.");
+                    // no error, just aborted somehow or a mail from another handshake
+                    return Ok(HandshakeMessage::Ignore);
+                }
             };
-
-            if cond {
-                warn!(context, "auth-required message out of sync.");
-                // no error, just aborted somehow or a mail from another handshake
-                return Ok(HandshakeMessage::Ignore);
-            }
-            let scanned_fingerprint_of_alice = get_qr_attr!(context, fingerprint).to_string();
-            let auth = get_qr_attr!(context, auth).to_string();
+            let scanned_fingerprint_of_alice = state.fingerprint.clone();
 
             if !encrypted_and_signed(context, mime_message, &scanned_fingerprint_of_alice) {
                 could_not_establish_secure_connection(
@@ -495,8 +662,7 @@ pub(crate) fn handle_securejoin_handshake(
                         "Not encrypted."
                     },
                 );
-                context.bob.write().unwrap().status = 0; // secure-join failed
-                context.stop_ongoing();
+                BobState::delete(context, contact_id); // secure-join failed
                 return Ok(HandshakeMessage::Ignore);
             }
             if !fingerprint_equals_sender(context, &scanned_fingerprint_of_alice, contact_chat_id) {
@@ -505,27 +671,24 @@ pub(crate) fn handle_securejoin_handshake(
                     contact_chat_id,
                     "Fingerprint mismatch on joiner-side.",
                 );
-                context.bob.write().unwrap().status = 0; // secure-join failed
-                context.stop_ongoing();
+                BobState::delete(context, contact_id); // secure-join failed
                 return Ok(HandshakeMessage::Ignore);
             }
             info!(context, "Fingerprint verified.",);
             own_fingerprint = get_self_fingerprint(context).unwrap();
             joiner_progress!(context, contact_id, 400);
-            context.bob.write().unwrap().expects = DC_VC_CONTACT_CONFIRM;
+            if let Err(err) = state.set_expects(context, DC_VC_CONTACT_CONFIRM) {
+                error!(context, "Failed to save secure-join state: {}", err);
+            }
 
             // Bob -> Alice
             send_handshake_msg(
                 context,
                 contact_chat_id,
                 &format!("{}-request-with-auth", &step[..2]),
-                auth,
+                &state.auth,
                 Some(own_fingerprint),
-                if join_vg {
-                    get_qr_attr!(context, text2).to_string()
-                } else {
-                    "".to_string()
-                },
+                &state.grpid,
             );
             Ok(HandshakeMessage::Done)
         }
@@ -577,8 +740,50 @@ pub(crate) fn handle_securejoin_handshake(
                     return Ok(HandshakeMessage::Ignore);
                 }
             };
-            if !token::exists(context, token::Namespace::Auth, &auth_0) {
-                could_not_establish_secure_connection(context, contact_chat_id, "Auth invalid.");
+            match token::get_invite_state(context, token::Namespace::Auth, &auth_0) {
+                token::InviteState::Active => {}
+                token::InviteState::Unknown => {
+                    could_not_establish_secure_connection(
+                        context,
+                        contact_chat_id,
+                        "Auth invalid.",
+                    );
+                    return Ok(HandshakeMessage::Ignore);
+                }
+                token::InviteState::Inactive => {
+                    could_not_establish_secure_connection(
+                        context,
+                        contact_chat_id,
+                        "Invite expired or revoked.",
+                    );
+                    send_handshake_msg(
+                        context,
+                        contact_chat_id,
+                        &format!("{}-invite-invalid", &step[..2]),
+                        "",
+                        None,
+                        "",
+                    );
+                    return Ok(HandshakeMessage::Ignore);
+                }
+            }
+            // the state may have changed by a join handled at the same time,
+            // counting the use checks the limits again
+            if let Err(err) = token::count_invite_use(context, &auth_0) {
+                warn!(context, "Secure-join denied: {}", err);
+                could_not_establish_secure_connection(
+                    context,
+                    contact_chat_id,
+                    "Invite expired or revoked.",
+                );
+                send_handshake_msg(
+                    context,
+                    contact_chat_id,
+                    &format!("{}-invite-invalid", &step[..2]),
+                    "",
+                    None,
+                    "",
+                );
                 return Ok(HandshakeMessage::Ignore);
             }
             if mark_peer_as_verified(context, fingerprint).is_err() {
@@ -591,6 +796,9 @@ pub(crate) fn handle_securejoin_handshake(
             }
             Contact::scaleup_origin_by_id(context, contact_id, Origin::SecurejoinInvited);
             info!(context, "Auth verified.",);
+            if !join_vg {
+                set_contact_chat_verified(context, contact_chat_id);
+            }
             secure_connection_established(context, contact_chat_id);
             emit_event!(context, Event::ContactsChanged(Some(contact_id)));
             inviter_progress!(context, contact_id, 600);
@@ -638,32 +846,30 @@ pub(crate) fn handle_securejoin_handshake(
                 HandshakeMessage::Ignore
             };
 
-            if context.bob.read().unwrap().expects != DC_VC_CONTACT_CONFIRM {
-                info!(context, "Message belongs to a different handshake.",);
-                return Ok(abort_retval);
-            }
-            let cond = {
-                let bob = context.bob.read().unwrap();
-                let scan = bob.qr_scan.as_ref();
-                scan.is_none() || (join_vg && scan.unwrap().state != LotState::QrAskVerifyGroup)
+            let mut state = match BobState::load_running(context, contact_id) {
+                Some(state) if state.expects == DC_VC_CONTACT_CONFIRM => state,
+                _ => {
+                    info!(context, "Message belongs to a different handshake.",);
+                    return Ok(abort_retval);
+                }
             };
-            if cond {
+            if join_vg != state.is_group_join() {
                 warn!(
                     context,
                     "Message out of sync or belongs to a different handshake.",
                 );
                 return Ok(abort_retval);
             }
-            let scanned_fingerprint_of_alice = get_qr_attr!(context, fingerprint).to_string();
+            let scanned_fingerprint_of_alice = state.fingerprint.clone();
 
             let vg_expect_encrypted = if join_vg {
-                let group_id = get_qr_attr!(context, text2).to_string();
+                let group_id = &state.grpid;
                 // This is buggy, is_verified_group will always be
                 // false since the group is created by receive_imf by
                 // the very handshake message we're handling now.  But
                 // only after we have returned.  It does not impact
                 // the security invariants of secure-join however.
-                let (_, is_verified_group, _) = chat::get_chat_id_by_grpid(context, &group_id)
+                let (_, is_verified_group, _) = chat::get_chat_id_by_grpid(context, group_id)
                     .unwrap_or((ChatId::new(0), false, Blocked::Not));
                 // when joining a non-verified group
                 // the vg-member-added message may be unencrypted
@@ -682,7 +888,7 @@ pub(crate) fn handle_securejoin_handshake(
                     contact_chat_id,
                     "Contact confirm message not encrypted.",
                 );
-                context.bob.write().unwrap().status = 0;
+                BobState::delete(context, contact_id); // secure-join failed
                 return Ok(abort_retval);
             }
 
@@ -708,8 +914,18 @@ pub(crate) fn handle_securejoin_handshake(
                 info!(context, "Message belongs to a different handshake (scaled up contact anyway to allow creation of group).");
                 return Ok(abort_retval);
             }
+            if !join_vg {
+                set_contact_chat_verified(context, contact_chat_id);
+            }
             secure_connection_established(context, contact_chat_id);
-            context.bob.write().unwrap().expects = 0;
+            if join_vg {
+                // keep the state until the group is created, see dc_join_securejoin()
+                if let Err(err) = state.set_expects(context, 0) {
+                    error!(context, "Failed to save secure-join state: {}", err);
+                }
+            } else {
+                BobState::delete(context, contact_id);
+            }
             if join_vg {
                 // Bob -> Alice
                 send_handshake_msg(
@@ -721,8 +937,6 @@ pub(crate) fn handle_securejoin_handshake(
                     "",
                 );
             }
-            context.bob.write().unwrap().status = 1;
-            context.stop_ongoing();
             Ok(if join_vg {
                 HandshakeMessage::Propagate
             } else {
@@ -763,6 +977,35 @@ pub(crate) fn handle_securejoin_handshake(
                 Ok(HandshakeMessage::Ignore)
             }
         }
+        "vg-invite-invalid" | "vc-invite-invalid" => {
+            /*=======================================================
+            ====             Bob - the joiner's side             ====
+            ====   the inviter rejected an expired or revoked    ====
+            ====   invite in step 3 or 5                         ====
+            =======================================================*/
+            let state = match BobState::load_running(context, contact_id) {
+                Some(state) if join_vg == state.is_group_join() => state,
+                _ => {
+                    warn!(context, "invite-invalid message out of sync.");
+                    return Ok(HandshakeMessage::Ignore);
+                }
+            };
+            if !encrypted_and_signed(context, mime_message, &state.fingerprint) {
+                warn!(context, "invite-invalid message not encrypted and signed.");
+                return Ok(HandshakeMessage::Ignore);
+            }
+            let addr = Contact::get_by_id(context, contact_id)
+                .map(|contact| contact.get_addr().to_string())
+                .unwrap_or_default();
+            chat::add_info_msg(
+                context,
+                contact_chat_id,
+                context.stock_string_repl_str(StockMessage::SecurejoinInviteInvalid, addr),
+            );
+            error!(context, "Secure-join failed (invite expired or revoked).");
+            BobState::delete(context, contact_id); // secure-join failed
+            Ok(HandshakeMessage::Done)
+        }
         _ => {
             warn!(context, "invalid step: {}", step);
             Ok(HandshakeMessage::Ignore)
@@ -783,6 +1026,12 @@ fn secure_connection_established(context: &Context, contact_chat_id: ChatId) {
     emit_event!(context, Event::ChatModified(contact_chat_id));
 }
 
+fn set_contact_chat_verified(context: &Context, contact_chat_id: ChatId) {
+    if let Err(err) = chat::set_chat_verified(context, contact_chat_id, true) {
+        warn!(context, "Cannot mark chat as verified: {}", err);
+    }
+}
+
 fn could_not_establish_secure_connection(
     context: &Context,
     contact_chat_id: ChatId,
@@ -826,6 +1075,100 @@ fn mark_peer_as_verified(context: &Context, fingerprint: impl AsRef<str>) -> Res
     );
 }
 
+/* ******************************************************************************
+ * Safety numbers
+ ******************************************************************************/
+
+/// Returns the own key and the key of the contact used for encryption.
+fn get_safety_number_keys(context: &Context, contact_id: u32) -> Result<(Key, Peerstate), Error> {
+    ensure!(
+        contact_id > DC_CONTACT_ID_LAST_SPECIAL,
+        "Cannot get safety number of special contact {}",
+        contact_id
+    );
+    let self_addr = ensure_secret_key_exists(context)?;
+    let self_key = Key::from_self_public(context, &self_addr, &context.sql)
+        .ok_or_else(|| format_err!("Own key not found"))?;
+    let contact = Contact::get_by_id(context, contact_id)?;
+    let peerstate = Peerstate::from_addr(context, &context.sql, contact.get_addr())
+        .filter(|peerstate| {
+            peerstate
+                .peek_key(PeerstateVerifiedStatus::Unverified)
+                .is_some()
+        })
+        .ok_or_else(|| format_err!("No key known for {}", contact.get_addr()))?;
+    Ok((self_key, peerstate))
+}
+
+/// Returns the safety number of the own key and the key of the contact.
+///
+/// The safety number is the same on both sides,
+/// so it can be compared out-of-band, eg. by reading it out in a phone call,
+/// if scanning a QR code is not possible.
+/// If the numbers match, the contact can be verified using `confirm_safety_number()`.
+pub fn get_safety_number(context: &Context, contact_id: u32) -> Result<String, Error> {
+    let (self_key, peerstate) = get_safety_number_keys(context, contact_id)?;
+    let peer_key = peerstate
+        .peek_key(PeerstateVerifiedStatus::Unverified)
+        .unwrap();
+    Ok(dc_safety_number(
+        &self_key.fingerprint(),
+        &peer_key.fingerprint(),
+    ))
+}
+
+/// Marks the contact as verified after the user confirmed
+/// that the safety numbers on both sides match.
+///
+/// `safety_number` is the number shown to the user by `get_safety_number()`;
+/// if the keys were changed in between, the contact is not verified.
+pub fn confirm_safety_number(
+    context: &Context,
+    contact_id: u32,
+    safety_number: &str,
+) -> Result<(), Error> {
+    let (self_key, mut peerstate) = get_safety_number_keys(context, contact_id)?;
+    let peer_key = peerstate
+        .peek_key(PeerstateVerifiedStatus::Unverified)
+        .unwrap();
+    let fingerprint = peer_key.fingerprint();
+    let expected = dc_safety_number(&self_key.fingerprint(), &fingerprint);
+    ensure!(
+        normalize_safety_number(safety_number) == normalize_safety_number(&expected),
+        "Safety number does not match, the keys may have changed"
+    );
+
+    let which_key = if peerstate.public_key_fingerprint.as_ref() == Some(&fingerprint) {
+        PeerstateKeyType::PublicKey
+    } else {
+        PeerstateKeyType::GossipKey
+    };
+    ensure!(
+        peerstate.set_verified(
+            which_key,
+            &fingerprint,
+            PeerstateVerifiedStatus::BidirectVerified
+        ),
+        "Could not mark peer as verified for fingerprint {}",
+        fingerprint
+    );
+    peerstate.prefer_encrypt = EncryptPreference::Mutual;
+    peerstate.save_to_db(&context.sql, false)?;
+
+    if let Ok((contact_chat_id, _)) = chat::lookup_by_contact_id(context, contact_id) {
+        if !contact_chat_id.is_unset() {
+            set_contact_chat_verified(context, contact_chat_id);
+            secure_connection_established(context, contact_chat_id);
+        }
+    }
+    context.call_cb(Event::ContactsChanged(Some(contact_id)));
+    Ok(())
+}
+
+fn normalize_safety_number(safety_number: &str) -> String {
+    safety_number.chars().filter(char::is_ascii_digit).collect()
+}
+
 /* ******************************************************************************
  * Tools: Misc.
  ******************************************************************************/
@@ -886,8 +1229,330 @@ pub fn handle_degrade_event(context: &Context, peerstate: &Peerstate) -> Result<
                 .stock_string_repl_str(StockMessage::ContactSetupChanged, peerstate.addr.clone());
 
             chat::add_info_msg(context, contact_chat_id, msg);
+
+            // the new key is not verified, so the one-to-one chat cannot stay verified
+            let chat_verified = Chat::load_from_db(context, contact_chat_id)
+                .map(|chat| chat.is_verified())
+                .unwrap_or_default();
+            if chat_verified {
+                chat::set_chat_verified(context, contact_chat_id, false)?;
+                let msg = context
+                    .stock_string_repl_str(StockMessage::ChatVerificationLost, &peerstate.addr);
+                chat::add_info_msg(context, contact_chat_id, msg);
+            }
             emit_event!(context, Event::ChatModified(contact_chat_id));
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chat::create_group_chat;
+    use crate::dc_receive_imf::dc_receive_imf;
+    use crate::message::MsgId;
+    use crate::mimefactory::MimeFactory;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_parallel_joins_persisted() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+
+        let qr1 = "OPENPGP4FPR:79252762C34C5096AF57958F4FC3D21A81B0F0A7#a=cli%40deltachat.de&n=cli&i=TbnwJ6lSvD5&s=0ejvbdFSQxB";
+        let qr2 = "OPENPGP4FPR:1234567890123456789012345678901234567890#a=bob%40example.net&n=bob&i=9JEXlxAqGM0&s=0V7LzL9cxRL";
+        let chat_id1 = dc_join_securejoin(&t.ctx, qr1);
+        let chat_id2 = dc_join_securejoin(&t.ctx, qr2);
+        assert!(!chat_id1.is_unset());
+        assert!(!chat_id2.is_unset());
+        assert_ne!(chat_id1, chat_id2);
+
+        let contact_id1 = Contact::lookup_id_by_addr(&t.ctx, "cli@deltachat.de");
+        let contact_id2 = Contact::lookup_id_by_addr(&t.ctx, "bob@example.net");
+        let state1 = BobState::load_running(&t.ctx, contact_id1).unwrap();
+        assert_eq!(state1.expects, DC_VC_AUTH_REQUIRED);
+        assert_eq!(
+            state1.fingerprint,
+            "79252762C34C5096AF57958F4FC3D21A81B0F0A7"
+        );
+        assert_eq!(state1.invitenumber, "TbnwJ6lSvD5");
+        assert_eq!(state1.auth, "0ejvbdFSQxB");
+        assert!(!state1.is_group_join());
+        let state2 = BobState::load_running(&t.ctx, contact_id2).unwrap();
+        assert_eq!(state2.auth, "0V7LzL9cxRL");
+
+        // a new join with the same inviter replaces the former one
+        let qr3 = "OPENPGP4FPR:79252762C34C5096AF57958F4FC3D21A81B0F0A7#a=cli%40deltachat.de&n=cli&i=Ux8GrQ5lBfZ&s=7hQ6FqMbXe1";
+        dc_join_securejoin(&t.ctx, qr3);
+        let state1 = BobState::load_running(&t.ctx, contact_id1).unwrap();
+        assert_eq!(state1.invitenumber, "Ux8GrQ5lBfZ");
+        assert_eq!(BobState::load_running(&t.ctx, contact_id2), Some(state2));
+
+        assert!(dc_abort_securejoin(&t.ctx, contact_id1));
+        assert!(!dc_abort_securejoin(&t.ctx, contact_id1));
+        assert!(BobState::load(&t.ctx, contact_id1).is_none());
+        assert!(BobState::load(&t.ctx, contact_id2).is_some());
+    }
+
+    /// Renders the last handshake message sent from the context.
+    fn render_last_handshake_msg(context: &Context) -> (String, Vec<u8>) {
+        let msg_id: u32 = context
+            .sql
+            .query_get_value(
+                context,
+                "SELECT MAX(id) FROM msgs WHERE hidden=1;",
+                params![],
+            )
+            .unwrap();
+        let msg = Message::load_from_db(context, MsgId::new(msg_id)).unwrap();
+        let step = msg.param.get(Param::Arg).unwrap_or_default().to_string();
+        let rendered = MimeFactory::from_msg(context, &msg, false)
+            .unwrap()
+            .render()
+            .unwrap();
+        (step, rendered.message)
+    }
+
+    #[test]
+    fn test_used_up_invite_rejected() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+        configure_bob_keypair(&bob.ctx);
+
+        let qr = dc_create_securejoin_invite(&alice.ctx, ChatId::new(0), 0, 1).unwrap();
+        let invite = token::get_invites(&alice.ctx, ChatId::new(0)).remove(0);
+        dc_join_securejoin(&bob.ctx, &qr);
+
+        let (step, msg) = render_last_handshake_msg(&bob.ctx);
+        assert_eq!(step, "vc-request");
+        dc_receive_imf(&alice.ctx, &msg, "INBOX", 1, false).unwrap();
+        let (step, msg) = render_last_handshake_msg(&alice.ctx);
+        assert_eq!(step, "vc-auth-required");
+        dc_receive_imf(&bob.ctx, &msg, "INBOX", 1, false).unwrap();
+        let (step, msg) = render_last_handshake_msg(&bob.ctx);
+        assert_eq!(step, "vc-request-with-auth");
+
+        // meanwhile, another join used up the invite
+        token::count_invite_use(&alice.ctx, &invite.auth).unwrap();
+
+        dc_receive_imf(&alice.ctx, &msg, "INBOX", 2, false).unwrap();
+        let (step, _) = render_last_handshake_msg(&alice.ctx);
+        assert_eq!(step, "vc-invite-invalid");
+        let bob_id = Contact::lookup_id_by_addr(&alice.ctx, "bob@example.net");
+        let contact = Contact::get_by_id(&alice.ctx, bob_id).unwrap();
+        assert_eq!(contact.is_verified(&alice.ctx), VerifiedStatus::Unverified);
+    }
+
+    #[test]
+    fn test_expired_invite_rejected() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+        configure_bob_keypair(&bob.ctx);
+
+        let qr = dc_create_securejoin_invite(&alice.ctx, ChatId::new(0), 0, 0).unwrap();
+        let invite = token::get_invites(&alice.ctx, ChatId::new(0)).remove(0);
+        token::revoke_invite(&alice.ctx, &invite.invitenumber).unwrap();
+        dc_join_securejoin(&bob.ctx, &qr);
+
+        let (step, msg) = render_last_handshake_msg(&bob.ctx);
+        assert_eq!(step, "vc-request");
+        dc_receive_imf(&alice.ctx, &msg, "INBOX", 1, false).unwrap();
+        let (step, _) = render_last_handshake_msg(&alice.ctx);
+        assert_eq!(step, "vc-invite-invalid");
+    }
+
+    #[test]
+    fn test_failed_qr_creates_no_invite() {
+        // not configured
+        let t = dummy_context();
+        assert!(dc_create_securejoin_invite(&t.ctx, ChatId::new(0), 0, 1).is_none());
+        assert!(token::get_invites(&t.ctx, ChatId::new(0)).is_empty());
+    }
+
+    #[test]
+    fn test_join_resumes_after_restart() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+        configure_bob_keypair(&bob.ctx);
+
+        let qr = dc_get_securejoin_qr(&alice.ctx, ChatId::new(0)).unwrap();
+        assert!(!dc_join_securejoin(&bob.ctx, &qr).is_unset());
+
+        // Alice answers the vc-request, meanwhile Bob's app is restarted
+        let bob_id = add_peer(&alice.ctx, &bob_keypair());
+        let alice_chat_id = chat::create_by_contact_id(&alice.ctx, bob_id).unwrap();
+        send_handshake_msg(&alice.ctx, alice_chat_id, "vc-auth-required", "", None, "");
+        let (_, msg) = render_last_handshake_msg(&alice.ctx);
+
+        let TestContext { ctx, dir } = bob;
+        drop(ctx);
+        let bob = Context::new(
+            Box::new(|_, _| ()),
+            "FakeOs".into(),
+            dir.path().join("db.sqlite"),
+        )
+        .unwrap();
+
+        dc_receive_imf(&bob, &msg, "INBOX", 1, false).unwrap();
+        let alice_id = Contact::lookup_id_by_addr(&bob, "alice@example.com");
+        let state = BobState::load_running(&bob, alice_id).unwrap();
+        assert_eq!(state.expects, DC_VC_CONTACT_CONFIRM);
+    }
+
+    #[test]
+    fn test_abort_join() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+        configure_bob_keypair(&bob.ctx);
+
+        let qr = dc_get_securejoin_qr(&alice.ctx, ChatId::new(0)).unwrap();
+        assert!(!dc_join_securejoin(&bob.ctx, &qr).is_unset());
+        let alice_id = Contact::lookup_id_by_addr(&bob.ctx, "alice@example.com");
+        assert!(dc_abort_securejoin(&bob.ctx, alice_id));
+        assert!(BobState::load(&bob.ctx, alice_id).is_none());
+
+        // the answer of Alice does not revive the join
+        let bob_id = add_peer(&alice.ctx, &bob_keypair());
+        let alice_chat_id = chat::create_by_contact_id(&alice.ctx, bob_id).unwrap();
+        send_handshake_msg(&alice.ctx, alice_chat_id, "vc-auth-required", "", None, "");
+        let (_, msg) = render_last_handshake_msg(&alice.ctx);
+        let sent_handshake_msgs = |context: &Context| -> i32 {
+            context
+                .sql
+                .query_get_value(
+                    context,
+                    "SELECT COUNT(*) FROM msgs WHERE hidden=1 AND from_id=?;",
+                    params![DC_CONTACT_ID_SELF as i32],
+                )
+                .unwrap()
+        };
+        assert_eq!(sent_handshake_msgs(&bob.ctx), 1);
+
+        dc_receive_imf(&bob.ctx, &msg, "INBOX", 1, false).unwrap();
+        assert!(BobState::load(&bob.ctx, alice_id).is_none());
+        assert_eq!(sent_handshake_msgs(&bob.ctx), 1);
+    }
+
+    #[test]
+    fn test_delete_expired_bobstates() {
+        let t = dummy_context();
+        let now = time();
+        for &(contact_id, expects, age) in &[
+            (10, DC_VC_AUTH_REQUIRED, 0),
+            (11, DC_VC_CONTACT_CONFIRM, BOBSTATE_RUNNING_TIMEOUT + 60),
+            (12, 0, BOBSTATE_DONE_TIMEOUT + 60),
+            (13, 0, 0),
+        ] {
+            let state = BobState {
+                contact_id,
+                expects,
+                fingerprint: String::new(),
+                invitenumber: String::new(),
+                auth: String::new(),
+                grpid: "grp".to_string(),
+            };
+            state.save(&t.ctx).unwrap();
+            t.ctx
+                .sql
+                .execute(
+                    "UPDATE bobstate SET timestamp=? WHERE contact_id=?;",
+                    params![now - age, contact_id as i32],
+                )
+                .unwrap();
+        }
+
+        delete_expired_bobstates(&t.ctx);
+        assert!(BobState::load(&t.ctx, 10).is_some());
+        assert!(BobState::load(&t.ctx, 11).is_none());
+        assert!(BobState::load(&t.ctx, 12).is_none());
+        assert!(BobState::load(&t.ctx, 13).is_some());
+    }
+
+    fn add_peer(context: &Context, keypair: &crate::key::KeyPair) -> u32 {
+        let mut peerstate = Peerstate::new(context, keypair.addr.to_string());
+        let key = Key::from(keypair.public.clone());
+        peerstate.public_key_fingerprint = Some(key.fingerprint());
+        peerstate.public_key = Some(key);
+        peerstate.save_to_db(&context.sql, true).unwrap();
+        Contact::create(context, "", keypair.addr.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_safety_number() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+        let bob_keypair = bob_keypair();
+        bob.ctx
+            .set_config(Config::ConfiguredAddr, Some(&bob_keypair.addr.to_string()))
+            .unwrap();
+        crate::key::store_self_keypair(&bob.ctx, &bob_keypair, crate::key::KeyPairUse::Default)
+            .unwrap();
+
+        let bob_id = add_peer(&alice.ctx, &bob_keypair);
+        let alice_id = add_peer(&bob.ctx, &alice_keypair());
+        let number = get_safety_number(&alice.ctx, bob_id).unwrap();
+        assert_eq!(number, get_safety_number(&bob.ctx, alice_id).unwrap());
+        assert!(Contact::get_encrinfo(&alice.ctx, bob_id)
+            .unwrap()
+            .contains(&number));
+
+        let chat_id = chat::create_by_contact_id(&alice.ctx, bob_id).unwrap();
+        assert!(confirm_safety_number(&alice.ctx, bob_id, "12345").is_err());
+        let contact = Contact::get_by_id(&alice.ctx, bob_id).unwrap();
+        assert_eq!(contact.is_verified(&alice.ctx), VerifiedStatus::Unverified);
+
+        // the number may be entered without spaces
+        confirm_safety_number(&alice.ctx, bob_id, &number.replace(char::is_whitespace, ""))
+            .unwrap();
+        let contact = Contact::get_by_id(&alice.ctx, bob_id).unwrap();
+        assert_eq!(
+            contact.is_verified(&alice.ctx),
+            VerifiedStatus::BidirectVerified
+        );
+        assert!(Chat::load_from_db(&alice.ctx, chat_id)
+            .unwrap()
+            .is_verified());
+    }
+
+    #[test]
+    fn test_invite_url_roundtrip() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        let bob = dummy_context();
+
+        for chat_id in &[
+            ChatId::new(0),
+            create_group_chat(&alice.ctx, VerifiedStatus::Unverified, "foo & bar?").unwrap(),
+        ] {
+            let qr = dc_get_securejoin_qr(&alice.ctx, *chat_id).unwrap();
+            let url = dc_get_securejoin_invite_url(&alice.ctx, *chat_id).unwrap();
+            assert!(url.starts_with("https://i.delta.chat/#"));
+
+            let qr_lot = check_qr(&bob.ctx, &qr);
+            let url_lot = check_qr(&bob.ctx, &url);
+            assert_eq!(url_lot.get_state(), qr_lot.get_state());
+            assert_eq!(url_lot.get_id(), qr_lot.get_id());
+            assert_eq!(url_lot.get_text1(), qr_lot.get_text1());
+            assert_eq!(url_lot.get_text2(), qr_lot.get_text2());
+            assert_eq!(url_lot.fingerprint, qr_lot.fingerprint);
+            assert_eq!(url_lot.invitenumber, qr_lot.invitenumber);
+            assert_eq!(url_lot.auth, qr_lot.auth);
+        }
+
+        let chat_id =
+            create_group_chat(&alice.ctx, VerifiedStatus::Unverified, "foo & bar?").unwrap();
+        let url = dc_get_securejoin_invite_url(&alice.ctx, chat_id).unwrap();
+        let lot = check_qr(&bob.ctx, &url);
+        assert_eq!(lot.get_state(), LotState::QrAskVerifyGroup);
+        assert_eq!(lot.get_text1(), Some("foo & bar?"));
+        let contact = Contact::get_by_id(&bob.ctx, lot.get_id()).unwrap();
+        assert_eq!(contact.get_addr(), "alice@example.com");
+    }
+}
@@ -13,6 +13,7 @@ use crate::context::Context;
 use crate::dc_tools::*;
 use crate::param::*;
 use crate::peerstate::*;
+use crate::securejoin;
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -64,6 +65,9 @@ pub struct Sql {
     pool: RwLock<Option<r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>>>,
     #[debug_stub = "ThreadLocal<String>"]
     in_use: Arc<ThreadLocal<String>>,
+    /// Passphrase the database is encrypted with, requires the `sqlcipher` feature.
+    #[debug_stub = "RwLock<Option<String>>"]
+    passphrase: RwLock<Option<String>>,
 }
 
 impl Default for Sql {
@@ -71,6 +75,7 @@ impl Default for Sql {
         Self {
             pool: RwLock::new(None),
             in_use: Arc::new(ThreadLocal::new()),
+            passphrase: RwLock::new(None),
         }
     }
 }
@@ -84,6 +89,73 @@ impl Sql {
         self.pool.read().unwrap().is_some()
     }
 
+    /// Sets the passphrase used by the next call to open().
+    pub fn set_passphrase(&self, passphrase: Option<String>) {
+        *self.passphrase.write().unwrap() = passphrase;
+    }
+
+    pub fn get_passphrase(&self) -> Option<String> {
+        self.passphrase.read().unwrap().clone()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.passphrase.read().unwrap().is_some()
+    }
+
+    /// Re-encrypts the opened database `dbfile` with a new passphrase.
+    ///
+    /// If `passphrase` is `None`, the database is decrypted.
+    /// The database is exported to a new file using `sqlcipher_export()`
+    /// which then replaces `dbfile`, this works for all combinations of
+    /// encrypted and unencrypted databases, unlike `PRAGMA rekey`.
+    pub fn change_passphrase(
+        &self,
+        context: &Context,
+        dbfile: &std::path::Path,
+        passphrase: Option<String>,
+    ) -> crate::error::Result<()> {
+        ensure!(
+            cfg!(feature = "sqlcipher"),
+            "Database encryption is not supported, compile with the sqlcipher feature"
+        );
+        ensure!(self.is_open(), "Database not opened");
+
+        let mut tmp_name = dbfile.as_os_str().to_owned();
+        tmp_name.push("-rekey");
+        let tmp_file = std::path::PathBuf::from(tmp_name);
+        if tmp_file.exists() {
+            std::fs::remove_file(&tmp_file)?;
+        }
+
+        let res = self.with_conn(|conn| {
+            conn.execute(
+                "ATTACH DATABASE ? AS rekeyed KEY ?;",
+                params![
+                    tmp_file.to_string_lossy(),
+                    passphrase.clone().unwrap_or_default()
+                ],
+            )?;
+            let res = conn.query_row("SELECT sqlcipher_export('rekeyed');", NO_PARAMS, |_| Ok(()));
+            conn.execute("DETACH DATABASE rekeyed;", NO_PARAMS)?;
+            res.map_err(Into::into)
+        });
+        if let Err(err) = res {
+            error!(context, "Cannot change database passphrase: {}", err);
+            std::fs::remove_file(&tmp_file).ok();
+            return Err(err.into());
+        }
+
+        self.close(context);
+        std::fs::rename(&tmp_file, dbfile)?;
+        self.set_passphrase(passphrase);
+        ensure!(
+            self.open(context, dbfile, false),
+            "Cannot re-open database after changing passphrase"
+        );
+        info!(context, "Database passphrase changed.");
+        Ok(())
+    }
+
     pub fn close(&self, context: &Context) {
         let _ = self.pool.write().unwrap().take();
         self.in_use.remove();
@@ -138,6 +210,29 @@ impl Sql {
         res
     }
 
+    /// Runs `g` in a single transaction on one connection.
+    ///
+    /// The transaction is committed if `g` returns `Ok`, otherwise it is rolled back.
+    pub(crate) fn with_transaction<G, H>(&self, g: G) -> Result<H>
+    where
+        G: FnOnce(&Connection) -> Result<H>,
+    {
+        self.start_stmt("BEGIN");
+        self.with_conn(|conn| {
+            conn.execute_batch("BEGIN;")?;
+            match g(conn) {
+                Ok(res) => {
+                    conn.execute_batch("COMMIT;")?;
+                    Ok(res)
+                }
+                Err(err) => {
+                    conn.execute_batch("ROLLBACK;").ok();
+                    Err(err)
+                }
+            }
+        })
+    }
+
     pub fn prepare<G, H>(&self, sql: &str, g: G) -> Result<H>
     where
         G: FnOnce(Statement<'_>, &Connection) -> Result<H>,
@@ -405,9 +500,21 @@ fn open(
         open_flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
         open_flags.insert(OpenFlags::SQLITE_OPEN_CREATE);
     }
+    // without SQLCipher, `PRAGMA key` is silently ignored and the database would not be encrypted
+    let passphrase = sql.get_passphrase();
+    ensure!(
+        passphrase.is_none() || cfg!(feature = "sqlcipher"),
+        "Database encryption is not supported, compile with the sqlcipher feature"
+    );
     let mgr = r2d2_sqlite::SqliteConnectionManager::file(dbfile.as_ref())
         .with_flags(open_flags)
-        .with_init(|c| c.execute_batch("PRAGMA secure_delete=on;"));
+        .with_init(move |c| {
+            // the key must be set before any other access to the database
+            if let Some(passphrase) = &passphrase {
+                c.pragma_update(None, "key", passphrase)?;
+            }
+            c.execute_batch("PRAGMA secure_delete=on;")
+        });
     let pool = r2d2::Pool::builder()
         .min_idle(Some(2))
         .max_size(10)
@@ -419,6 +526,19 @@ fn open(
         *sql.pool.write().unwrap() = Some(pool);
     }
 
+    // a wrong passphrase is only detected when the database is read
+    if let Err(err) = sql.query_row("SELECT COUNT(*) FROM sqlite_master;", NO_PARAMS, |row| {
+        row.get::<_, isize>(0)
+    }) {
+        error!(
+            context,
+            "Cannot read database \"{:?}\", wrong passphrase? {}",
+            dbfile.as_ref(),
+            err
+        );
+        return Err(Error::SqlFailedToOpen.into());
+    }
+
     if !readonly {
         let mut exists_before_update = false;
         let mut dbversion_before_update = 0;
@@ -893,6 +1013,88 @@ fn open(
             )?;
             sql.set_raw_config_int(context, "dbversion", 62)?;
         }
+        if dbversion < 63 {
+            info!(context, "[migration] v63");
+            sql.execute(
+                "ALTER TABLE locations ADD COLUMN label TEXT DEFAULT '';",
+                NO_PARAMS,
+            )?;
+            sql.execute(
+                "ALTER TABLE locations ADD COLUMN description TEXT DEFAULT '';",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 63)?;
+        }
+        if dbversion < 64 {
+            info!(context, "[migration] v64");
+            sql.execute(
+                "ALTER TABLE chats_contacts ADD COLUMN is_admin INTEGER DEFAULT 0;",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 64)?;
+        }
+        if dbversion < 65 {
+            info!(context, "[migration] v65");
+            // `invite` links an auth token to the invitenumber of the same invite,
+            // expiry and use count are tracked on the invitenumber.
+            sql.execute(
+                "ALTER TABLE tokens ADD COLUMN invite TEXT DEFAULT '';",
+                NO_PARAMS,
+            )?;
+            sql.execute(
+                "ALTER TABLE tokens ADD COLUMN expires INTEGER DEFAULT 0;",
+                NO_PARAMS,
+            )?;
+            sql.execute(
+                "ALTER TABLE tokens ADD COLUMN max_uses INTEGER DEFAULT 0;",
+                NO_PARAMS,
+            )?;
+            sql.execute(
+                "ALTER TABLE tokens ADD COLUMN uses INTEGER DEFAULT 0;",
+                NO_PARAMS,
+            )?;
+            sql.execute(
+                "UPDATE tokens SET invite=COALESCE((SELECT i.token FROM tokens i WHERE i.namespc=100 AND i.foreign_id=tokens.foreign_id ORDER BY i.id DESC LIMIT 1), '') WHERE namespc=110;",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 65)?;
+        }
+        if dbversion < 66 {
+            info!(context, "[migration] v66");
+            sql.execute(
+                "CREATE TABLE bobstate (id INTEGER PRIMARY KEY, contact_id INTEGER UNIQUE NOT NULL, expects INTEGER DEFAULT 0, fingerprint TEXT DEFAULT '', invitenumber TEXT DEFAULT '', auth TEXT DEFAULT '', grpid TEXT DEFAULT '', timestamp INTEGER DEFAULT 0);",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 66)?;
+        }
+        if dbversion < 67 {
+            info!(context, "[migration] v67");
+            sql.execute(
+                "CREATE TABLE filter_rules (id INTEGER PRIMARY KEY, kind INTEGER NOT NULL, header TEXT DEFAULT '', pattern TEXT NOT NULL, action INTEGER NOT NULL, timestamp INTEGER DEFAULT 0);",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 67)?;
+        }
+        if dbversion < 68 {
+            info!(context, "[migration] v68");
+            sql.execute(
+                "CREATE TABLE contacts_addrs (id INTEGER PRIMARY KEY, contact_id INTEGER NOT NULL, addr TEXT NOT NULL UNIQUE COLLATE NOCASE);",
+                NO_PARAMS,
+            )?;
+            sql.execute(
+                "CREATE INDEX contacts_addrs_index1 ON contacts_addrs (contact_id);",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 68)?;
+        }
+        if dbversion < 69 {
+            info!(context, "[migration] v69");
+            sql.execute(
+                "ALTER TABLE contacts ADD COLUMN last_seen INTEGER DEFAULT 0;",
+                NO_PARAMS,
+            )?;
+            sql.set_raw_config_int(context, "dbversion", 69)?;
+        }
 
         // (2) updates that require high-level objects
         // (the structure is complete now and all objects are usable)
@@ -1053,26 +1255,32 @@ pub fn housekeeping(context: &Context) {
     let mut unreferenced_count = 0;
 
     info!(context, "Start housekeeping...");
+    securejoin::delete_expired_bobstates(context);
+
     maybe_add_from_param(
         context,
+        &context.sql,
         &mut files_in_use,
         "SELECT param FROM msgs  WHERE chat_id!=3   AND type!=10;",
         Param::File,
     );
     maybe_add_from_param(
         context,
+        &context.sql,
         &mut files_in_use,
         "SELECT param FROM jobs;",
         Param::File,
     );
     maybe_add_from_param(
         context,
+        &context.sql,
         &mut files_in_use,
         "SELECT param FROM chats;",
         Param::ProfileImage,
     );
     maybe_add_from_param(
         context,
+        &context.sql,
         &mut files_in_use,
         "SELECT param FROM contacts;",
         Param::ProfileImage,
@@ -1185,31 +1393,31 @@ fn maybe_add_file(files_in_use: &mut HashSet<String>, file: impl AsRef<str>) {
     files_in_use.insert(file.as_ref()[9..].into());
 }
 
-fn maybe_add_from_param(
+/// Adds the blob files referenced by `param_id` in the params returned by `query`.
+pub(crate) fn maybe_add_from_param(
     context: &Context,
+    sql: &Sql,
     files_in_use: &mut HashSet<String>,
     query: &str,
     param_id: Param,
 ) {
-    context
-        .sql
-        .query_map(
-            query,
-            NO_PARAMS,
-            |row| row.get::<_, String>(0),
-            |rows| {
-                for row in rows {
-                    let param: Params = row?.parse().unwrap_or_default();
-                    if let Some(file) = param.get(param_id) {
-                        maybe_add_file(files_in_use, file);
-                    }
+    sql.query_map(
+        query,
+        NO_PARAMS,
+        |row| row.get::<_, String>(0),
+        |rows| {
+            for row in rows {
+                let param: Params = row?.parse().unwrap_or_default();
+                if let Some(file) = param.get(param_id) {
+                    maybe_add_file(files_in_use, file);
                 }
-                Ok(())
-            },
-        )
-        .unwrap_or_else(|err| {
-            warn!(context, "sql: failed to add_from_param: {}", err);
-        });
+            }
+            Ok(())
+        },
+    )
+    .unwrap_or_else(|err| {
+        warn!(context, "sql: failed to add_from_param: {}", err);
+    });
 }
 
 #[cfg(test)]
@@ -188,6 +188,39 @@ pub enum StockMessage {
 
     #[strum(props(fallback = "Unknown Sender for this chat. See 'info' for more details."))]
     UnknownSenderForChat = 72,
+
+    #[strum(props(fallback = "%1$s promoted to group admin."))]
+    MsgGrpAdminAdded = 73,
+
+    #[strum(props(fallback = "%1$s is no longer group admin."))]
+    MsgGrpAdminRemoved = 74,
+
+    #[strum(props(fallback = "Group description changed."))]
+    MsgGrpDescriptionChanged = 75,
+
+    #[strum(props(
+        fallback = "The invitation of %1$s has expired or was revoked. Ask for a new invitation."
+    ))]
+    SecurejoinInviteInvalid = 76,
+
+    #[strum(props(fallback = "Changed setup for %1$s, the chat is no longer verified."))]
+    ChatVerificationLost = 77,
+
+    #[strum(props(fallback = "Last seen: %1$s"))]
+    LastSeen = 78,
+
+    #[strum(props(
+        fallback = "Your key was replaced, your contacts learn the new key with your next messages. New fingerprint:\n%1$s"
+    ))]
+    SelfKeyChanged = 79,
+
+    #[strum(props(
+        fallback = "Message not sent: Encryption is required, but no key is known for %1$s."
+    ))]
+    EncryptionRequiredNoKey = 80,
+
+    #[strum(props(fallback = "Safety number:\n%1$s"))]
+    SafetyNumber = 81,
 }
 
 /*
@@ -313,7 +346,11 @@ impl Context {
         param2: impl AsRef<str>,
         from_id: u32,
     ) -> String {
-        let insert1 = if id == StockMessage::MsgAddMember || id == StockMessage::MsgDelMember {
+        let insert1 = if id == StockMessage::MsgAddMember
+            || id == StockMessage::MsgDelMember
+            || id == StockMessage::MsgGrpAdminAdded
+            || id == StockMessage::MsgGrpAdminRemoved
+        {
             let contact_id = Contact::lookup_id_by_addr(self, param1.as_ref());
             if contact_id != 0 {
                 Contact::get_by_id(self, contact_id)
@@ -101,3 +101,15 @@ pub(crate) fn bob_keypair() -> key::KeyPair {
         secret,
     }
 }
+
+/// Creates Bob with a pre-generated keypair.
+///
+/// Returns the address of the keypair created (bob@example.net).
+pub(crate) fn configure_bob_keypair(ctx: &Context) -> String {
+    let keypair = bob_keypair();
+    ctx.set_config(Config::ConfiguredAddr, Some(&keypair.addr.to_string()))
+        .unwrap();
+    key::store_self_keypair(&ctx, &keypair, key::KeyPairUse::Default)
+        .expect("Failed to save Bob's key");
+    keypair.addr.to_string()
+}
@@ -3,12 +3,16 @@
 //! Functions to read/write token from/to the database. A token is any string associated with a key.
 //!
 //! Tokens are used in countermitm verification protocols.
+//! An invitenumber and an auth token form an invite,
+//! which may expire or be limited to a number of joins.
 
 use deltachat_derive::*;
+use serde::{Deserialize, Serialize};
 
 use crate::chat::ChatId;
 use crate::context::Context;
 use crate::dc_tools::*;
+use crate::error::Error;
 use crate::sql;
 
 /// Token namespace
@@ -26,39 +30,302 @@ impl Default for Namespace {
     }
 }
 
-/// Creates a new token and saves it into the database.
-/// Returns created token.
-pub fn save(context: &Context, namespace: Namespace, foreign_id: ChatId) -> String {
-    // foreign_id may be 0
-    let token = dc_create_id();
+/// A secure-join invite, consisting of an invitenumber and an auth token.
+///
+/// The invitenumber starts the handshake, the auth token verifies the fingerprint;
+/// both are written to the QR code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invite {
+    /// The group the invite is for, unset for Setup-Contact invites.
+    pub chat_id: ChatId,
+    pub invitenumber: String,
+    pub auth: String,
+    /// Creation time.
+    pub timestamp: i64,
+    /// Time after which the invite cannot be used anymore, 0 for never.
+    pub expires: i64,
+    /// Number of joins allowed with the invite, 0 for unlimited.
+    pub max_uses: u32,
+    /// Number of joins done with the invite so far.
+    pub uses: u32,
+}
+
+impl Invite {
+    /// Returns true if the invite can still be used for joining.
+    pub fn is_active(&self) -> bool {
+        (self.expires == 0 || self.expires > time())
+            && (self.max_uses == 0 || self.uses < self.max_uses)
+    }
+}
+
+/// State of the invite a received token belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InviteState {
+    /// The token is not known at all.
+    Unknown,
+    /// The token belongs to an invite that can still be used.
+    Active,
+    /// The invite has expired, was revoked or all allowed joins are done.
+    Inactive,
+}
+
+/// Creates a new invite for a chat.
+///
+/// `expires` is a timestamp after which the invite cannot be used anymore,
+/// `max_uses` limits the number of joins; pass 0 for no limits.
+pub fn create_invite(context: &Context, chat_id: ChatId, expires: i64, max_uses: u32) -> Invite {
+    let invite = Invite {
+        chat_id,
+        invitenumber: dc_create_id(),
+        auth: dc_create_id(),
+        timestamp: time(),
+        expires,
+        max_uses,
+        uses: 0,
+    };
     sql::execute(
         context,
         &context.sql,
-        "INSERT INTO tokens (namespc, foreign_id, token, timestamp) VALUES (?, ?, ?, ?);",
-        params![namespace, foreign_id, &token, time()],
+        "INSERT INTO tokens (namespc, foreign_id, token, timestamp, expires, max_uses) VALUES (?, ?, ?, ?, ?, ?);",
+        params![
+            Namespace::InviteNumber,
+            chat_id,
+            &invite.invitenumber,
+            invite.timestamp,
+            expires,
+            max_uses
+        ],
     )
     .ok();
-    token
-}
-
-pub fn lookup(context: &Context, namespace: Namespace, foreign_id: ChatId) -> Option<String> {
-    context.sql.query_get_value::<_, String>(
+    sql::execute(
         context,
-        "SELECT token FROM tokens WHERE namespc=? AND foreign_id=?;",
-        params![namespace, foreign_id],
+        &context.sql,
+        "INSERT INTO tokens (namespc, foreign_id, token, timestamp, invite) VALUES (?, ?, ?, ?, ?);",
+        params![
+            Namespace::Auth,
+            chat_id,
+            &invite.auth,
+            invite.timestamp,
+            &invite.invitenumber
+        ],
     )
+    .ok();
+    invite
 }
 
-pub fn lookup_or_new(context: &Context, namespace: Namespace, foreign_id: ChatId) -> String {
-    lookup(context, namespace, foreign_id).unwrap_or_else(|| save(context, namespace, foreign_id))
-}
-
-pub fn exists(context: &Context, namespace: Namespace, token: &str) -> bool {
+/// Returns the active invites of a chat, the newest first.
+///
+/// For Setup-Contact invites, pass an unset chat ID.
+pub fn get_invites(context: &Context, chat_id: ChatId) -> Vec<Invite> {
     context
         .sql
-        .exists(
-            "SELECT id FROM tokens WHERE namespc=? AND token=?;",
-            params![namespace, token],
+        .query_map(
+            "SELECT i.token, a.token, i.timestamp, i.expires, i.max_uses, i.uses \
+             FROM tokens i INNER JOIN tokens a ON a.namespc=? AND a.invite=i.token \
+             WHERE i.namespc=? AND i.foreign_id=? \
+             ORDER BY i.id DESC;",
+            params![Namespace::Auth, Namespace::InviteNumber, chat_id],
+            |row| {
+                Ok(Invite {
+                    chat_id,
+                    invitenumber: row.get(0)?,
+                    auth: row.get(1)?,
+                    timestamp: row.get(2)?,
+                    expires: row.get(3)?,
+                    max_uses: row.get(4)?,
+                    uses: row.get(5)?,
+                })
+            },
+            |invites| invites.collect::<Result<Vec<_>, _>>().map_err(Into::into),
         )
         .unwrap_or_default()
+        .into_iter()
+        .filter(Invite::is_active)
+        .collect()
+}
+
+/// Returns the newest active invite of a chat without expiry and use limit,
+/// a new one is created if there is none.
+///
+/// This is the invite offered by the default QR code.
+pub fn lookup_or_new_invite(context: &Context, chat_id: ChatId) -> Invite {
+    get_invites(context, chat_id)
+        .into_iter()
+        .find(|invite| invite.expires == 0 && invite.max_uses == 0)
+        .unwrap_or_else(|| create_invite(context, chat_id, 0, 0))
+}
+
+/// Revokes the invite with the given invitenumber.
+///
+/// The invite is marked as expired, so joiners still using it get
+/// an error message instead of no response at all.
+pub fn revoke_invite(context: &Context, invitenumber: &str) -> Result<(), Error> {
+    let now = time();
+    let updated = context.sql.execute(
+        "UPDATE tokens SET expires=? WHERE namespc=? AND token=? AND (expires=0 OR expires>?);",
+        params![now, Namespace::InviteNumber, invitenumber, now],
+    )?;
+    ensure!(updated > 0, "No active invite {}", invitenumber);
+    Ok(())
+}
+
+/// Revokes all invites of a chat.
+///
+/// The next QR code requested for the chat offers a new invite.
+pub fn regenerate_invites(context: &Context, chat_id: ChatId) -> Result<(), Error> {
+    let now = time();
+    context.sql.execute(
+        "UPDATE tokens SET expires=? WHERE namespc=? AND foreign_id=? AND (expires=0 OR expires>?);",
+        params![now, Namespace::InviteNumber, chat_id, now],
+    )?;
+    Ok(())
+}
+
+/// Checks the state of the invite a received invitenumber or auth token belongs to.
+///
+/// Auth tokens created before invites could expire are not linked to an invite
+/// and are always active.
+pub fn get_invite_state(context: &Context, namespace: Namespace, token: &str) -> InviteState {
+    fn get_limits(row: &rusqlite::Row) -> rusqlite::Result<(i64, u32, u32)> {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    }
+
+    let limits = match namespace {
+        Namespace::InviteNumber => context.sql.query_row(
+            "SELECT expires, max_uses, uses FROM tokens WHERE namespc=? AND token=?;",
+            params![namespace, token],
+            get_limits,
+        ),
+        Namespace::Auth => context.sql.query_row(
+            "SELECT COALESCE(i.expires, 0), COALESCE(i.max_uses, 0), COALESCE(i.uses, 0) \
+             FROM tokens a LEFT JOIN tokens i ON i.namespc=? AND i.token=a.invite \
+             WHERE a.namespc=? AND a.token=?;",
+            params![Namespace::InviteNumber, namespace, token],
+            get_limits,
+        ),
+        Namespace::Unknown => return InviteState::Unknown,
+    };
+    match limits {
+        Ok((expires, max_uses, uses)) => {
+            if (expires == 0 || expires > time()) && (max_uses == 0 || uses < max_uses) {
+                InviteState::Active
+            } else {
+                InviteState::Inactive
+            }
+        }
+        Err(_) => InviteState::Unknown,
+    }
+}
+
+/// Counts a join with the invite the given auth token belongs to.
+///
+/// Fails if the invite cannot be used anymore.
+/// The check and the count are done in one statement,
+/// so joins handled at the same time cannot exceed the limits of the invite.
+/// Auth tokens created before invites could expire are not linked to an invite
+/// and are not counted.
+pub fn count_invite_use(context: &Context, auth: &str) -> Result<(), Error> {
+    let invite: String = context
+        .sql
+        .query_get_value(
+            context,
+            "SELECT invite FROM tokens WHERE namespc=? AND token=?;",
+            params![Namespace::Auth, auth],
+        )
+        .ok_or_else(|| format_err!("Unknown auth token"))?;
+    if invite.is_empty() {
+        return Ok(());
+    }
+    let now = time();
+    let updated = context.sql.execute(
+        "UPDATE tokens SET uses=uses+1 \
+         WHERE namespc=? AND token=? AND (max_uses=0 OR uses<max_uses) AND (expires=0 OR expires>?);",
+        params![Namespace::InviteNumber, &invite, now],
+    )?;
+    ensure!(updated > 0, "Invite {} cannot be used anymore", invite);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_invite_limits() {
+        let t = dummy_context();
+        let chat_id = ChatId::new(0);
+
+        let invite = lookup_or_new_invite(&t.ctx, chat_id);
+        assert_eq!(lookup_or_new_invite(&t.ctx, chat_id), invite);
+        let limited = create_invite(&t.ctx, chat_id, time() + 3600, 1);
+        assert_eq!(
+            get_invites(&t.ctx, chat_id),
+            vec![limited.clone(), invite.clone()]
+        );
+        assert_eq!(lookup_or_new_invite(&t.ctx, chat_id), invite);
+
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::InviteNumber, &limited.invitenumber),
+            InviteState::Active
+        );
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::Auth, &limited.auth),
+            InviteState::Active
+        );
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::Auth, &limited.invitenumber),
+            InviteState::Unknown
+        );
+
+        // the limited invite can be used only once
+        count_invite_use(&t.ctx, &limited.auth).unwrap();
+        assert!(count_invite_use(&t.ctx, &limited.auth).is_err());
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::InviteNumber, &limited.invitenumber),
+            InviteState::Inactive
+        );
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::Auth, &limited.auth),
+            InviteState::Inactive
+        );
+        count_invite_use(&t.ctx, &invite.auth).unwrap();
+        assert_eq!(get_invites(&t.ctx, chat_id)[0].uses, 1);
+
+        let expired = create_invite(&t.ctx, chat_id, time() - 1, 0);
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::Auth, &expired.auth),
+            InviteState::Inactive
+        );
+        assert_eq!(get_invites(&t.ctx, chat_id).len(), 1);
+    }
+
+    #[test]
+    fn test_revoke_invites() {
+        let t = dummy_context();
+        let chat_id = ChatId::new(0);
+
+        let invite = lookup_or_new_invite(&t.ctx, chat_id);
+        revoke_invite(&t.ctx, &invite.invitenumber).unwrap();
+        assert!(revoke_invite(&t.ctx, &invite.invitenumber).is_err());
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::InviteNumber, &invite.invitenumber),
+            InviteState::Inactive
+        );
+        assert!(get_invites(&t.ctx, chat_id).is_empty());
+
+        let new_invite = lookup_or_new_invite(&t.ctx, chat_id);
+        assert_ne!(new_invite.invitenumber, invite.invitenumber);
+        create_invite(&t.ctx, chat_id, 0, 5);
+        assert_eq!(get_invites(&t.ctx, chat_id).len(), 2);
+
+        regenerate_invites(&t.ctx, chat_id).unwrap();
+        assert!(get_invites(&t.ctx, chat_id).is_empty());
+        assert_eq!(
+            get_invite_state(&t.ctx, Namespace::Auth, &new_invite.auth),
+            InviteState::Inactive
+        );
+        assert_ne!(lookup_or_new_invite(&t.ctx, chat_id), new_invite);
+    }
 }